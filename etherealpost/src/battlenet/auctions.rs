@@ -1,4 +1,7 @@
-use serde::Deserialize;
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::io::Read;
 
 /// A raw Auctions resource which is returned by Blizzard's
 /// Auction House API for single Connected Realm, and consisting of [`Auction`]s.
@@ -49,6 +52,109 @@ impl AuctionFile {
     pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
         serde_json::from_str(s)
     }
+
+    /// Deserialize an instance of [`AuctionFile`] from a reader which produces JSON,
+    /// such as an HTTP response body or a file on disk.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, serde_json::Error> {
+        serde_json::from_reader(reader)
+    }
+
+    /// Streams the auctions of an auction file from a reader which produces JSON,
+    /// calling `f` with each [`Auction`] as soon as it has been parsed.
+    ///
+    /// Unlike [from_reader](`Self::from_reader`), the auctions are never collected
+    /// into a `Vec`. Each one is handed to `f` by value and dropped afterwards unless
+    /// `f` keeps it, so memory usage does not grow with the size of the file.
+    ///
+    /// The reader is read in many small pieces, so for files and sockets it should be
+    /// wrapped in a [BufReader](`std::io::BufReader`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use etherealpost::auctions::AuctionFile;
+    ///
+    /// let json = r#"{"auctions": [
+    ///     {"id": 1, "quantity": 1, "item": {"id": 72092}, "unit_price": 100, "time_left": "LONG"},
+    ///     {"id": 2, "quantity": 5, "item": {"id": 72092}, "unit_price": 120, "time_left": "SHORT"}
+    /// ]}"#;
+    ///
+    /// let mut total_qty = 0;
+    /// AuctionFile::for_each_auction(json.as_bytes(), |auction| {
+    ///     total_qty += auction.quantity;
+    /// })
+    /// .unwrap();
+    /// assert_eq!(6, total_qty);
+    /// ```
+    pub fn for_each_auction<R, F>(reader: R, f: F) -> Result<(), serde_json::Error>
+    where
+        R: Read,
+        F: FnMut(Auction),
+    {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        deserializer.deserialize_map(AuctionFileVisitor { f })?;
+        deserializer.end()
+    }
+}
+
+/// Visits the top level object of an auction file, streaming the `auctions` array
+/// and skipping over every other field.
+struct AuctionFileVisitor<F> {
+    f: F,
+}
+
+impl<'de, F: FnMut(Auction)> Visitor<'de> for AuctionFileVisitor<F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an auction file object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<(), A::Error> {
+        let mut found_auctions = false;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "auctions" {
+                map.next_value_seed(AuctionsSeed { f: &mut self.f })?;
+                found_auctions = true;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        if found_auctions {
+            Ok(())
+        } else {
+            Err(de::Error::missing_field("auctions"))
+        }
+    }
+}
+
+/// Streams each element of the `auctions` array into a callback.
+struct AuctionsSeed<'f, F> {
+    f: &'f mut F,
+}
+
+impl<'de, F: FnMut(Auction)> DeserializeSeed<'de> for AuctionsSeed<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F: FnMut(Auction)> Visitor<'de> for AuctionsSeed<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of auctions")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(auction) = seq.next_element::<Auction>()? {
+            (self.f)(auction);
+        }
+        Ok(())
+    }
 }
 
 /// A single Auction     that is currently on the Auction House.
@@ -81,12 +187,13 @@ pub struct Auction {
     /// If Unit Price is present, `buyout` and `bid` _will not_ be present.
     pub unit_price: Option<u64>,
 
-    /// The price to buyout the auction.
+    /// The price to buyout the auction, for the whole stack. See
+    /// [per_unit_price](`Auction::per_unit_price`) for the price of a single unit.
     ///
     /// If Buyout is present, `unit_price` _will not_ be present. `bid` _may or may not_ be present.
     pub buyout: Option<u64>,
 
-    /// The current bid price for the auction.
+    /// The current bid price for the auction, for the whole stack.
     ///
     /// If Bid is present, `unit_price` _will not_ be present and `buyout` _may or may not_
     /// be present.
@@ -96,6 +203,61 @@ pub struct Auction {
     pub time_left: TimeLeft,
 }
 
+/// Which of the prices of an [`Auction`] to use.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PriceKind {
+    /// The price to buy the auction right away, which is the `unit_price` for
+    /// commodities and the `buyout` for everything else.
+    Buyout,
+
+    /// The current bid on the auction.
+    Bid,
+}
+
+impl Auction {
+    /// The price of a single unit of the auction, or `None` if the auction doesn't
+    /// have that kind of price.
+    ///
+    /// The `unit_price` of a commodity is already per unit, but the `buyout` and `bid`
+    /// are the price of the whole stack, so they are divided by the `quantity` and
+    /// rounded to the nearest copper.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use etherealpost::auctions::{AuctionFile, PriceKind};
+    ///
+    /// let auction_file = AuctionFile::from_json(r#"{"auctions": [
+    ///     {"id": 1, "quantity": 20, "item": {"id": 2589}, "bid": 1500, "buyout": 2000, "time_left": "LONG"},
+    ///     {"id": 2, "quantity": 200, "item": {"id": 171276}, "unit_price": 1150000, "time_left": "LONG"}
+    /// ]}"#).unwrap();
+    ///
+    /// let stack = &auction_file.auctions[0];
+    /// assert_eq!(Some(100), stack.per_unit_price(PriceKind::Buyout));
+    /// assert_eq!(Some(75), stack.per_unit_price(PriceKind::Bid));
+    ///
+    /// let commodity = &auction_file.auctions[1];
+    /// assert_eq!(Some(1150000), commodity.per_unit_price(PriceKind::Buyout));
+    /// assert_eq!(None, commodity.per_unit_price(PriceKind::Bid));
+    /// ```
+    pub fn per_unit_price(&self, kind: PriceKind) -> Option<u64> {
+        let stack_price = match kind {
+            PriceKind::Buyout => {
+                if let Some(unit_price) = self.unit_price {
+                    return Some(unit_price);
+                }
+                self.buyout?
+            }
+            PriceKind::Bid => self.bid?,
+        };
+
+        // rounding could overflow a u64 for the largest prices, and the rounded price
+        // is never more than the stack price, so it always fits back into one
+        let quantity = u128::from(self.quantity.max(1));
+        Some(((u128::from(stack_price) + quantity / 2) / quantity) as u64)
+    }
+}
+
 /// An item which is up for auction on an [`Auction`].
 #[derive(Deserialize)]
 pub struct Item {
@@ -202,7 +364,7 @@ pub struct ItemModifier {
 }
 
 /// The amount of time left on an [`Auction`].
-#[derive(Deserialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
 pub enum TimeLeft {
     /// Parses from `VERY_LONG` and means more than **12 hours** remaining.
     #[serde(rename = "VERY_LONG")]
@@ -221,9 +383,98 @@ pub enum TimeLeft {
     Short,
 }
 
+impl TimeLeft {
+    /// The least amount of time, in seconds, that an auction in this bucket
+    /// has remaining before it expires.
+    pub fn min_remaining_secs(&self) -> u64 {
+        match self {
+            TimeLeft::VeryLong => 12 * 60 * 60,
+            TimeLeft::Long => 2 * 60 * 60,
+            TimeLeft::Medium => 30 * 60,
+            TimeLeft::Short => 0,
+        }
+    }
+
+    /// The most amount of time, in seconds, that an auction in this bucket
+    /// can have remaining before it expires.
+    ///
+    /// Auctions can be posted for at most 48 hours, which bounds `VeryLong`.
+    pub fn max_remaining_secs(&self) -> u64 {
+        match self {
+            TimeLeft::VeryLong => 48 * 60 * 60,
+            TimeLeft::Long => 12 * 60 * 60,
+            TimeLeft::Medium => 2 * 60 * 60,
+            TimeLeft::Short => 30 * 60,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn for_each_auction_streams_every_auction() {
+        let json = r#"{
+            "_links": {"self": {"href": "https://us.api.blizzard.com/"}},
+            "connected_realm": {"href": "https://us.api.blizzard.com/"},
+            "auctions": [
+                {"id": 1, "quantity": 1, "item": {"id": 5}, "buyout": 100, "time_left": "LONG"},
+                {"id": 2, "quantity": 3, "item": {"id": 6}, "unit_price": 7, "time_left": "SHORT"}
+            ],
+            "commodities": {"href": "https://us.api.blizzard.com/"}
+        }"#;
+
+        let mut ids = Vec::new();
+        AuctionFile::for_each_auction(json.as_bytes(), |auction| ids.push(auction.id)).unwrap();
+        assert_eq!(vec![1, 2], ids);
+    }
+
+    #[test]
+    fn per_unit_price_divides_stack_prices() {
+        let json = r#"{"auctions": [
+            {"id": 1, "quantity": 3, "item": {"id": 5}, "bid": 10, "buyout": 100, "time_left": "LONG"},
+            {"id": 2, "quantity": 1, "item": {"id": 5}, "bid": 7, "time_left": "LONG"},
+            {"id": 3, "quantity": 0, "item": {"id": 5}, "buyout": 50, "time_left": "LONG"},
+            {"id": 4, "quantity": 3, "item": {"id": 6}, "unit_price": 7, "time_left": "SHORT"}
+        ]}"#;
+        let auctions = AuctionFile::from_json(json).unwrap().auctions;
+
+        // 100 / 3 and 10 / 3, rounded to the nearest copper
+        assert_eq!(Some(33), auctions[0].per_unit_price(PriceKind::Buyout));
+        assert_eq!(Some(3), auctions[0].per_unit_price(PriceKind::Bid));
+        assert_eq!(None, auctions[1].per_unit_price(PriceKind::Buyout));
+        assert_eq!(Some(7), auctions[1].per_unit_price(PriceKind::Bid));
+        assert_eq!(Some(50), auctions[2].per_unit_price(PriceKind::Buyout));
+        assert_eq!(Some(7), auctions[3].per_unit_price(PriceKind::Buyout));
+        assert_eq!(None, auctions[3].per_unit_price(PriceKind::Bid));
+    }
+
+    #[test]
+    fn per_unit_price_rounds_the_largest_prices() {
+        let json = r#"{"auctions": [{"id": 1, "quantity": 2, "item": {"id": 5},
+            "buyout": 18446744073709551615, "time_left": "LONG"}]}"#;
+        let auctions = AuctionFile::from_json(json).unwrap().auctions;
+
+        assert_eq!(
+            Some(u64::MAX / 2 + 1),
+            auctions[0].per_unit_price(PriceKind::Buyout)
+        );
+    }
+
+    #[test]
+    fn for_each_auction_requires_auctions_field() {
+        let res = AuctionFile::for_each_auction(r#"{"other": []}"#.as_bytes(), |_| {});
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn for_each_auction_rejects_malformed_auction() {
+        let json = r#"{"auctions": [{"id": 1, "item": {"id": 5}, "time_left": "LONG"}]}"#;
+        let res = AuctionFile::for_each_auction(json.as_bytes(), |_| {});
+        assert!(res.is_err());
+    }
+
     #[test]
     fn item_with_all_pet_fields_returns_pet() {
         let item = Item {
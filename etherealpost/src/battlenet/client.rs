@@ -0,0 +1,760 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::auctions::{Auction, AuctionFile};
+use crate::battlenet::commodities::CommodityFile;
+use crate::battlenet::last_modified::LastModifiedStore;
+use crate::battlenet::realms::{ConnectedRealm, ConnectedRealmIndex, Realm, RealmIndex};
+
+/// Tokens are refreshed this long before Blizzard says they expire so that
+/// a request is never sent with a token that lapses while it is in flight.
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+const DEFAULT_LOCALE: &str = "en_US";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Auction files can take a while to download, so rather than limiting the whole
+/// request, a request only fails once the connection stalls for this long.
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A Battle.net API region.
+///
+/// Each region has its own API host, and the data returned by the
+/// `dynamic` and `static` namespaces is scoped to that region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Region {
+    /// North America, Oceania and Latin America.
+    Us,
+    /// Europe and Russia.
+    Eu,
+    /// Korea.
+    Kr,
+    /// Taiwan.
+    Tw,
+    /// China.
+    Cn,
+}
+
+impl Region {
+    /// The short, lowercase name of the region (eg, `us`) as used in
+    /// hostnames and namespaces.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Region::Us => "us",
+            Region::Eu => "eu",
+            Region::Kr => "kr",
+            Region::Tw => "tw",
+            Region::Cn => "cn",
+        }
+    }
+
+    /// The default base URL of the Game Data API for this region.
+    pub fn api_url(&self) -> String {
+        match self {
+            Region::Cn => "https://gateway.battlenet.com.cn".to_string(),
+            _ => format!("https://{}.api.blizzard.com", self.as_str()),
+        }
+    }
+
+    /// The default URL of the OAuth token endpoint for this region.
+    pub fn oauth_url(&self) -> String {
+        match self {
+            Region::Cn => "https://oauth.battlenet.com.cn/token".to_string(),
+            _ => "https://oauth.battle.net/token".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Region {
+    type Err = ClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "us" => Ok(Region::Us),
+            "eu" => Ok(Region::Eu),
+            "kr" => Ok(Region::Kr),
+            "tw" => Ok(Region::Tw),
+            "cn" => Ok(Region::Cn),
+            _ => Err(ClientError::UnknownRegion(s.to_string())),
+        }
+    }
+}
+
+/// The Game Data API namespace a request is made against.
+///
+/// Auction data lives in the `dynamic` namespace, while data which only
+/// changes with game patches (items, pet species, etc.) lives in `static`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Namespace {
+    /// Data which changes frequently, such as auctions and realm status.
+    Dynamic,
+    /// Data which only changes with game patches.
+    Static,
+}
+
+impl Namespace {
+    /// The full namespace header value for a region, eg `dynamic-us`.
+    pub fn for_region(&self, region: Region) -> String {
+        let prefix = match self {
+            Namespace::Dynamic => "dynamic",
+            Namespace::Static => "static",
+        };
+        format!("{}-{}", prefix, region)
+    }
+}
+
+/// Errors which can occur while talking to the Battle.net API.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request could not be made or the response could not be read,
+    /// for example due to a connection failure or a timeout.
+    Transport(Box<ureq::Transport>),
+
+    /// The API responded with a non-successful HTTP status code.
+    Status {
+        /// The HTTP status code.
+        status: u16,
+        /// The URL which was requested.
+        url: String,
+    },
+
+    /// The response body could not be deserialized.
+    Json(serde_json::Error),
+
+    /// A region name could not be parsed into a [`Region`].
+    UnknownRegion(String),
+
+    /// A [`LastModifiedStore`] could not be written to disk.
+    Io(io::Error),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Transport(e) => write!(f, "transport error: {}", e),
+            ClientError::Status { status, url } => {
+                write!(f, "unexpected HTTP status {} from {}", status, url)
+            }
+            ClientError::Json(e) => write!(f, "unable to deserialize response: {}", e),
+            ClientError::UnknownRegion(region) => write!(f, "unknown region '{}'", region),
+            ClientError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Transport(e) => Some(e.as_ref()),
+            ClientError::Json(e) => Some(e),
+            ClientError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ureq::Error> for ClientError {
+    fn from(e: ureq::Error) -> Self {
+        match e {
+            ureq::Error::Status(status, response) => ClientError::Status {
+                status,
+                url: response.get_url().to_string(),
+            },
+            ureq::Error::Transport(transport) => ClientError::Transport(Box::new(transport)),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(e: serde_json::Error) -> Self {
+        ClientError::Json(e)
+    }
+}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        ClientError::Io(e)
+    }
+}
+
+/// The result of a conditional fetch.
+pub enum Fetched<T> {
+    /// The resource has changed since it was last fetched, and this is the new version.
+    Modified(T),
+
+    /// The resource has not changed since it was last fetched.
+    NotModified,
+}
+
+/// Response body of the OAuth client credentials flow.
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// A cached OAuth access token.
+struct AccessToken {
+    token: String,
+    expires_at: Instant,
+}
+
+impl AccessToken {
+    fn is_fresh(&self) -> bool {
+        Instant::now() + TOKEN_EXPIRY_MARGIN < self.expires_at
+    }
+}
+
+/// A blocking client for Blizzard's World of Warcraft Game Data APIs.
+///
+/// The client takes care of the OAuth client credentials flow: an access token
+/// is requested on first use, cached, and transparently refreshed when it is
+/// about to expire or when the API rejects it.
+///
+/// The client is `Sync`, so a single instance can be shared between threads
+/// which fetch auctions for different connected realms.
+///
+/// # Example
+///
+/// ```no_run
+/// use etherealpost::battlenet::client::{BattleNetClient, Region};
+///
+/// let client = BattleNetClient::new("client-id", "client-secret", Region::Us)
+///     .with_locale("en_GB");
+/// let auction_file = client.auctions(3676).unwrap();
+/// println!("{} auctions on Area 52", auction_file.auctions.len());
+/// ```
+pub struct BattleNetClient {
+    client_id: String,
+    client_secret: String,
+    region: Region,
+    locale: String,
+    base_url: String,
+    oauth_url: String,
+    agent: ureq::Agent,
+    token: Mutex<Option<AccessToken>>,
+}
+
+impl BattleNetClient {
+    /// Creates a new client for a `region` using the OAuth credentials of a
+    /// client registered on the [Battle.net developer portal](https://develop.battle.net/).
+    ///
+    /// The client uses the region's public API and OAuth hosts and the `en_US` locale.
+    /// Requests time out if connecting takes longer than 10 seconds, or if no data is
+    /// received for 60 seconds.
+    pub fn new(client_id: &str, client_secret: &str, region: Region) -> BattleNetClient {
+        BattleNetClient {
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            region,
+            locale: DEFAULT_LOCALE.to_string(),
+            base_url: region.api_url(),
+            oauth_url: region.oauth_url(),
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(CONNECT_TIMEOUT)
+                .timeout_read(READ_TIMEOUT)
+                .build(),
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Sets the locale which localized fields (names, descriptions) are returned in.
+    pub fn with_locale(mut self, locale: &str) -> BattleNetClient {
+        self.locale = locale.to_string();
+        self
+    }
+
+    /// Overrides the base URL of the Game Data API, for example to point the
+    /// client at a proxy or a local mock server.
+    pub fn with_base_url(mut self, base_url: &str) -> BattleNetClient {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Overrides the URL of the OAuth token endpoint.
+    pub fn with_oauth_url(mut self, oauth_url: &str) -> BattleNetClient {
+        self.oauth_url = oauth_url.to_string();
+        self
+    }
+
+    /// The region this client makes requests against.
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    /// The locale this client requests localized data in.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Fetches the current auctions for a Connected Realm.
+    ///
+    /// # Arguments
+    ///
+    /// * `connected_realm_id` - The ID of the connected realm whose Auction House
+    ///   should be fetched.
+    pub fn auctions(&self, connected_realm_id: u32) -> Result<AuctionFile, ClientError> {
+        let path = format!("/data/wow/connected-realm/{}/auctions", connected_realm_id);
+        let response = self.get(&path, Namespace::Dynamic, None)?;
+        Ok(AuctionFile::from_reader(response.into_reader())?)
+    }
+
+    /// Streams the current auctions for a Connected Realm into `f` one at a time,
+    /// without ever holding the whole snapshot in memory.
+    ///
+    /// See [AuctionFile::for_each_auction](`crate::auctions::AuctionFile::for_each_auction`).
+    pub fn for_each_auction<F>(&self, connected_realm_id: u32, f: F) -> Result<(), ClientError>
+    where
+        F: FnMut(Auction),
+    {
+        let path = format!("/data/wow/connected-realm/{}/auctions", connected_realm_id);
+        let response = self.get(&path, Namespace::Dynamic, None)?;
+        let reader = io::BufReader::new(response.into_reader());
+        Ok(AuctionFile::for_each_auction(reader, f)?)
+    }
+
+    /// Fetches the current auctions for a Connected Realm, unless they haven't
+    /// changed since they were last fetched.
+    ///
+    /// The `Last-Modified` timestamp recorded in `store` for the connected realm is
+    /// sent as `If-Modified-Since`. If the API reports that the snapshot hasn't
+    /// changed, [`Fetched::NotModified`] is returned without downloading it again.
+    /// Otherwise, the new snapshot is returned and its timestamp is recorded in
+    /// `store` for next time.
+    ///
+    /// # Arguments
+    ///
+    /// * `connected_realm_id` - The ID of the connected realm whose Auction House
+    ///   should be fetched.
+    /// * `store` - The record of previously fetched snapshots.
+    pub fn auctions_if_modified(
+        &self,
+        connected_realm_id: u32,
+        store: &mut LastModifiedStore,
+    ) -> Result<Fetched<AuctionFile>, ClientError> {
+        let path = format!("/data/wow/connected-realm/{}/auctions", connected_realm_id);
+        let response = self.get(&path, Namespace::Dynamic, store.get(connected_realm_id))?;
+
+        if response.status() == 304 {
+            return Ok(Fetched::NotModified);
+        }
+
+        let last_modified = response.header("Last-Modified").map(|s| s.to_string());
+        let auction_file = AuctionFile::from_reader(response.into_reader())?;
+
+        // only record the timestamp once the snapshot has been read successfully,
+        // otherwise a failed download would be skipped on the next attempt
+        if let Some(last_modified) = last_modified {
+            store.record(connected_realm_id, &last_modified)?;
+        }
+
+        Ok(Fetched::Modified(auction_file))
+    }
+
+    /// Fetches the current auctions on the region-wide commodities market.
+    ///
+    /// Commodities are shared by every connected realm in the client's region,
+    /// so this only needs to be fetched once per region rather than once per realm.
+    pub fn commodities(&self) -> Result<CommodityFile, ClientError> {
+        let response = self.get("/data/wow/auctions/commodities", Namespace::Dynamic, None)?;
+        Ok(CommodityFile::from_reader(response.into_reader())?)
+    }
+
+    /// Fetches the index of all Connected Realms in the client's region.
+    pub fn connected_realm_index(&self) -> Result<ConnectedRealmIndex, ClientError> {
+        self.get_json("/data/wow/connected-realm/index", Namespace::Dynamic)
+    }
+
+    /// Fetches a single Connected Realm, including the metadata of all of its realms.
+    pub fn connected_realm(&self, connected_realm_id: u32) -> Result<ConnectedRealm, ClientError> {
+        let path = format!("/data/wow/connected-realm/{}", connected_realm_id);
+        self.get_json(&path, Namespace::Dynamic)
+    }
+
+    /// Fetches the index of all realms in the client's region.
+    pub fn realm_index(&self) -> Result<RealmIndex, ClientError> {
+        self.get_json("/data/wow/realm/index", Namespace::Dynamic)
+    }
+
+    /// Fetches a single realm by its slug (eg, `area-52`).
+    pub fn realm(&self, slug: &str) -> Result<Realm, ClientError> {
+        let path = format!("/data/wow/realm/{}", slug);
+        self.get_json(&path, Namespace::Dynamic)
+    }
+
+    /// Fetches an arbitrary Game Data API resource and deserializes the JSON body.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the resource, relative to the API base URL,
+    ///   eg `/data/wow/connected-realm/index`.
+    /// * `namespace` - The namespace the resource lives in.
+    pub fn get_json<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        namespace: Namespace,
+    ) -> Result<T, ClientError> {
+        let response = self.get(path, namespace, None)?;
+        Ok(serde_json::from_reader(response.into_reader())?)
+    }
+
+    /// Makes an authenticated `GET` request, retrying once with a fresh token
+    /// if the API reports that the current one is no longer valid.
+    ///
+    /// If `if_modified_since` is set, the request is made conditional and the
+    /// response may have a `304` status with no body.
+    fn get(
+        &self,
+        path: &str,
+        namespace: Namespace,
+        if_modified_since: Option<&str>,
+    ) -> Result<ureq::Response, ClientError> {
+        match self.send_get(path, namespace, if_modified_since) {
+            Err(ClientError::Status { status: 401, .. }) => {
+                self.invalidate_token();
+                self.send_get(path, namespace, if_modified_since)
+            }
+            result => result,
+        }
+    }
+
+    fn send_get(
+        &self,
+        path: &str,
+        namespace: Namespace,
+        if_modified_since: Option<&str>,
+    ) -> Result<ureq::Response, ClientError> {
+        let token = self.access_token()?;
+        let mut request = self
+            .agent
+            .get(&format!("{}{}", self.base_url, path))
+            .set("Authorization", &format!("Bearer {}", token))
+            .set("Battlenet-Namespace", &namespace.for_region(self.region))
+            .query("locale", &self.locale);
+        if let Some(since) = if_modified_since {
+            request = request.set("If-Modified-Since", since);
+        }
+        Ok(request.call()?)
+    }
+
+    /// Returns the cached access token, requesting a new one if there is no
+    /// token yet or the cached one is about to expire.
+    fn access_token(&self) -> Result<String, ClientError> {
+        let mut cached = self.token.lock().unwrap();
+        if let Some(token) = cached.as_ref() {
+            if token.is_fresh() {
+                return Ok(token.token.clone());
+            }
+        }
+
+        let token = self.request_token()?;
+        let value = token.token.clone();
+        *cached = Some(token);
+        Ok(value)
+    }
+
+    fn invalidate_token(&self) {
+        *self.token.lock().unwrap() = None;
+    }
+
+    /// Performs the OAuth client credentials flow.
+    fn request_token(&self) -> Result<AccessToken, ClientError> {
+        let requested_at = Instant::now();
+        let response = self
+            .agent
+            .post(&self.oauth_url)
+            .set(
+                "Authorization",
+                &format!(
+                    "Basic {}",
+                    base64_encode(&format!("{}:{}", self.client_id, self.client_secret))
+                ),
+            )
+            .send_form(&[("grant_type", "client_credentials")])?;
+        let response: TokenResponse = serde_json::from_reader(response.into_reader())?;
+
+        Ok(AccessToken {
+            token: response.access_token,
+            expires_at: requested_at + Duration::from_secs(response.expires_in),
+        })
+    }
+}
+
+/// Standard (RFC 4648) base64 encoding, used for HTTP Basic authentication.
+fn base64_encode(input: &str) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let bytes = input.as_bytes();
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        if chunk.len() > 1 {
+            out.push(ALPHABET[(n >> 6) as usize & 63] as char);
+        } else {
+            out.push('=');
+        }
+        if chunk.len() > 2 {
+            out.push(ALPHABET[n as usize & 63] as char);
+        } else {
+            out.push('=');
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battlenet::mock_server::{MockResponse, MockServer};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const AUCTIONS_PATH: &str = "/data/wow/connected-realm/3676/auctions";
+
+    const AUCTIONS_JSON: &str = r#"{
+        "_links": { "self": { "href": "https://us.api.blizzard.com/" } },
+        "connected_realm": { "href": "https://us.api.blizzard.com/" },
+        "auctions": [
+            {
+                "id": 1234,
+                "item": { "id": 72092 },
+                "quantity": 20,
+                "unit_price": 164068,
+                "time_left": "MEDIUM"
+            }
+        ]
+    }"#;
+
+    fn token_json(token: &str, expires_in: u64) -> String {
+        format!(
+            r#"{{"access_token": "{}", "token_type": "bearer", "expires_in": {}, "sub": "abc"}}"#,
+            token, expires_in
+        )
+    }
+
+    fn client_for(server: &MockServer) -> BattleNetClient {
+        BattleNetClient::new("id", "secret", Region::Us)
+            .with_base_url(&server.url)
+            .with_oauth_url(&format!("{}/token", server.url))
+    }
+
+    #[test]
+    fn auctions_requests_token_and_returns_auction_file() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/token" => MockResponse::json(200, &token_json("t1", 86399)),
+            AUCTIONS_PATH => MockResponse::json(200, AUCTIONS_JSON),
+            _ => MockResponse::json(404, "{}"),
+        });
+        let client = client_for(&server).with_locale("de_DE");
+
+        let auction_file = client.auctions(3676).unwrap();
+        assert_eq!(1, auction_file.auctions.len());
+        assert_eq!(164068, auction_file.auctions[0].unit_price.unwrap());
+
+        let token_req = &server.requests_to("/token")[0];
+        assert_eq!("POST", token_req.method);
+        assert_eq!("grant_type=client_credentials", token_req.body);
+        assert_eq!(
+            Some("Basic aWQ6c2VjcmV0"),
+            token_req.header("authorization")
+        );
+
+        let auction_req = &server.requests_to(AUCTIONS_PATH)[0];
+        assert_eq!(Some("Bearer t1"), auction_req.header("authorization"));
+        assert_eq!(
+            Some("dynamic-us"),
+            auction_req.header("battlenet-namespace")
+        );
+        assert_eq!("de_DE", auction_req.query["locale"]);
+    }
+
+    #[test]
+    fn commodities_returns_commodity_file() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/token" => MockResponse::json(200, &token_json("t1", 86399)),
+            "/data/wow/auctions/commodities" => MockResponse::json(
+                200,
+                r#"{"auctions": [{"id": 1, "item": {"id": 171276}, "quantity": 4000,
+                    "unit_price": 1150000, "time_left": "LONG"}]}"#,
+            ),
+            _ => MockResponse::json(404, "{}"),
+        });
+        let client = client_for(&server);
+
+        let commodity_file = client.commodities().unwrap();
+        assert_eq!(4000, commodity_file.auctions[0].quantity);
+        assert_eq!(1150000, commodity_file.auctions[0].unit_price);
+    }
+
+    #[test]
+    fn auctions_if_modified_skips_unchanged_snapshot() {
+        const LAST_MODIFIED: &str = "Tue, 16 Mar 2021 19:03:12 GMT";
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/token" => MockResponse::json(200, &token_json("t1", 86399)),
+            AUCTIONS_PATH if req.header("if-modified-since") == Some(LAST_MODIFIED) => {
+                MockResponse::empty(304)
+            }
+            AUCTIONS_PATH => {
+                MockResponse::json(200, AUCTIONS_JSON).with_header("Last-Modified", LAST_MODIFIED)
+            }
+            _ => MockResponse::json(404, "{}"),
+        });
+        let client = client_for(&server);
+        let mut store = LastModifiedStore::in_memory();
+
+        match client.auctions_if_modified(3676, &mut store).unwrap() {
+            Fetched::Modified(auction_file) => assert_eq!(1, auction_file.auctions.len()),
+            Fetched::NotModified => panic!("expected the first fetch to return a snapshot"),
+        }
+        assert_eq!(Some(LAST_MODIFIED), store.get(3676));
+
+        match client.auctions_if_modified(3676, &mut store).unwrap() {
+            Fetched::Modified(_) => panic!("expected the second fetch to be skipped"),
+            Fetched::NotModified => {}
+        }
+
+        let requests = server.requests_to(AUCTIONS_PATH);
+        assert_eq!(None, requests[0].header("if-modified-since"));
+        assert_eq!(Some(LAST_MODIFIED), requests[1].header("if-modified-since"));
+    }
+
+    #[test]
+    fn auctions_if_modified_records_new_timestamp() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/token" => MockResponse::json(200, &token_json("t1", 86399)),
+            _ => MockResponse::json(200, AUCTIONS_JSON)
+                .with_header("Last-Modified", "Tue, 16 Mar 2021 20:03:12 GMT"),
+        });
+        let client = client_for(&server);
+        let mut store = LastModifiedStore::in_memory();
+        store.record(3676, "Tue, 16 Mar 2021 19:03:12 GMT").unwrap();
+
+        match client.auctions_if_modified(3676, &mut store).unwrap() {
+            Fetched::Modified(_) => {}
+            Fetched::NotModified => panic!("expected a new snapshot"),
+        }
+        assert_eq!(Some("Tue, 16 Mar 2021 20:03:12 GMT"), store.get(3676));
+    }
+
+    #[test]
+    fn for_each_auction_streams_auctions() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/token" => MockResponse::json(200, &token_json("t1", 86399)),
+            _ => MockResponse::json(200, AUCTIONS_JSON),
+        });
+        let client = client_for(&server);
+
+        let mut ids = Vec::new();
+        client
+            .for_each_auction(3676, |auction| ids.push(auction.id))
+            .unwrap();
+        assert_eq!(vec![1234], ids);
+    }
+
+    #[test]
+    fn token_is_cached_between_requests() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/token" => MockResponse::json(200, &token_json("t1", 86399)),
+            _ => MockResponse::json(200, AUCTIONS_JSON),
+        });
+        let client = client_for(&server);
+
+        client.auctions(3676).unwrap();
+        client.auctions(3676).unwrap();
+
+        assert_eq!(1, server.requests_to("/token").len());
+        assert_eq!(2, server.requests_to(AUCTIONS_PATH).len());
+    }
+
+    #[test]
+    fn expiring_token_is_refreshed() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            // expires within the safety margin, so it is never reused
+            "/token" => MockResponse::json(200, &token_json("t1", 30)),
+            _ => MockResponse::json(200, AUCTIONS_JSON),
+        });
+        let client = client_for(&server);
+
+        client.auctions(3676).unwrap();
+        client.auctions(3676).unwrap();
+
+        assert_eq!(2, server.requests_to("/token").len());
+    }
+
+    #[test]
+    fn rejected_token_is_refreshed_and_request_retried() {
+        let issued = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&issued);
+        let server = MockServer::start(move |req| match req.path.as_str() {
+            "/token" => {
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                MockResponse::json(200, &token_json(&format!("t{}", n), 86399))
+            }
+            _ if req.header("authorization") == Some("Bearer t1") => MockResponse::json(401, "{}"),
+            _ => MockResponse::json(200, AUCTIONS_JSON),
+        });
+        let client = client_for(&server);
+
+        let auction_file = client.auctions(3676).unwrap();
+        assert_eq!(1, auction_file.auctions.len());
+        assert_eq!(2, issued.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn error_status_is_returned() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/token" => MockResponse::json(200, &token_json("t1", 86399)),
+            _ => MockResponse::json(404, "{}"),
+        });
+        let client = client_for(&server);
+
+        match client.auctions(1) {
+            Err(ClientError::Status { status, .. }) => assert_eq!(404, status),
+            _ => panic!("expected a status error"),
+        }
+    }
+
+    #[test]
+    fn namespace_for_region() {
+        assert_eq!("dynamic-eu", Namespace::Dynamic.for_region(Region::Eu));
+        assert_eq!("static-kr", Namespace::Static.for_region(Region::Kr));
+    }
+
+    #[test]
+    fn region_from_str() {
+        assert_eq!(Region::Us, "US".parse().unwrap());
+        assert_eq!(Region::Tw, "tw".parse().unwrap());
+        assert!("mars".parse::<Region>().is_err());
+    }
+
+    #[test]
+    fn base64_encode_padding() {
+        assert_eq!("", base64_encode(""));
+        assert_eq!("Zg==", base64_encode("f"));
+        assert_eq!("Zm8=", base64_encode("fo"));
+        assert_eq!("Zm9v", base64_encode("foo"));
+        assert_eq!("aWQ6c2VjcmV0", base64_encode("id:secret"));
+    }
+}
@@ -0,0 +1,90 @@
+use serde::Deserialize;
+use std::io::Read;
+
+use crate::auctions::TimeLeft;
+
+/// A raw Commodities resource which is returned by Blizzard's Auction House API
+/// for an entire region, consisting of [`CommodityAuction`]s.
+///
+/// Since patch 9.2.7, stackable goods (herbs, ore, cloth, consumables, etc.) are no
+/// longer listed on each connected realm's auction house. Instead, they are traded
+/// on a single, region-wide commodities market which is exposed through the
+/// `/data/wow/auctions/commodities` endpoint.
+///
+/// Like [AuctionFile](`crate::auctions::AuctionFile`), only the `auctions` field is
+/// kept from the raw resource.
+#[derive(Deserialize)]
+pub struct CommodityFile {
+    /// Vector containing all of the auctions currently on the region's
+    /// commodities market.
+    pub auctions: Vec<CommodityAuction>,
+}
+
+impl CommodityFile {
+    /// Deserialize an instance of [`CommodityFile`] from a JSON string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use etherealpost::battlenet::commodities::CommodityFile;
+    ///
+    /// let json = r#"
+    ///   {
+    ///     "auctions": [
+    ///       {
+    ///         "id": 1234,
+    ///         "item": { "id": 171276 },
+    ///         "quantity": 4000,
+    ///         "unit_price": 1150000,
+    ///         "time_left": "LONG"
+    ///       }
+    ///     ]
+    ///   }"#;
+    /// let commodity_file = CommodityFile::from_json(json).unwrap();
+    /// assert_eq!(4000, commodity_file.auctions[0].quantity);
+    /// ```
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+
+    /// Deserialize an instance of [`CommodityFile`] from a reader which produces JSON,
+    /// such as an HTTP response body or a file on disk.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, serde_json::Error> {
+        serde_json::from_reader(reader)
+    }
+}
+
+/// A single auction on the region-wide commodities market.
+///
+/// Unlike an [Auction](`crate::auctions::Auction`), commodities are always bought
+/// per unit, so there is no `buyout` or `bid`. Only a `unit_price` is present.
+#[derive(Deserialize)]
+pub struct CommodityAuction {
+    /// Unique ID for the auction.
+    pub id: u64,
+
+    /// The commodity which is being auctioned.
+    pub item: CommodityItem,
+
+    /// The number of units available in this auction.
+    ///
+    /// Commodity auctions are not bound by the stack size of the item,
+    /// so this can be considerably larger than a realm auction's quantity.
+    pub quantity: u64,
+
+    /// The price of a single unit.
+    pub unit_price: u64,
+
+    /// The current time left for the auction. See [`TimeLeft`].
+    pub time_left: TimeLeft,
+}
+
+/// A commodity which is up for auction on a [`CommodityAuction`].
+///
+/// Commodities never carry bonuses, modifiers or pet data, so the item ID is
+/// the only identifying field.
+#[derive(Deserialize)]
+pub struct CommodityItem {
+    /// The ID of the item.
+    pub id: u64,
+}
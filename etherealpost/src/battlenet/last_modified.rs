@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A record of the `Last-Modified` timestamp of the most recently fetched
+/// auction snapshot for each Connected Realm.
+///
+/// Blizzard only refreshes auction data roughly once an hour. By sending the
+/// recorded timestamp back in an `If-Modified-Since` header, the API can answer
+/// with `304 Not Modified` instead of re-sending a snapshot which has already
+/// been processed. See
+/// [auctions_if_modified](`crate::battlenet::client::BattleNetClient::auctions_if_modified`).
+///
+/// The timestamps are kept exactly as the API returned them (an HTTP date, eg
+/// `Tue, 16 Mar 2021 19:03:12 GMT`) since they are only ever echoed back.
+///
+/// A store can either live purely in memory, or be backed by a JSON file in which
+/// case every recorded timestamp is written through to disk so that it survives
+/// a restart of the process.
+pub struct LastModifiedStore {
+    path: Option<PathBuf>,
+    connected_realms: HashMap<u32, String>,
+}
+
+impl LastModifiedStore {
+    /// Creates an empty store which is not persisted.
+    pub fn in_memory() -> LastModifiedStore {
+        LastModifiedStore {
+            path: None,
+            connected_realms: HashMap::new(),
+        }
+    }
+
+    /// Opens a store which is persisted as JSON at `path`.
+    ///
+    /// If the file does not exist yet, the store starts out empty and the file
+    /// is created the first time a timestamp is recorded.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<LastModifiedStore> {
+        let path = path.as_ref().to_path_buf();
+        let connected_realms = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(LastModifiedStore {
+            path: Some(path),
+            connected_realms,
+        })
+    }
+
+    /// Returns the `Last-Modified` timestamp of the last snapshot fetched for
+    /// a connected realm, if one has been recorded.
+    pub fn get(&self, connected_realm_id: u32) -> Option<&str> {
+        self.connected_realms
+            .get(&connected_realm_id)
+            .map(|s| s.as_str())
+    }
+
+    /// Records the `Last-Modified` timestamp of a freshly fetched snapshot,
+    /// writing the store to disk if it is file backed.
+    pub fn record(&mut self, connected_realm_id: u32, last_modified: &str) -> io::Result<()> {
+        self.connected_realms
+            .insert(connected_realm_id, last_modified.to_string());
+        self.save()
+    }
+
+    /// Forgets the timestamp of a connected realm, so the next fetch for it is
+    /// unconditional.
+    pub fn remove(&mut self, connected_realm_id: u32) -> io::Result<()> {
+        self.connected_realms.remove(&connected_realm_id);
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => {
+                let json = serde_json::to_string_pretty(&self.connected_realms)?;
+                // write to a temporary file first so a crash mid-write can't
+                // leave a truncated store behind
+                let tmp = path.with_extension("tmp");
+                fs::write(&tmp, json)?;
+                fs::rename(&tmp, path)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("etherealpost-{}-{}.json", name, process::id()))
+    }
+
+    #[test]
+    fn in_memory_store_records_timestamps() {
+        let mut store = LastModifiedStore::in_memory();
+        assert!(store.get(3676).is_none());
+
+        store.record(3676, "Tue, 16 Mar 2021 19:03:12 GMT").unwrap();
+        assert_eq!(Some("Tue, 16 Mar 2021 19:03:12 GMT"), store.get(3676));
+
+        store.remove(3676).unwrap();
+        assert!(store.get(3676).is_none());
+    }
+
+    #[test]
+    fn file_store_survives_reopening() {
+        let path = temp_path("last-modified");
+        let _ = fs::remove_file(&path);
+
+        let mut store = LastModifiedStore::open(&path).unwrap();
+        store.record(3676, "Tue, 16 Mar 2021 19:03:12 GMT").unwrap();
+        store.record(11, "Tue, 16 Mar 2021 19:10:00 GMT").unwrap();
+
+        let reopened = LastModifiedStore::open(&path).unwrap();
+        assert_eq!(Some("Tue, 16 Mar 2021 19:03:12 GMT"), reopened.get(3676));
+        assert_eq!(Some("Tue, 16 Mar 2021 19:10:00 GMT"), reopened.get(11));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1 +1,12 @@
 pub mod auctions;
+pub mod client;
+pub mod commodities;
+pub mod last_modified;
+pub mod realms;
+
+#[cfg(test)]
+mod mock_server;
+
+pub use client::BattleNetClient;
+pub use last_modified::LastModifiedStore;
+pub use realms::RealmDirectory;
@@ -0,0 +1,428 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::battlenet::client::{BattleNetClient, ClientError, Region};
+
+/// A link to another API resource, as returned in the `href` fields of the
+/// Game Data APIs.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Link {
+    /// The full URL of the linked resource, including its namespace query parameter.
+    pub href: String,
+}
+
+impl Link {
+    /// Extracts the Connected Realm ID from a link to a connected realm resource,
+    /// eg `https://us.api.blizzard.com/data/wow/connected-realm/3676?namespace=dynamic-us`.
+    pub fn connected_realm_id(&self) -> Option<u32> {
+        let path = self.href.split('?').next()?;
+        let (_, rest) = path.split_once("/connected-realm/")?;
+        rest.split('/').next()?.parse().ok()
+    }
+}
+
+/// A typed enumeration value as returned by the API, which pairs a stable
+/// `type` identifier with a localized display `name`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Typed<T> {
+    /// The stable identifier of the value.
+    #[serde(rename = "type")]
+    pub kind: T,
+
+    /// The localized display name of the value.
+    pub name: String,
+}
+
+/// The index of all Connected Realms in a region, returned by
+/// `/data/wow/connected-realm/index`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ConnectedRealmIndex {
+    /// Links to each of the connected realms in the region.
+    pub connected_realms: Vec<Link>,
+}
+
+impl ConnectedRealmIndex {
+    /// The IDs of all of the connected realms in the index.
+    pub fn ids(&self) -> Vec<u32> {
+        self.connected_realms
+            .iter()
+            .filter_map(|link| link.connected_realm_id())
+            .collect()
+    }
+}
+
+/// A Connected Realm, returned by `/data/wow/connected-realm/{id}`.
+///
+/// A connected realm is a group of one or more realms which share a single
+/// Auction House, which is why auction data is keyed by the connected realm
+/// rather than by the individual realm.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ConnectedRealm {
+    /// The ID of the connected realm.
+    pub id: u32,
+
+    /// Whether or not the realms currently have a login queue.
+    pub has_queue: bool,
+
+    /// Whether the realms are currently up or down.
+    pub status: Typed<RealmStatus>,
+
+    /// The population of the connected realm.
+    pub population: Typed<RealmPopulation>,
+
+    /// The realms which make up the connected realm.
+    pub realms: Vec<Realm>,
+}
+
+/// A single Realm, returned either as part of a [`ConnectedRealm`] or by
+/// `/data/wow/realm/{slug}`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Realm {
+    /// The ID of the realm. This is _not_ the same as the connected realm ID,
+    /// although for the "parent" realm of a connected realm they are often equal.
+    pub id: u32,
+
+    /// The localized name of the realm, eg `Area 52`.
+    pub name: String,
+
+    /// The URL-friendly name of the realm, eg `area-52`. Slugs are unique
+    /// within a region.
+    pub slug: String,
+
+    /// The localized category of the realm, eg `United States` or `Oceanic`.
+    pub category: String,
+
+    /// The game locale of the realm, eg `enUS`.
+    pub locale: String,
+
+    /// The IANA timezone of the realm, eg `America/New_York`.
+    pub timezone: String,
+
+    /// The ruleset of the realm.
+    #[serde(rename = "type")]
+    pub realm_type: Typed<RealmType>,
+
+    /// Whether or not this is a tournament realm.
+    pub is_tournament: bool,
+
+    /// Link to the connected realm this realm belongs to. This is only present
+    /// when the realm is fetched on its own.
+    pub connected_realm: Option<Link>,
+}
+
+/// A list of all of the realms in a region, returned by `/data/wow/realm/index`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RealmIndex {
+    /// Every realm in the region.
+    pub realms: Vec<RealmReference>,
+}
+
+/// An abbreviated [`Realm`] as listed in a [`RealmIndex`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RealmReference {
+    /// The ID of the realm.
+    pub id: u32,
+
+    /// The localized name of the realm.
+    pub name: String,
+
+    /// The URL-friendly name of the realm.
+    pub slug: String,
+}
+
+/// Whether a realm is currently up or down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RealmStatus {
+    Up,
+    Down,
+    #[serde(other)]
+    Unknown,
+}
+
+/// The population of a connected realm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RealmPopulation {
+    Low,
+    Medium,
+    High,
+    Full,
+    Locked,
+    Recommended,
+    NewPlayers,
+    #[serde(other)]
+    Unknown,
+}
+
+/// The ruleset of a realm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RealmType {
+    Normal,
+    Rp,
+    Pvp,
+    Rppvp,
+    #[serde(other)]
+    Unknown,
+}
+
+/// A directory of realms which resolves human-readable realm names to the
+/// Connected Realm IDs which auction data is keyed by.
+///
+/// Realms are identified by their region and slug, written as `region/slug`
+/// (eg, `us/area-52`).
+///
+/// # Example
+///
+/// ```no_run
+/// use etherealpost::battlenet::client::{BattleNetClient, Region};
+/// use etherealpost::battlenet::realms::RealmDirectory;
+///
+/// let client = BattleNetClient::new("client-id", "client-secret", Region::Us);
+/// let directory = RealmDirectory::fetch(&client).unwrap();
+///
+/// let connected_realm_id = directory.resolve("us/area-52").unwrap();
+/// let auction_file = client.auctions(connected_realm_id).unwrap();
+/// ```
+#[derive(Default)]
+pub struct RealmDirectory {
+    connected_realms: HashMap<(Region, u32), ConnectedRealm>,
+    slugs: HashMap<(Region, String), u32>,
+}
+
+impl RealmDirectory {
+    /// Creates an empty directory.
+    pub fn new() -> RealmDirectory {
+        RealmDirectory::default()
+    }
+
+    /// Builds a directory of every connected realm in the client's region.
+    ///
+    /// This makes one request for the connected realm index and one more for
+    /// each connected realm in it. To cover several regions, build one directory
+    /// and [insert](`Self::insert`) the connected realms of the other regions.
+    pub fn fetch(client: &BattleNetClient) -> Result<RealmDirectory, ClientError> {
+        let mut directory = RealmDirectory::new();
+        for id in client.connected_realm_index()?.ids() {
+            directory.insert(client.region(), client.connected_realm(id)?);
+        }
+        Ok(directory)
+    }
+
+    /// Adds a connected realm, and all of its realms, to the directory.
+    pub fn insert(&mut self, region: Region, connected_realm: ConnectedRealm) {
+        for realm in &connected_realm.realms {
+            self.slugs
+                .insert((region, realm.slug.clone()), connected_realm.id);
+        }
+        self.connected_realms
+            .insert((region, connected_realm.id), connected_realm);
+    }
+
+    /// Resolves a realm written as `region/slug` (eg, `us/area-52`) to the ID of
+    /// the connected realm it belongs to.
+    ///
+    /// Returns `None` if the region is not known or the realm is not in the directory.
+    pub fn resolve(&self, realm: &str) -> Option<u32> {
+        let (region, slug) = realm.split_once('/')?;
+        let region: Region = region.parse().ok()?;
+        self.connected_realm_id(region, slug)
+    }
+
+    /// Looks up the ID of the connected realm a realm belongs to by its slug.
+    pub fn connected_realm_id(&self, region: Region, slug: &str) -> Option<u32> {
+        self.slugs
+            .get(&(region, slug.to_ascii_lowercase()))
+            .copied()
+    }
+
+    /// Looks up a connected realm by its ID.
+    pub fn connected_realm(&self, region: Region, id: u32) -> Option<&ConnectedRealm> {
+        self.connected_realms.get(&(region, id))
+    }
+
+    /// Looks up a realm by its slug.
+    pub fn realm(&self, region: Region, slug: &str) -> Option<&Realm> {
+        let slug = slug.to_ascii_lowercase();
+        let id = self.slugs.get(&(region, slug.clone()))?;
+        self.connected_realms
+            .get(&(region, *id))?
+            .realms
+            .iter()
+            .find(|r| r.slug == slug)
+    }
+
+    /// A human-readable name for a connected realm, made up of the names of its
+    /// realms joined with `/` (eg, `Area 52/Blackrock`).
+    pub fn display_name(&self, region: Region, id: u32) -> Option<String> {
+        let connected_realm = self.connected_realms.get(&(region, id))?;
+        let mut names: Vec<&str> = connected_realm
+            .realms
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        names.sort_unstable();
+        Some(names.join("/"))
+    }
+
+    /// The number of connected realms in the directory.
+    pub fn len(&self) -> usize {
+        self.connected_realms.len()
+    }
+
+    /// Whether the directory has no connected realms in it.
+    pub fn is_empty(&self) -> bool {
+        self.connected_realms.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battlenet::mock_server::{MockResponse, MockServer};
+
+    const INDEX_JSON: &str = r#"{
+        "_links": { "self": { "href": "https://us.api.blizzard.com/data/wow/connected-realm/?namespace=dynamic-us" } },
+        "connected_realms": [
+            { "href": "https://us.api.blizzard.com/data/wow/connected-realm/3676?namespace=dynamic-us" },
+            { "href": "https://us.api.blizzard.com/data/wow/connected-realm/11?namespace=dynamic-us" }
+        ]
+    }"#;
+
+    const AREA_52_JSON: &str = r#"{
+        "id": 3676,
+        "has_queue": false,
+        "status": { "type": "UP", "name": "Up" },
+        "population": { "type": "FULL", "name": "Full" },
+        "realms": [
+            {
+                "id": 3676,
+                "region": { "name": "North America", "id": 1 },
+                "connected_realm": { "href": "https://us.api.blizzard.com/data/wow/connected-realm/3676?namespace=dynamic-us" },
+                "name": "Area 52",
+                "category": "United States",
+                "locale": "enUS",
+                "timezone": "America/New_York",
+                "type": { "type": "NORMAL", "name": "Normal" },
+                "is_tournament": false,
+                "slug": "area-52"
+            }
+        ],
+        "auctions": { "href": "https://us.api.blizzard.com/data/wow/connected-realm/3676/auctions?namespace=dynamic-us" }
+    }"#;
+
+    const TICHONDRIUS_JSON: &str = r#"{
+        "id": 11,
+        "has_queue": false,
+        "status": { "type": "UP", "name": "Up" },
+        "population": { "type": "HIGH", "name": "High" },
+        "realms": [
+            {
+                "id": 11, "name": "Tichondrius", "slug": "tichondrius", "category": "United States",
+                "locale": "enUS", "timezone": "America/Los_Angeles",
+                "type": { "type": "NORMAL", "name": "Normal" }, "is_tournament": false
+            },
+            {
+                "id": 1168, "name": "Blackrock", "slug": "blackrock", "category": "United States",
+                "locale": "enUS", "timezone": "America/Los_Angeles",
+                "type": { "type": "PVP", "name": "PvP" }, "is_tournament": false
+            }
+        ]
+    }"#;
+
+    fn directory_server() -> MockServer {
+        MockServer::start(|req| match req.path.as_str() {
+            "/token" => MockResponse::json(
+                200,
+                r#"{"access_token": "t1", "token_type": "bearer", "expires_in": 86399}"#,
+            ),
+            "/data/wow/connected-realm/index" => MockResponse::json(200, INDEX_JSON),
+            "/data/wow/connected-realm/3676" => MockResponse::json(200, AREA_52_JSON),
+            "/data/wow/connected-realm/11" => MockResponse::json(200, TICHONDRIUS_JSON),
+            _ => MockResponse::json(404, "{}"),
+        })
+    }
+
+    fn client_for(server: &MockServer) -> BattleNetClient {
+        BattleNetClient::new("id", "secret", Region::Us)
+            .with_base_url(&server.url)
+            .with_oauth_url(&format!("{}/token", server.url))
+    }
+
+    #[test]
+    fn link_connected_realm_id() {
+        let link = Link {
+            href: "https://us.api.blizzard.com/data/wow/connected-realm/3676?namespace=dynamic-us"
+                .to_string(),
+        };
+        assert_eq!(Some(3676), link.connected_realm_id());
+
+        let link = Link {
+            href: "https://us.api.blizzard.com/data/wow/realm/area-52".to_string(),
+        };
+        assert!(link.connected_realm_id().is_none());
+    }
+
+    #[test]
+    fn connected_realm_deserializes_metadata() {
+        let connected_realm: ConnectedRealm = serde_json::from_str(TICHONDRIUS_JSON).unwrap();
+        assert_eq!(RealmStatus::Up, connected_realm.status.kind);
+        assert_eq!(RealmPopulation::High, connected_realm.population.kind);
+        assert_eq!(2, connected_realm.realms.len());
+        assert_eq!(RealmType::Pvp, connected_realm.realms[1].realm_type.kind);
+        assert_eq!("America/Los_Angeles", connected_realm.realms[1].timezone);
+    }
+
+    #[test]
+    fn unknown_population_is_tolerated() {
+        let population: Typed<RealmPopulation> =
+            serde_json::from_str(r#"{"type": "SOMETHING_NEW", "name": "New"}"#).unwrap();
+        assert_eq!(RealmPopulation::Unknown, population.kind);
+    }
+
+    #[test]
+    fn directory_fetches_and_resolves_realms() {
+        let server = directory_server();
+        let directory = RealmDirectory::fetch(&client_for(&server)).unwrap();
+
+        assert_eq!(2, directory.len());
+        assert_eq!(Some(3676), directory.resolve("us/area-52"));
+        assert_eq!(Some(11), directory.resolve("US/Blackrock"));
+        assert_eq!(Some(11), directory.resolve("us/tichondrius"));
+        assert_eq!(None, directory.resolve("eu/area-52"));
+        assert_eq!(None, directory.resolve("area-52"));
+
+        assert_eq!(
+            "Blackrock/Tichondrius",
+            directory.display_name(Region::Us, 11).unwrap()
+        );
+        assert_eq!(
+            "America/New_York",
+            directory.realm(Region::Us, "area-52").unwrap().timezone
+        );
+    }
+
+    #[test]
+    fn client_fetches_single_realm() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/token" => MockResponse::json(
+                200,
+                r#"{"access_token": "t1", "token_type": "bearer", "expires_in": 86399}"#,
+            ),
+            "/data/wow/realm/area-52" => MockResponse::json(
+                200,
+                r#"{"id": 3676, "name": "Area 52", "slug": "area-52", "category": "United States",
+                    "locale": "enUS", "timezone": "America/New_York",
+                    "type": {"type": "NORMAL", "name": "Normal"}, "is_tournament": false,
+                    "connected_realm": {"href": "https://us.api.blizzard.com/data/wow/connected-realm/3676?namespace=dynamic-us"}}"#,
+            ),
+            _ => MockResponse::json(404, "{}"),
+        });
+        let realm = client_for(&server).realm("area-52").unwrap();
+        assert_eq!(
+            Some(3676),
+            realm.connected_realm.unwrap().connected_realm_id()
+        );
+    }
+}
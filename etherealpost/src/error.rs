@@ -0,0 +1,105 @@
+use std::fmt;
+use std::io;
+
+use crate::battlenet::client::ClientError;
+use crate::wow::db2::Db2Error;
+use crate::wow::GameDataError;
+
+/// The error type for everything in this crate.
+///
+/// The modules which talk to a single source keep their own, more specific error
+/// types, such as [`ClientError`] and [`Db2Error`]. These convert into an `Error`, so
+/// callers combining several modules can use `?` throughout.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be read or written.
+    Io(io::Error),
+
+    /// JSON could not be serialized or deserialized.
+    Json(serde_json::Error),
+
+    /// A row of a data table could not be read.
+    ///
+    /// This is only returned when tables are loaded with
+    /// [LoadMode::Strict](`crate::wow::data_tables::LoadMode::Strict`). Lenient
+    /// loads skip the row and record it in their
+    /// [LoadReport](`crate::wow::data_tables::LoadReport`) instead.
+    InvalidRow {
+        /// The name of the table the row belongs to.
+        table: &'static str,
+        /// The line of the row in a CSV file, or the position of the record in
+        /// a DB2 file, starting from `1`.
+        line: u64,
+        /// Why the row could not be read.
+        reason: String,
+    },
+
+    /// A DB2 file could not be parsed.
+    Db2(Db2Error),
+
+    /// A request to the Blizzard API failed.
+    Client(ClientError),
+
+    /// Game data could not be loaded.
+    GameData(GameDataError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Json(e) => write!(f, "JSON error: {}", e),
+            Error::InvalidRow {
+                table,
+                line,
+                reason,
+            } => write!(f, "invalid row in {} at line {}: {}", table, line, reason),
+            Error::Db2(e) => write!(f, "DB2 error: {}", e),
+            Error::Client(e) => write!(f, "{}", e),
+            Error::GameData(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::InvalidRow { .. } => None,
+            Error::Db2(e) => Some(e),
+            Error::Client(e) => Some(e),
+            Error::GameData(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<Db2Error> for Error {
+    fn from(e: Db2Error) -> Self {
+        Error::Db2(e)
+    }
+}
+
+impl From<ClientError> for Error {
+    fn from(e: ClientError) -> Self {
+        Error::Client(e)
+    }
+}
+
+impl From<GameDataError> for Error {
+    fn from(e: GameDataError) -> Self {
+        Error::GameData(e)
+    }
+}
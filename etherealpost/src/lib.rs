@@ -1,9 +1,13 @@
 pub mod battlenet;
+mod error;
 pub mod parse;
+pub mod storage;
 pub mod utils;
 pub mod wow;
 
 pub use battlenet::auctions;
+pub use battlenet::commodities;
+pub use error::Error;
 pub use utils::stats;
 
 #[cfg(test)]
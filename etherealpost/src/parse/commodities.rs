@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::commodities;
+use crate::parse::summary::{summarize_groups, GroupAuctions};
+use crate::parse::{AuctionsSummary, ItemSummary, SummaryOptions};
+
+/// A summarized snapshot of a raw [CommodityFile](`crate::commodities::CommodityFile`),
+/// which covers the commodities market of an entire region.
+///
+/// Commodities never have bonuses, item levels or pet data, so unlike an
+/// [`AuctionsSummary`] there is only a single mapping of **Item ID** to its summary.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct CommoditiesSummary {
+    /// Mapping of **Item ID** to the Auction Item Summary information for those items.
+    pub item_summaries: HashMap<u64, ItemSummary>,
+}
+
+impl CommoditiesSummary {
+    /// Takes a [CommodityFile](`crate::commodities::CommodityFile`) struct representation
+    /// of the JSON file for all of the commodity auctions currently in a region and
+    /// summarizes the prices of each item.
+    pub fn from_commodity_file(commodity_file: &commodities::CommodityFile) -> Self {
+        CommoditiesSummary::from_commodity_file_with_options(
+            commodity_file,
+            &SummaryOptions::default(),
+        )
+    }
+
+    /// Summarizes a [CommodityFile](`crate::commodities::CommodityFile`) like
+    /// [from_commodity_file](`Self::from_commodity_file`), according to `options`.
+    ///
+    /// Commodities have neither bids nor variants, so only the
+    /// [market price config](`SummaryOptions::market_price`) and the
+    /// [extended summary](`SummaryOptions::extended_summary`) options apply.
+    pub fn from_commodity_file_with_options(
+        commodity_file: &commodities::CommodityFile,
+        options: &SummaryOptions,
+    ) -> Self {
+        let mut items: HashMap<u64, GroupAuctions> = HashMap::new();
+
+        for auction in &commodity_file.auctions {
+            items
+                .entry(auction.item.id)
+                .or_default()
+                .add_price(auction.unit_price, auction.quantity);
+        }
+
+        CommoditiesSummary {
+            item_summaries: summarize_groups(items, options),
+        }
+    }
+}
+
+/// Which auction house an item's price was found on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarketSource {
+    /// The item trades on the connected realm's own auction house.
+    ConnectedRealm,
+
+    /// The item is a commodity and trades on the region-wide commodities market.
+    Commodities,
+}
+
+/// A combined view of a connected realm's [`AuctionsSummary`] and its region's
+/// [`CommoditiesSummary`].
+///
+/// An item is either a commodity or it isn't, so it will only ever be listed on
+/// one of the two auction houses. This view answers "what does this item cost
+/// on this realm" without the caller needing to know which one that is.
+///
+/// # Example
+///
+/// ```rust
+/// use etherealpost::auctions::AuctionFile;
+/// use etherealpost::commodities::CommodityFile;
+/// use etherealpost::parse::{AuctionsSummary, CommoditiesSummary, RealmMarket};
+/// use etherealpost::wow::GameData;
+///
+/// let realm_file = AuctionFile::from_json(r#"{"auctions": [{"id": 1, "quantity": 1,
+///     "item": {"id": 19019}, "buyout": 9990000, "time_left": "LONG"}]}"#).unwrap();
+/// let commodity_file = CommodityFile::from_json(r#"{"auctions": [{"id": 2, "quantity": 200,
+///     "item": {"id": 171276}, "unit_price": 1150000, "time_left": "LONG"}]}"#).unwrap();
+///
+/// let realm = AuctionsSummary::from_auction_file(&realm_file, &GameData::default());
+/// let commodities = CommoditiesSummary::from_commodity_file(&commodity_file);
+///
+/// let market = RealmMarket::new(&realm, &commodities);
+/// assert_eq!(9990000, market.item_summary(&19019).unwrap().market_price);
+/// assert_eq!(1150000, market.item_summary(&171276).unwrap().market_price);
+/// ```
+pub struct RealmMarket<'s, 'a> {
+    realm: &'s AuctionsSummary<'a>,
+    commodities: &'s CommoditiesSummary,
+}
+
+impl<'s, 'a> RealmMarket<'s, 'a> {
+    /// Combines a connected realm summary with the commodities summary of the
+    /// region the connected realm belongs to.
+    pub fn new(realm: &'s AuctionsSummary<'a>, commodities: &'s CommoditiesSummary) -> Self {
+        RealmMarket { realm, commodities }
+    }
+
+    /// Looks up the summary for an item, along with which auction house it was found on.
+    ///
+    /// The connected realm's auction house is checked first, followed by the
+    /// region's commodities market.
+    pub fn lookup(&self, item_id: &u64) -> Option<(MarketSource, &'s ItemSummary)> {
+        self.realm
+            .item_summaries
+            .get(item_id)
+            .map(|s| (MarketSource::ConnectedRealm, s))
+            .or_else(|| {
+                self.commodities
+                    .item_summaries
+                    .get(item_id)
+                    .map(|s| (MarketSource::Commodities, s))
+            })
+    }
+
+    /// Looks up the summary for an item, regardless of which auction house it trades on.
+    pub fn item_summary(&self, item_id: &u64) -> Option<&'s ItemSummary> {
+        self.lookup(item_id).map(|(_, s)| s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auctions::AuctionFile;
+    use crate::commodities::CommodityFile;
+    use crate::stats::MarketPriceConfig;
+    use crate::wow::GameData;
+
+    const COMMODITIES_JSON: &str = r#"{"auctions": [
+        {"id": 1, "item": {"id": 171276}, "quantity": 2, "unit_price": 100, "time_left": "LONG"},
+        {"id": 2, "item": {"id": 171276}, "quantity": 3, "unit_price": 110, "time_left": "SHORT"},
+        {"id": 3, "item": {"id": 171315}, "quantity": 1, "unit_price": 5000, "time_left": "LONG"}
+    ]}"#;
+
+    #[test]
+    fn commodities_summary_groups_by_item() {
+        let file = CommodityFile::from_json(COMMODITIES_JSON).unwrap();
+        let summary = CommoditiesSummary::from_commodity_file(&file);
+
+        let flask = summary.item_summaries.get(&171276).unwrap();
+        assert_eq!(5, flask.total_qty);
+        assert_eq!(2, flask.num_auctions);
+        assert_eq!(100, flask.min_buyout);
+
+        let other = summary.item_summaries.get(&171315).unwrap();
+        assert_eq!(5000, other.market_price);
+        assert_eq!(1, other.num_auctions);
+    }
+
+    #[test]
+    fn commodities_summary_with_market_price_config() {
+        let file = CommodityFile::from_json(COMMODITIES_JSON).unwrap();
+        let default = CommoditiesSummary::from_commodity_file(&file);
+        assert_eq!(
+            default,
+            CommoditiesSummary::from_commodity_file_with_options(&file, &SummaryOptions::default())
+        );
+        assert_eq!(100, default.item_summaries[&171276].market_price);
+
+        // every unit is considered once the window reaches all of them
+        let config = MarketPriceConfig::default()
+            .with_percentiles(100.0, 100.0)
+            .with_std_dev_cutoff(f64::MAX);
+        let options = SummaryOptions::default().with_market_price_config(config);
+        let summary = CommoditiesSummary::from_commodity_file_with_options(&file, &options);
+        assert_eq!(106, summary.item_summaries[&171276].market_price);
+    }
+
+    #[test]
+    fn realm_market_looks_up_both_auction_houses() {
+        let realm_file = AuctionFile::from_json(
+            r#"{"auctions": [{"id": 9, "quantity": 1, "item": {"id": 19019},
+                "buyout": 9990000, "time_left": "LONG"}]}"#,
+        )
+        .unwrap();
+        let realm = AuctionsSummary::from_auction_file(&realm_file, &GameData::default());
+        let commodities = CommoditiesSummary::from_commodity_file(
+            &CommodityFile::from_json(COMMODITIES_JSON).unwrap(),
+        );
+        let market = RealmMarket::new(&realm, &commodities);
+
+        let (source, summary) = market.lookup(&19019).unwrap();
+        assert_eq!(MarketSource::ConnectedRealm, source);
+        assert_eq!(9990000, summary.market_price);
+
+        let (source, summary) = market.lookup(&171315).unwrap();
+        assert_eq!(MarketSource::Commodities, source);
+        assert_eq!(5000, summary.market_price);
+
+        assert!(market.lookup(&1).is_none());
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::auctions;
+use crate::parse::summary::{summarize_groups, GroupAuctions};
+use crate::parse::{ItemSummary, SummaryOptions};
+use crate::wow::GameData;
+
+/// A way of grouping auctions, which defines the key of the group every auction
+/// belongs to.
+///
+/// Every grouping gets an [`ItemSummary`] per key by wrapping it in a
+/// [`GroupedSummaries`]. Several groupings can be collected in the same pass over an
+/// auction file as the [AuctionsSummary](`crate::parse::AuctionsSummary`) itself, see
+/// [from_auction_file_with_groups](`crate::parse::AuctionsSummary::from_auction_file_with_groups`).
+///
+/// Besides the groupings in this module, any closure taking an
+/// [Auction](`crate::auctions::Auction`) and the [GameData](`crate::wow::GameData`)
+/// and returning an `Option` of a key is a grouping.
+///
+/// # Example
+///
+/// ```rust
+/// use etherealpost::auctions::{Auction, AuctionFile};
+/// use etherealpost::parse::{AuctionsSummary, GroupedSummaries, ItemContext, SummaryOptions};
+/// use etherealpost::wow::GameData;
+///
+/// let auction_file = AuctionFile::from_json(r#"{"auctions": [
+///     {"id": 1, "quantity": 1, "item": {"id": 19019, "context": 5}, "buyout": 900, "time_left": "LONG"},
+///     {"id": 2, "quantity": 3, "item": {"id": 2589}, "buyout": 30, "time_left": "LONG"}
+/// ]}"#).unwrap();
+///
+/// let mut by_context = GroupedSummaries::new(ItemContext);
+/// let mut stacks = GroupedSummaries::new(|auction: &Auction, _: &GameData| {
+///     Some(auction.quantity > 1)
+/// });
+///
+/// let summary = AuctionsSummary::from_auction_file_with_groups(
+///     &auction_file,
+///     &GameData::default(),
+///     &SummaryOptions::default(),
+///     &mut [&mut by_context, &mut stacks],
+/// );
+///
+/// assert_eq!(900, by_context.into_summaries()[&(19019, Some(5))].market_price);
+/// assert_eq!(3, stacks.into_summaries()[&true].total_qty);
+/// assert_eq!(2, summary.item_summaries.len());
+/// ```
+pub trait AuctionGrouping {
+    /// The key which identifies a group.
+    type Key: Eq + Hash + Clone;
+
+    /// The key of the group an auction belongs to, or `None` if the auction isn't
+    /// part of any group.
+    fn key(&self, auction: &auctions::Auction, game_data: &GameData) -> Option<Self::Key>;
+}
+
+impl<K, F> AuctionGrouping for F
+where
+    K: Eq + Hash + Clone,
+    F: Fn(&auctions::Auction, &GameData) -> Option<K>,
+{
+    type Key = K;
+
+    fn key(&self, auction: &auctions::Auction, game_data: &GameData) -> Option<K> {
+        self(auction, game_data)
+    }
+}
+
+/// Something which is fed the auctions of a file one at a time.
+///
+/// This is what allows groupings with different key types to be collected in the
+/// same pass. It is implemented by [`GroupedSummaries`].
+pub trait AuctionCollector {
+    /// Adds a single auction.
+    fn add(&mut self, auction: &auctions::Auction, game_data: &GameData);
+}
+
+/// Collects the auctions of an [`AuctionGrouping`] and summarizes every group.
+///
+/// The same auctions are used, and the same statistics calculated, as for the
+/// summaries of an [AuctionsSummary](`crate::parse::AuctionsSummary`), including the
+/// bid statistics. Groups which only have bid-only auctions have no summary.
+pub struct GroupedSummaries<G: AuctionGrouping> {
+    grouping: G,
+    options: SummaryOptions,
+    groups: HashMap<G::Key, GroupAuctions>,
+}
+
+impl<G: AuctionGrouping> GroupedSummaries<G> {
+    /// Creates an empty collector for a grouping, which summarizes its groups using
+    /// the default [`SummaryOptions`].
+    pub fn new(grouping: G) -> Self {
+        GroupedSummaries {
+            grouping,
+            options: SummaryOptions::default(),
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Summarizes the groups according to `options`, such as the
+    /// [bid weight](`SummaryOptions::bid_weight`). The variant grouping of the options
+    /// is not used.
+    pub fn with_options(mut self, options: &SummaryOptions) -> Self {
+        self.options = options.clone();
+        self
+    }
+
+    /// Adds every auction of an auction file.
+    pub fn add_auction_file(&mut self, auction_file: &auctions::AuctionFile, game_data: &GameData) {
+        for auction in &auction_file.auctions {
+            self.add(auction, game_data);
+        }
+    }
+
+    /// Computes the summary of every group.
+    pub fn into_summaries(self) -> HashMap<G::Key, ItemSummary> {
+        summarize_groups(self.groups, &self.options)
+    }
+}
+
+impl<G: AuctionGrouping> AuctionCollector for GroupedSummaries<G> {
+    fn add(&mut self, auction: &auctions::Auction, game_data: &GameData) {
+        if let Some(key) = self.grouping.key(auction, game_data) {
+            self.groups.entry(key).or_default().add(auction);
+        }
+    }
+}
+
+/// Groups items by **Item ID** and the context they dropped in, such as a dungeon
+/// difficulty or a world quest.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ItemContext;
+
+impl AuctionGrouping for ItemContext {
+    type Key = (u64, Option<u16>);
+
+    fn key(&self, auction: &auctions::Auction, _: &GameData) -> Option<Self::Key> {
+        Some((auction.item.id, auction.item.context))
+    }
+}
+
+/// Groups caged pets by **Pet Species ID** and **Breed ID**.
+///
+/// Pets which aren't in a pet cage have no breed yet, and are left out.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PetBreed;
+
+impl AuctionGrouping for PetBreed {
+    type Key = (u32, u32);
+
+    fn key(&self, auction: &auctions::Auction, _: &GameData) -> Option<Self::Key> {
+        auction.item.pet().map(|pet| (pet.species, pet.breed))
+    }
+}
+
+/// Groups caged pets by **Pet Species ID** and a bracket of pet levels.
+///
+/// The bracket is identified by its lowest level. With a bracket size of `5`, pets of
+/// levels `1` to `5` are in bracket `1`, `6` to `10` in bracket `6`, and so on.
+///
+/// Pets which aren't in a pet cage are left out.
+#[derive(Clone, Copy, Debug)]
+pub struct PetLevelBracket {
+    bracket_size: u8,
+}
+
+impl PetLevelBracket {
+    /// Creates a grouping with brackets of `bracket_size` levels. A size of `0` is
+    /// treated as `1`.
+    pub fn new(bracket_size: u8) -> Self {
+        PetLevelBracket {
+            bracket_size: bracket_size.max(1),
+        }
+    }
+}
+
+impl AuctionGrouping for PetLevelBracket {
+    type Key = (u32, u8);
+
+    fn key(&self, auction: &auctions::Auction, _: &GameData) -> Option<Self::Key> {
+        auction.item.pet().map(|pet| {
+            let bracket = pet.level.saturating_sub(1) / self.bracket_size * self.bracket_size + 1;
+            (pet.species, bracket)
+        })
+    }
+}
+
+/// Groups items by **Item ID** and the set of bonus IDs on the item.
+///
+/// The bonus IDs are sorted, so the order they're listed in doesn't matter.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BonusListSet;
+
+impl AuctionGrouping for BonusListSet {
+    type Key = (u64, Vec<u32>);
+
+    fn key(&self, auction: &auctions::Auction, _: &GameData) -> Option<Self::Key> {
+        let mut bonus_ids = auction.item.bonus_lists.clone().unwrap_or_default();
+        bonus_ids.sort_unstable();
+        bonus_ids.dedup();
+        Some((auction.item.id, bonus_ids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::AuctionsSummary;
+
+    const AUCTIONS_JSON: &str = r#"{"auctions": [
+        {"id": 1, "quantity": 1, "item": {"id": 19019, "context": 5, "bonus_lists": [2, 1]},
+            "buyout": 1000, "time_left": "LONG"},
+        {"id": 2, "quantity": 1, "item": {"id": 19019, "context": 5, "bonus_lists": [1, 2]},
+            "buyout": 1200, "time_left": "LONG"},
+        {"id": 3, "quantity": 1, "item": {"id": 19019}, "buyout": 300, "time_left": "LONG"},
+        {"id": 4, "quantity": 1, "item": {"id": 82800, "pet_breed_id": 4, "pet_level": 1,
+            "pet_quality_id": 3, "pet_species_id": 85}, "buyout": 300, "time_left": "LONG"},
+        {"id": 5, "quantity": 1, "item": {"id": 82800, "pet_breed_id": 4, "pet_level": 25,
+            "pet_quality_id": 3, "pet_species_id": 85}, "buyout": 5000, "time_left": "LONG"},
+        {"id": 6, "quantity": 1, "item": {"id": 82800, "pet_breed_id": 7, "pet_level": 5,
+            "pet_quality_id": 3, "pet_species_id": 85}, "bid": 10, "time_left": "LONG"}
+    ]}"#;
+
+    #[test]
+    fn provided_groupings() {
+        let file = auctions::AuctionFile::from_json(AUCTIONS_JSON).unwrap();
+        let game_data = GameData::default();
+
+        let mut by_context = GroupedSummaries::new(ItemContext);
+        by_context.add_auction_file(&file, &game_data);
+        let by_context = by_context.into_summaries();
+        assert_eq!(2, by_context[&(19019, Some(5))].num_auctions);
+        assert_eq!(300, by_context[&(19019, None)].market_price);
+
+        let mut by_breed = GroupedSummaries::new(PetBreed);
+        by_breed.add_auction_file(&file, &game_data);
+        let by_breed = by_breed.into_summaries();
+        assert_eq!(2, by_breed[&(85, 4)].num_auctions);
+        // the bid only auction has nothing to buy out
+        assert!(!by_breed.contains_key(&(85, 7)));
+
+        let mut by_level = GroupedSummaries::new(PetLevelBracket::new(5));
+        by_level.add_auction_file(&file, &game_data);
+        let by_level = by_level.into_summaries();
+        assert_eq!(300, by_level[&(85, 1)].market_price);
+        assert_eq!(5000, by_level[&(85, 21)].market_price);
+
+        let mut by_bonuses = GroupedSummaries::new(BonusListSet);
+        by_bonuses.add_auction_file(&file, &game_data);
+        let by_bonuses = by_bonuses.into_summaries();
+        assert_eq!(2, by_bonuses[&(19019, vec![1, 2])].num_auctions);
+        assert_eq!(1, by_bonuses[&(19019, vec![])].num_auctions);
+    }
+
+    #[test]
+    fn groups_are_collected_in_the_same_pass_as_the_summary() {
+        let file = auctions::AuctionFile::from_json(AUCTIONS_JSON).unwrap();
+        let game_data = GameData::default();
+
+        let mut by_item = GroupedSummaries::new(|auction: &auctions::Auction, _: &GameData| {
+            Some(auction.item.id)
+        });
+        let mut by_breed = GroupedSummaries::new(PetBreed);
+        let summary = AuctionsSummary::from_reader_with_groups(
+            AUCTIONS_JSON.as_bytes(),
+            &game_data,
+            &Default::default(),
+            &mut [&mut by_item, &mut by_breed],
+        )
+        .unwrap();
+
+        assert_eq!(summary.item_summaries, by_item.into_summaries());
+
+        let species = &AuctionsSummary::from_auction_file(&file, &game_data).pet_summaries[&85];
+        let breed = &by_breed.into_summaries()[&(85, 4)];
+        assert_eq!(species.market_price, breed.market_price);
+        assert_eq!(species.num_auctions, breed.num_auctions);
+        // the bid only auction is of another breed
+        assert_eq!(1, species.bids.num_bid_only);
+        assert_eq!(0, breed.bids.num_bid_only);
+    }
+}
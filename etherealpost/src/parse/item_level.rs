@@ -49,6 +49,14 @@ impl ItemLevelCurve {
         curve
     }
 
+    /// The `(x, y)` coordinates of the curve, sorted by player level.
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        self.points
+            .iter()
+            .map(|point| (point.player_level, point.item_level))
+            .collect()
+    }
+
     /// Maps an the entire [Db2CurvePoints](`crate::wow::data_tables::Db2CurvePoints`) table
     /// into a mapping of curve IDs to an [`ItemLevelCurve`] wrapping the
     /// curve coordinates.
@@ -56,7 +64,7 @@ impl ItemLevelCurve {
         table
             .curve_ids
             .iter()
-            .map(|(curve_id, points)| (*curve_id, ItemLevelCurve::from_points(&points)))
+            .map(|(curve_id, points)| (*curve_id, ItemLevelCurve::from_points(points)))
             .collect()
     }
 
@@ -68,8 +76,8 @@ impl ItemLevelCurve {
     ///
     pub fn from_table(curve_id: &u32, table: &Db2CurvePoints) -> Option<ItemLevelCurve> {
         table
-            .points(&curve_id)
-            .map(|curve_points| ItemLevelCurve::from_points(&curve_points))
+            .points(curve_id)
+            .map(|curve_points| ItemLevelCurve::from_points(curve_points))
     }
 
     /// Using the item level curve points, calculates the effective item level
@@ -137,7 +145,35 @@ impl ItemLevelCurve {
     /// assert_eq!(146, ilvl_curve.calc_ilvl(&65));
     /// ```
     pub fn calc_ilvl(&self, looted_level: &u32) -> u32 {
-        let looted_level = *looted_level as f64;
+        match self.segment(*looted_level as f64) {
+            Segment::Point(point) => point.item_level as u32,
+            // interpolate: y = y0 + (x - x0) * ( (y1 - y0) / (x1 - x0) )
+            Segment::Between(prev, point) => {
+                (prev.item_level
+                    + (*looted_level as f64 - prev.player_level)
+                        * ((point.item_level - prev.item_level)
+                            / (point.player_level - prev.player_level))) as u32
+            }
+        }
+    }
+
+    /// The `(x, y)` coordinates which [calc_ilvl](`Self::calc_ilvl`) uses for the
+    /// player's level when the item was looted.
+    ///
+    /// This is a single point if the looted level is on the curve or past its end,
+    /// otherwise the two points the item level is interpolated between.
+    pub fn interpolation_points(&self, looted_level: &u32) -> Vec<(f64, f64)> {
+        match self.segment(*looted_level as f64) {
+            Segment::Point(point) => vec![(point.player_level, point.item_level)],
+            Segment::Between(prev, point) => vec![
+                (prev.player_level, prev.item_level),
+                (point.player_level, point.item_level),
+            ],
+        }
+    }
+
+    /// Finds the part of the curve which applies to a looted level.
+    fn segment(&self, looted_level: f64) -> Segment<'_> {
         let error_margin = 0.01f64;
 
         let mut prev = &self.points[0];
@@ -145,23 +181,27 @@ impl ItemLevelCurve {
         for point in &mut self.points.iter() {
             // if the player level is the looted level, we don't need to interpolate
             if (looted_level - point.player_level).abs() < error_margin {
-                return point.item_level as u32;
+                return Segment::Point(point);
             }
-            // interpolate: y = y0 + (x - x0) * ( (y1 - y0) / (x1 - x0) )
             if looted_level < point.player_level {
-                return (prev.item_level
-                    + (looted_level - prev.player_level)
-                        * ((point.item_level - prev.item_level)
-                            / (point.player_level - prev.player_level)))
-                    as u32;
+                return Segment::Between(prev, point);
             }
             prev = point;
         }
 
-        prev.item_level as u32
+        Segment::Point(prev)
     }
 }
 
+/// The part of an [`ItemLevelCurve`] which applies to a looted level.
+enum Segment<'c> {
+    /// The looted level is on this point, or past the end of the curve.
+    Point(&'c CurvePoint),
+
+    /// The looted level is between these two points.
+    Between(&'c CurvePoint, &'c CurvePoint),
+}
+
 pub type ItemLevelCurvePoints = HashMap<u32, ItemLevelCurve>;
 
 /// An individual curve point, which represents `(x, y)` coordinates on a graph,
@@ -201,6 +241,19 @@ mod tests {
         assert_eq!(33, curve.calc_ilvl(&60));
     }
 
+    #[test]
+    fn interpolation_points_match_calc_ilvl() {
+        let curve_points: Vec<(f64, f64)> =
+            vec![(1.0, 6.0), (25.0, 31.0), (26.0, 32.0), (27.0, 33.0)];
+        let curve = ItemLevelCurve::from_points(&curve_points);
+        assert_eq!(
+            vec![(1.0, 6.0), (25.0, 31.0)],
+            curve.interpolation_points(&7)
+        );
+        assert_eq!(vec![(26.0, 32.0)], curve.interpolation_points(&26));
+        assert_eq!(vec![(27.0, 33.0)], curve.interpolation_points(&60));
+    }
+
     #[test]
     fn calc_ilvl_curve_looted_level_lt_one_curve() {
         let curve_points: Vec<(f64, f64)> =
@@ -0,0 +1,500 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::auctions;
+use crate::parse::FallbackCounts;
+use crate::wow::GameData;
+
+/// The modifier type on an auction [Item](`crate::auctions::Item`) which holds the
+/// player's level when the item dropped.
+const DROP_LEVEL_MODIFIER_TYPE: u16 = 9;
+
+/// A step-by-step explanation of how the item level of a single auction
+/// [Item](`crate::auctions::Item`) is resolved.
+///
+/// This is the same resolution that places auctions into the
+/// [item_level_summaries](`crate::parse::AuctionsSummary::item_level_summaries`) of a
+/// summary, so when an item lands in an unexpected bucket, its trace shows why.
+///
+/// The `Display` implementation prints the trace as a readable, multi-line report.
+///
+/// # Example
+///
+/// ```rust
+/// use etherealpost::auctions::AuctionFile;
+/// use etherealpost::parse::{ItemLevelFallback, ItemLevelTrace};
+/// use etherealpost::wow::GameData;
+///
+/// let auction_file = AuctionFile::from_json(r#"{"auctions": [{"id": 1, "quantity": 1,
+///     "item": {"id": 19019}, "buyout": 9990000, "time_left": "LONG"}]}"#).unwrap();
+/// let trace = ItemLevelTrace::for_item(&auction_file.auctions[0].item, &GameData::default());
+///
+/// assert_eq!(1, trace.item_level);
+/// assert_eq!(vec![ItemLevelFallback::MissingBaseItemLevel], trace.fallbacks);
+/// println!("{}", trace);
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ItemLevelTrace {
+    /// The Item ID of the item.
+    pub item_id: u64,
+
+    /// The pet species the item is or teaches, if it is a pet.
+    ///
+    /// Summaries group pets by species rather than by item level, so the item level
+    /// of a pet is never used.
+    pub pet_species: Option<u32>,
+
+    /// Whether or not the item can be equipped. Bonuses and curves only apply to
+    /// equippable items, every other item uses its base item level.
+    pub equippable: bool,
+
+    /// The base item level of the item, if the game data knows the item.
+    pub base_ilvl: Option<u32>,
+
+    /// Every bonus ID on the item which maps to a curve or an item level adjustment,
+    /// in the order they appear on the item.
+    pub bonuses: Vec<BonusMatch>,
+
+    /// The curve chosen from the [bonuses](`Self::bonuses`). When several bonuses map
+    /// to a curve, the highest Curve ID wins.
+    pub curve_id: Option<u32>,
+
+    /// The player's level when the item dropped, from the modifier with a type of `9`.
+    pub drop_level: Option<u32>,
+
+    /// The `(x, y)` curve points the item level was interpolated from, if a curve
+    /// was used. See
+    /// [ItemLevelCurve::interpolation_points](`crate::parse::ItemLevelCurve::interpolation_points`).
+    pub interpolation_points: Vec<(f64, f64)>,
+
+    /// What the item level was resolved from.
+    pub source: ItemLevelSource,
+
+    /// Every fallback which was taken because the game data was missing something.
+    pub fallbacks: Vec<ItemLevelFallback>,
+
+    /// The resolved item level.
+    pub item_level: u32,
+}
+
+/// A bonus ID on an item which affects its item level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum BonusMatch {
+    /// The bonus maps to an item level curve.
+    Curve {
+        /// The bonus ID on the item.
+        bonus_id: u32,
+        /// The Curve ID the bonus maps to.
+        curve_id: u32,
+    },
+
+    /// The bonus adjusts the base item level.
+    Adjustment {
+        /// The bonus ID on the item.
+        bonus_id: u32,
+        /// The adjustment to the base item level, which could be negative.
+        adjustment: i32,
+    },
+}
+
+/// What the item level of an item was resolved from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ItemLevelSource {
+    /// Interpolated on a curve at the player's level when the item dropped.
+    Curve {
+        /// The Curve ID.
+        curve_id: u32,
+        /// The player's level when the item dropped.
+        drop_level: u32,
+    },
+
+    /// The base item level plus the sum of the item's adjustment bonuses.
+    Adjustment(i32),
+
+    /// The base item level, or `1` if the item has none.
+    Base,
+}
+
+/// A fallback taken while resolving an item level, because the game data didn't
+/// have everything needed to resolve it properly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ItemLevelFallback {
+    /// The item has no base item level in the game data, so `1` was used.
+    MissingBaseItemLevel,
+
+    /// The item has a curve bonus but no drop level modifier, so the base item
+    /// level was used.
+    MissingDropLevel,
+
+    /// The curve of the item isn't in the game data, so the base item level was used.
+    MissingCurve,
+}
+
+impl ItemLevelTrace {
+    /// Resolves the item level of an auction item, recording every step.
+    ///
+    /// This collects far more than the item level, so summaries use the leaner
+    /// [resolve_item_level](`resolve_item_level`) instead. Both resolve the item level
+    /// the same way.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - The item of an auction.
+    /// * `game_data` - The game data the item level is resolved with. See
+    ///   [GameData](`crate::wow::GameData`) for how to load it.
+    pub fn for_item(item: &auctions::Item, game_data: &GameData) -> ItemLevelTrace {
+        let db2_bonuses = &game_data.item_bonuses;
+        let bonus_ids: &[u32] = item.bonus_lists.as_deref().unwrap_or(&[]);
+
+        let mut bonuses = Vec::new();
+        for bonus_id in bonus_ids {
+            if let Some(curve_id) = db2_bonuses.curve_id(bonus_id) {
+                bonuses.push(BonusMatch::Curve {
+                    bonus_id: *bonus_id,
+                    curve_id,
+                });
+            }
+            if let Some(adjustment) = db2_bonuses.ilvl_adjustment(bonus_id) {
+                bonuses.push(BonusMatch::Adjustment {
+                    bonus_id: *bonus_id,
+                    adjustment,
+                });
+            }
+        }
+
+        let mut fallbacks = Vec::new();
+        let (item_level, source) = resolve(item, game_data, |fallback, _| fallbacks.push(fallback));
+
+        let interpolation_points = match source {
+            ItemLevelSource::Curve {
+                curve_id,
+                drop_level,
+            } => game_data.curve_points[&curve_id].interpolation_points(&drop_level),
+            _ => Vec::new(),
+        };
+
+        ItemLevelTrace {
+            item_id: item.id,
+            pet_species: item
+                .pet()
+                .map(|pet_cage| pet_cage.species)
+                .or_else(|| game_data.pet_species(&item.id)),
+            equippable: game_data.is_equippable(&item.id),
+            base_ilvl: game_data.known_base_ilvl(&item.id),
+            bonuses,
+            curve_id: db2_bonuses.resolve_curve_id(bonus_ids),
+            drop_level: drop_level(item),
+            interpolation_points,
+            source,
+            fallbacks,
+            item_level,
+        }
+    }
+}
+
+/// Resolves the item level of an auction item, counting every fallback taken.
+///
+/// This is the resolution that places auctions into the
+/// [item_level_summaries](`crate::parse::AuctionsSummary::item_level_summaries`) of a
+/// summary. Use [ItemLevelTrace::for_item](`ItemLevelTrace::for_item`) to see how an
+/// item level was resolved.
+///
+/// # Arguments
+///
+/// * `item` - The item of an auction.
+/// * `game_data` - The game data the item level is resolved with.
+/// * `fallbacks` - The counts which any fallbacks taken are added to.
+pub fn resolve_item_level(
+    item: &auctions::Item,
+    game_data: &GameData,
+    fallbacks: &mut FallbackCounts,
+) -> u32 {
+    let (item_level, _) = resolve(item, game_data, |fallback, curve_id| {
+        fallbacks.record(fallback, item.id, curve_id)
+    });
+    item_level
+}
+
+/// Resolves the item level of an item and what it was resolved from, calling
+/// `fallback` with every fallback taken and the item's Curve ID, if it has one.
+fn resolve<F>(
+    item: &auctions::Item,
+    game_data: &GameData,
+    mut fallback: F,
+) -> (u32, ItemLevelSource)
+where
+    F: FnMut(ItemLevelFallback, Option<u32>),
+{
+    if game_data.is_equippable(&item.id) {
+        let bonus_ids: &[u32] = item.bonus_lists.as_deref().unwrap_or(&[]);
+        match game_data.item_bonuses.resolve_curve_id(bonus_ids) {
+            Some(curve_id) => {
+                let curve = game_data.curve_points.get(&curve_id);
+                match (drop_level(item), curve) {
+                    (Some(drop_level), Some(curve)) => {
+                        return (
+                            curve.calc_ilvl(&drop_level),
+                            ItemLevelSource::Curve {
+                                curve_id,
+                                drop_level,
+                            },
+                        );
+                    }
+                    (None, _) => fallback(ItemLevelFallback::MissingDropLevel, Some(curve_id)),
+                    // the curve is not in the table that was pre-cached
+                    (Some(_), None) => fallback(ItemLevelFallback::MissingCurve, Some(curve_id)),
+                }
+            }
+            // there's no curve associated with this item, check for a standard ilvl adjustment
+            None => {
+                if let Some(adjustment) = game_data.item_bonuses.resolve_ilvl_adjustment(bonus_ids)
+                {
+                    let base_level = base_item_level(item, game_data, &mut fallback) as i32;
+                    return (
+                        (base_level + adjustment) as u32,
+                        ItemLevelSource::Adjustment(adjustment),
+                    );
+                }
+            }
+        }
+    }
+
+    (
+        base_item_level(item, game_data, &mut fallback),
+        ItemLevelSource::Base,
+    )
+}
+
+/// The base item level, or `1` with a fallback if the item has none.
+fn base_item_level<F>(item: &auctions::Item, game_data: &GameData, fallback: &mut F) -> u32
+where
+    F: FnMut(ItemLevelFallback, Option<u32>),
+{
+    game_data.known_base_ilvl(&item.id).unwrap_or_else(|| {
+        fallback(ItemLevelFallback::MissingBaseItemLevel, None);
+        1
+    })
+}
+
+/// Resolves the player's level when the item dropped, if
+/// this is an item which has such data.
+fn drop_level(item: &auctions::Item) -> Option<u32> {
+    item.modifiers
+        .as_ref()?
+        .iter()
+        .find(|modifier| modifier.modifier_type == DROP_LEVEL_MODIFIER_TYPE)
+        .map(|modifier| modifier.value as u32)
+}
+
+impl fmt::Display for ItemLevelTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "item {}: item level {}", self.item_id, self.item_level)?;
+        if let Some(species) = self.pet_species {
+            writeln!(
+                f,
+                "  pet species {}, grouped by species instead of item level",
+                species
+            )?;
+        }
+        writeln!(f, "  equippable: {}", self.equippable)?;
+        match self.base_ilvl {
+            Some(ilvl) => writeln!(f, "  base item level: {}", ilvl)?,
+            None => writeln!(f, "  base item level: unknown")?,
+        }
+
+        if self.bonuses.is_empty() {
+            writeln!(f, "  bonuses: none which affect the item level")?;
+        } else {
+            writeln!(f, "  bonuses:")?;
+            for bonus in &self.bonuses {
+                match bonus {
+                    BonusMatch::Curve { bonus_id, curve_id } => {
+                        writeln!(f, "    {} -> curve {}", bonus_id, curve_id)?
+                    }
+                    BonusMatch::Adjustment {
+                        bonus_id,
+                        adjustment,
+                    } => writeln!(f, "    {} -> adjustment {:+}", bonus_id, adjustment)?,
+                }
+            }
+        }
+        if let Some(curve_id) = self.curve_id {
+            writeln!(f, "  curve (highest Curve ID): {}", curve_id)?;
+        }
+        match self.drop_level {
+            Some(level) => writeln!(f, "  drop level: {}", level)?,
+            None => writeln!(f, "  drop level: none")?,
+        }
+        if !self.interpolation_points.is_empty() {
+            let points: Vec<String> = self
+                .interpolation_points
+                .iter()
+                .map(|(x, y)| format!("({}, {})", x, y))
+                .collect();
+            writeln!(f, "  interpolated between: {}", points.join(", "))?;
+        }
+
+        match self.source {
+            ItemLevelSource::Curve {
+                curve_id,
+                drop_level,
+            } => writeln!(f, "  source: curve {} at level {}", curve_id, drop_level)?,
+            ItemLevelSource::Adjustment(adjustment) => {
+                writeln!(f, "  source: base item level {:+}", adjustment)?
+            }
+            ItemLevelSource::Base => writeln!(f, "  source: base item level")?,
+        }
+        for fallback in &self.fallbacks {
+            let reason = match fallback {
+                ItemLevelFallback::MissingBaseItemLevel => {
+                    "no base item level, fell back to item level 1"
+                }
+                ItemLevelFallback::MissingDropLevel => {
+                    "curve without a drop level, fell back to the base item level"
+                }
+                ItemLevelFallback::MissingCurve => {
+                    "curve missing from the game data, fell back to the base item level"
+                }
+            };
+            writeln!(f, "  fallback: {}", reason)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::ItemLevelCurve;
+
+    fn game_data() -> GameData {
+        let mut game_data = GameData::default();
+        game_data.base_ilvls.insert(19019, 80);
+        game_data.equippable_items.insert(19019);
+        game_data.item_bonuses.curve_ids.insert(6707, 1748);
+        game_data.item_bonuses.curve_ids.insert(6708, 1749);
+        game_data.item_bonuses.ilvl_adjustments.insert(58, 10);
+        game_data.item_bonuses.ilvl_adjustments.insert(59, -2);
+        game_data.curve_points.insert(
+            1749,
+            ItemLevelCurve::from_points(&[(1.0, 6.0), (25.0, 31.0), (60.0, 146.0)]),
+        );
+        game_data
+    }
+
+    fn item(json: &str) -> auctions::Item {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn explains_curves() {
+        let item = item(
+            r#"{"id": 19019, "bonus_lists": [58, 6708, 6707],
+                "modifiers": [{"type": 28, "value": 1}, {"type": 9, "value": 37}]}"#,
+        );
+        let trace = ItemLevelTrace::for_item(&item, &game_data());
+
+        assert_eq!(
+            vec![
+                BonusMatch::Adjustment {
+                    bonus_id: 58,
+                    adjustment: 10
+                },
+                BonusMatch::Curve {
+                    bonus_id: 6708,
+                    curve_id: 1749
+                },
+                BonusMatch::Curve {
+                    bonus_id: 6707,
+                    curve_id: 1748
+                },
+            ],
+            trace.bonuses
+        );
+        assert_eq!(Some(1749), trace.curve_id);
+        assert_eq!(Some(37), trace.drop_level);
+        assert_eq!(
+            vec![(25.0, 31.0), (60.0, 146.0)],
+            trace.interpolation_points
+        );
+        assert_eq!(
+            ItemLevelSource::Curve {
+                curve_id: 1749,
+                drop_level: 37
+            },
+            trace.source
+        );
+        assert!(trace.fallbacks.is_empty());
+        assert_eq!(70, trace.item_level);
+    }
+
+    #[test]
+    fn explains_adjustments() {
+        let item = item(r#"{"id": 19019, "bonus_lists": [58, 59, 1]}"#);
+        let trace = ItemLevelTrace::for_item(&item, &game_data());
+
+        assert_eq!(None, trace.curve_id);
+        assert_eq!(ItemLevelSource::Adjustment(8), trace.source);
+        assert_eq!(88, trace.item_level);
+    }
+
+    #[test]
+    fn explains_fallbacks() {
+        let game_data = game_data();
+
+        let missing_curve = item(
+            r#"{"id": 19019, "bonus_lists": [6707], "modifiers": [{"type": 9, "value": 50}]}"#,
+        );
+        let trace = ItemLevelTrace::for_item(&missing_curve, &game_data);
+        assert_eq!(vec![ItemLevelFallback::MissingCurve], trace.fallbacks);
+        assert_eq!(ItemLevelSource::Base, trace.source);
+        assert_eq!(80, trace.item_level);
+
+        let missing_drop_level = item(r#"{"id": 19019, "bonus_lists": [6708]}"#);
+        let trace = ItemLevelTrace::for_item(&missing_drop_level, &game_data);
+        assert_eq!(vec![ItemLevelFallback::MissingDropLevel], trace.fallbacks);
+        assert!(trace.interpolation_points.is_empty());
+        assert_eq!(80, trace.item_level);
+
+        let unknown = item(r#"{"id": 2589, "bonus_lists": [58]}"#);
+        let trace = ItemLevelTrace::for_item(&unknown, &game_data);
+        assert!(!trace.equippable);
+        assert_eq!(
+            vec![ItemLevelFallback::MissingBaseItemLevel],
+            trace.fallbacks
+        );
+        assert_eq!(1, trace.item_level);
+        assert!(trace
+            .to_string()
+            .contains("no base item level, fell back to item level 1"));
+    }
+
+    #[test]
+    fn resolve_item_level_agrees_with_the_trace() {
+        let game_data = game_data();
+        let items = [
+            r#"{"id": 19019, "bonus_lists": [6708], "modifiers": [{"type": 9, "value": 37}]}"#,
+            r#"{"id": 19019, "bonus_lists": [58, 59]}"#,
+            r#"{"id": 19019, "bonus_lists": [6707], "modifiers": [{"type": 9, "value": 50}]}"#,
+            r#"{"id": 19019, "bonus_lists": [6708]}"#,
+            r#"{"id": 2589, "bonus_lists": [58]}"#,
+        ];
+
+        let mut fallbacks = FallbackCounts::default();
+        for json in &items {
+            let item = item(json);
+            assert_eq!(
+                ItemLevelTrace::for_item(&item, &game_data).item_level,
+                resolve_item_level(&item, &game_data, &mut fallbacks)
+            );
+        }
+
+        let expected = FallbackCounts {
+            missing_base_ilvl: 1,
+            missing_drop_level: 1,
+            missing_curve: 1,
+        };
+        assert_eq!(expected, fallbacks);
+    }
+}
@@ -1,7 +1,31 @@
+pub mod commodities;
+pub mod grouping;
 pub mod item_level;
+pub mod item_level_trace;
+pub mod region;
+pub mod sales;
 pub mod summary;
+pub mod variant;
 
+pub use commodities::{CommoditiesSummary, MarketSource, RealmMarket};
+pub use grouping::{
+    AuctionCollector, AuctionGrouping, BonusListSet, GroupedSummaries, ItemContext, PetBreed,
+    PetLevelBracket,
+};
 pub use item_level::ItemLevelCurve;
 pub use item_level::ItemLevelCurvePoints;
+pub use item_level_trace::{
+    resolve_item_level, BonusMatch, ItemLevelFallback, ItemLevelSource, ItemLevelTrace,
+};
+pub use region::{RegionAggregator, RegionItemSummary, RegionSummary};
+pub use sales::{AuctionSnapshot, SalesTracker, SnapshotDiff};
 pub use summary::AuctionsSummary;
+pub use summary::BidSummary;
+pub use summary::ExtendedSummary;
+pub use summary::FallbackCounts;
 pub use summary::ItemSummary;
+pub use summary::NamedEstimator;
+pub use summary::OwnedAuctionsSummary;
+pub use summary::SummaryKey;
+pub use summary::SummaryOptions;
+pub use variant::{ItemVariant, ParseVariantError, VariantGrouping};
@@ -0,0 +1,373 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::parse::{AuctionsSummary, ItemSummary, ItemVariant, OwnedAuctionsSummary, SummaryKey};
+use crate::stats;
+
+/// Region-wide statistics for a grouping of items, combined from the
+/// [`ItemSummary`] of every connected realm the grouping is listed on.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RegionItemSummary {
+    /// The average of the per-realm market prices, where every realm counts equally
+    /// regardless of how many auctions it has.
+    pub market_avg: u64,
+
+    /// The average of the per-realm market prices, weighted by the total quantity
+    /// available on each realm.
+    pub qty_weighted_market_price: u64,
+
+    /// The median of the per-realm market prices. With an even number of realms, this
+    /// is the average of the two middle prices.
+    pub median_market_price: u64,
+
+    /// The population standard deviation of the per-realm market prices. This is `0`
+    /// when the grouping is only listed on a single realm.
+    pub std_dev: f64,
+
+    /// The lowest minimum buyout across all realms.
+    pub min_buyout: u64,
+
+    /// The total quantity available across all realms.
+    pub total_qty: u64,
+
+    /// The total number of auctions across all realms.
+    pub num_auctions: u64,
+
+    /// The number of connected realms the grouping is listed on.
+    pub num_realms: u64,
+}
+
+/// A summary of an entire region, combined from the summaries of its connected realms.
+///
+/// This has the same layout as an [`OwnedAuctionsSummary`], so region-wide prices are
+/// available for items, item levels, item variants and pet species alike.
+///
+/// Use a [`RegionAggregator`] to build one.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct RegionSummary {
+    /// The number of connected realms which were combined into this summary.
+    pub num_realms: u64,
+
+    /// Mapping of **Item ID** to the region-wide summary for those items.
+    pub item_summaries: HashMap<u64, RegionItemSummary>,
+
+    /// Nested map of **Item ID -> Item Level** to the region-wide summary for
+    /// the items sharing the same item level.
+    pub item_level_summaries: HashMap<u64, HashMap<u32, RegionItemSummary>>,
+
+    /// Nested map of **Item ID -> Item Variant** to the region-wide summary for
+    /// the items sharing the same variant.
+    #[serde(default)]
+    pub item_variant_summaries: HashMap<u64, HashMap<ItemVariant, RegionItemSummary>>,
+
+    /// Mapping of **Pet Species ID** to the region-wide summary for the pets of
+    /// that species.
+    pub pet_summaries: HashMap<u32, RegionItemSummary>,
+}
+
+impl RegionSummary {
+    /// Combines the summaries of every connected realm in a region.
+    pub fn from_realms<'r, I>(realms: I) -> Self
+    where
+        I: IntoIterator<Item = &'r OwnedAuctionsSummary>,
+    {
+        let mut aggregator = RegionAggregator::new();
+        for realm in realms {
+            aggregator.add_realm(realm);
+        }
+        aggregator.finish()
+    }
+
+    /// Looks up a single region-wide summary by its key.
+    pub fn get(&self, key: &SummaryKey) -> Option<&RegionItemSummary> {
+        match key {
+            SummaryKey::Item(item_id) => self.item_summaries.get(item_id),
+            SummaryKey::ItemLevel(item_id, ilvl) => self
+                .item_level_summaries
+                .get(item_id)
+                .and_then(|levels| levels.get(ilvl)),
+            SummaryKey::ItemVariant(item_id, variant) => self
+                .item_variant_summaries
+                .get(item_id)
+                .and_then(|variants| variants.get(variant)),
+            SummaryKey::Pet(species_id) => self.pet_summaries.get(species_id),
+        }
+    }
+
+    /// The fraction of the region's connected realms a grouping is listed on,
+    /// between `0.0` and `1.0`.
+    pub fn realm_share(&self, summary: &RegionItemSummary) -> f64 {
+        if self.num_realms == 0 {
+            0.0
+        } else {
+            summary.num_realms as f64 / self.num_realms as f64
+        }
+    }
+}
+
+/// Collects the summaries of connected realms one at a time and combines them
+/// into a [`RegionSummary`].
+///
+/// Only the small per-realm [`ItemSummary`] values are retained, so realms can be
+/// summarized, added and dropped one after the other.
+///
+/// # Example
+///
+/// ```rust
+/// use etherealpost::parse::{ItemSummary, OwnedAuctionsSummary, RegionAggregator};
+///
+/// let mut aggregator = RegionAggregator::new();
+/// for (market_price, min_buyout) in &[(100, 90), (300, 250)] {
+///     let mut realm = OwnedAuctionsSummary::default();
+///     realm.item_summaries.insert(19019, ItemSummary {
+///         market_price: *market_price,
+///         min_buyout: *min_buyout,
+///         total_qty: 1,
+///         num_auctions: 1,
+///         ..ItemSummary::default()
+///     });
+///     aggregator.add_realm(&realm);
+/// }
+///
+/// let region = aggregator.finish();
+/// let summary = region.item_summaries.get(&19019).unwrap();
+/// assert_eq!(200, summary.market_avg);
+/// assert_eq!(90, summary.min_buyout);
+/// assert_eq!(2, summary.num_realms);
+/// ```
+#[derive(Default)]
+pub struct RegionAggregator {
+    num_realms: u64,
+    items: HashMap<u64, Vec<ItemSummary>>,
+    item_levels: HashMap<u64, HashMap<u32, Vec<ItemSummary>>>,
+    item_variants: HashMap<u64, HashMap<ItemVariant, Vec<ItemSummary>>>,
+    pets: HashMap<u32, Vec<ItemSummary>>,
+}
+
+impl RegionAggregator {
+    /// Creates an aggregator which hasn't seen any realms.
+    pub fn new() -> Self {
+        RegionAggregator::default()
+    }
+
+    /// Adds the summary of one connected realm.
+    pub fn add_realm(&mut self, realm: &OwnedAuctionsSummary) {
+        self.add(
+            &realm.item_summaries,
+            &realm.item_level_summaries,
+            &realm.item_variant_summaries,
+            &realm.pet_summaries,
+        );
+    }
+
+    /// Adds the summary of one connected realm, straight from an [`AuctionsSummary`].
+    pub fn add_auctions_summary(&mut self, realm: &AuctionsSummary) {
+        self.add(
+            &realm.item_summaries,
+            &realm.item_level_summaries,
+            &realm.item_variant_summaries,
+            &realm.pet_summaries,
+        );
+    }
+
+    fn add(
+        &mut self,
+        items: &HashMap<u64, ItemSummary>,
+        item_levels: &HashMap<u64, HashMap<u32, ItemSummary>>,
+        item_variants: &HashMap<u64, HashMap<ItemVariant, ItemSummary>>,
+        pets: &HashMap<u32, ItemSummary>,
+    ) {
+        self.num_realms += 1;
+        RegionAggregator::collect(&mut self.items, items);
+        for (item_id, levels) in item_levels {
+            RegionAggregator::collect(self.item_levels.entry(*item_id).or_default(), levels);
+        }
+        for (item_id, variants) in item_variants {
+            RegionAggregator::collect(self.item_variants.entry(*item_id).or_default(), variants);
+        }
+        RegionAggregator::collect(&mut self.pets, pets);
+    }
+
+    fn collect<T: Copy + Eq + Hash>(
+        into: &mut HashMap<T, Vec<ItemSummary>>,
+        from: &HashMap<T, ItemSummary>,
+    ) {
+        for (key, summary) in from {
+            into.entry(*key).or_default().push(summary.clone());
+        }
+    }
+
+    /// Combines every realm added so far into a [`RegionSummary`].
+    pub fn finish(self) -> RegionSummary {
+        RegionSummary {
+            num_realms: self.num_realms,
+            item_summaries: RegionAggregator::combine_all(self.items),
+            item_level_summaries: self
+                .item_levels
+                .into_iter()
+                .map(|(item_id, levels)| (item_id, RegionAggregator::combine_all(levels)))
+                .collect(),
+            item_variant_summaries: self
+                .item_variants
+                .into_iter()
+                .map(|(item_id, variants)| (item_id, RegionAggregator::combine_all(variants)))
+                .collect(),
+            pet_summaries: RegionAggregator::combine_all(self.pets),
+        }
+    }
+
+    fn combine_all<T: Eq + Hash>(
+        summaries: HashMap<T, Vec<ItemSummary>>,
+    ) -> HashMap<T, RegionItemSummary> {
+        summaries
+            .into_iter()
+            .map(|(key, realms)| (key, RegionAggregator::combine(&realms)))
+            .collect()
+    }
+
+    /// Combines the summaries of a single grouping across realms.
+    ///
+    /// This is only ever called with at least one summary.
+    fn combine(realms: &[ItemSummary]) -> RegionItemSummary {
+        let num_realms = realms.len() as u64;
+        let mut market_prices: Vec<u64> = realms.iter().map(|s| s.market_price).collect();
+        market_prices.sort_unstable();
+
+        let market_sum: u64 = market_prices.iter().sum();
+        let total_qty: u64 = realms.iter().map(|s| s.total_qty).sum();
+        let qty_weighted_market_price = if total_qty == 0 {
+            market_sum / num_realms
+        } else {
+            let weighted_sum: u128 = realms
+                .iter()
+                .map(|s| s.market_price as u128 * s.total_qty as u128)
+                .sum();
+            (weighted_sum / total_qty as u128) as u64
+        };
+
+        let mid = market_prices.len() / 2;
+        let median_market_price = if market_prices.len().is_multiple_of(2) {
+            (market_prices[mid - 1] + market_prices[mid]) / 2
+        } else {
+            market_prices[mid]
+        };
+
+        RegionItemSummary {
+            market_avg: market_sum / num_realms,
+            qty_weighted_market_price,
+            median_market_price,
+            std_dev: stats::std_dev(&market_prices, true).unwrap_or(0.0),
+            min_buyout: realms.iter().map(|s| s.min_buyout).min().unwrap(),
+            total_qty,
+            num_auctions: realms.iter().map(|s| s.num_auctions).sum(),
+            num_realms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(market_price: u64, min_buyout: u64, total_qty: u64) -> ItemSummary {
+        ItemSummary {
+            market_price,
+            min_buyout,
+            total_qty,
+            num_auctions: 1,
+            ..ItemSummary::default()
+        }
+    }
+
+    fn realm(prices: &[(u64, u64, u64)]) -> OwnedAuctionsSummary {
+        let mut realm = OwnedAuctionsSummary::default();
+        for (market_price, min_buyout, total_qty) in prices {
+            realm
+                .item_summaries
+                .insert(19019, summary(*market_price, *min_buyout, *total_qty));
+        }
+        realm
+    }
+
+    #[test]
+    fn combines_item_summaries_across_realms() {
+        let realms = vec![
+            realm(&[(100, 80, 1)]),
+            realm(&[(200, 150, 1)]),
+            realm(&[(600, 500, 2)]),
+        ];
+        let region = RegionSummary::from_realms(&realms);
+        assert_eq!(3, region.num_realms);
+
+        let item = region.get(&SummaryKey::Item(19019)).unwrap();
+        assert_eq!(300, item.market_avg);
+        // (100 + 200 + 600 * 2) / 4
+        assert_eq!(375, item.qty_weighted_market_price);
+        assert_eq!(200, item.median_market_price);
+        assert_eq!(80, item.min_buyout);
+        assert_eq!(4, item.total_qty);
+        assert_eq!(3, item.num_auctions);
+        assert_eq!(3, item.num_realms);
+        // sqrt(((-200)^2 + (-100)^2 + 300^2) / 3)
+        assert_relative_eq!(216.02469, item.std_dev, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn median_of_even_realms_averages_middle_prices() {
+        let realms = vec![
+            realm(&[(100, 100, 1)]),
+            realm(&[(400, 100, 1)]),
+            realm(&[(200, 100, 1)]),
+            realm(&[(900, 100, 1)]),
+        ];
+        let region = RegionSummary::from_realms(&realms);
+        assert_eq!(
+            300,
+            region
+                .item_summaries
+                .get(&19019)
+                .unwrap()
+                .median_market_price
+        );
+    }
+
+    #[test]
+    fn keeps_item_level_and_pet_breakdowns() {
+        let mut first = OwnedAuctionsSummary::default();
+        first
+            .item_level_summaries
+            .entry(2000)
+            .or_default()
+            .insert(200, summary(1000, 900, 1));
+        first.pet_summaries.insert(39, summary(50, 40, 1));
+
+        let mut second = OwnedAuctionsSummary::default();
+        second
+            .item_level_summaries
+            .entry(2000)
+            .or_default()
+            .insert(200, summary(3000, 2500, 1));
+        second
+            .item_level_summaries
+            .entry(2000)
+            .or_default()
+            .insert(210, summary(5000, 5000, 1));
+
+        let region = RegionSummary::from_realms(vec![&first, &second]);
+
+        let ilvl_200 = region.get(&SummaryKey::ItemLevel(2000, 200)).unwrap();
+        assert_eq!(2000, ilvl_200.market_avg);
+        assert_eq!(900, ilvl_200.min_buyout);
+        assert_eq!(2, ilvl_200.num_realms);
+
+        let ilvl_210 = region.get(&SummaryKey::ItemLevel(2000, 210)).unwrap();
+        assert_eq!(1, ilvl_210.num_realms);
+        assert_relative_eq!(0.5, region.realm_share(ilvl_210));
+
+        let pet = region.get(&SummaryKey::Pet(39)).unwrap();
+        assert_eq!(50, pet.market_avg);
+        assert_relative_eq!(0.0, pet.std_dev);
+        assert!(region.get(&SummaryKey::Item(2000)).is_none());
+    }
+}
@@ -0,0 +1,465 @@
+//! Inference of sales from consecutive auction snapshots.
+//!
+//! The Auction House API only reports what is currently listed and never reports
+//! what sold. However, [Auction IDs](`crate::auctions::Auction::id`) are stable for the
+//! lifetime of an auction on a connected realm, so comparing two snapshots taken some
+//! time apart shows exactly which auctions disappeared in between.
+//!
+//! An auction disappears for one of three reasons: it sold, it was cancelled, or it
+//! expired. The [`TimeLeft`](`crate::auctions::TimeLeft`) bucket of an auction puts
+//! a lower bound on when it can expire on its own. If it disappeared before that,
+//! it is counted as **likely sold**. Otherwise it is counted as
+//! **likely cancelled or expired**. Cancellations before the expiry bound can't be
+//! told apart from sales, so sale counts are an upper estimate.
+//!
+//! When auctions are followed across more than two snapshots with a [`SalesTracker`],
+//! the bucket transitions of each auction are combined, which narrows the expiry
+//! bound over time. For example, an auction seen as `VERY_LONG` and later as `MEDIUM`
+//! still can't expire until 12 hours after it was first seen.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::auctions;
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// A single auction as it was seen in an [`AuctionSnapshot`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListedAuction {
+    /// The ID of the item being auctioned.
+    pub item_id: u64,
+
+    /// The quantity (stack size) of the auction.
+    pub quantity: u64,
+
+    /// The time left bucket of the auction when the snapshot was taken.
+    pub time_left: auctions::TimeLeft,
+
+    /// The earliest time, in seconds since the Unix epoch, at which the auction
+    /// could have expired on its own.
+    pub earliest_expiry: u64,
+}
+
+/// The minimal information about every auction in a snapshot which is needed to
+/// infer sales, keyed by **Auction ID**.
+///
+/// This is much smaller than a full [AuctionFile](`crate::auctions::AuctionFile`),
+/// so the previous snapshot of a connected realm can cheaply be kept around until
+/// the next one is fetched.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuctionSnapshot {
+    /// When the snapshot was taken, in seconds since the Unix epoch.
+    pub timestamp: u64,
+
+    /// Mapping of **Auction ID** to the auction.
+    pub auctions: HashMap<u64, ListedAuction>,
+}
+
+impl AuctionSnapshot {
+    /// Creates an empty snapshot taken at `timestamp`.
+    pub fn new(timestamp: u64) -> Self {
+        AuctionSnapshot {
+            timestamp,
+            auctions: HashMap::new(),
+        }
+    }
+
+    /// Builds a snapshot of every auction in an [AuctionFile](`crate::auctions::AuctionFile`)
+    /// fetched at `timestamp`.
+    pub fn from_auction_file(timestamp: u64, auction_file: &auctions::AuctionFile) -> Self {
+        let mut snapshot = AuctionSnapshot::new(timestamp);
+        for auction in &auction_file.auctions {
+            snapshot.insert(auction);
+        }
+        snapshot
+    }
+
+    /// Builds a snapshot directly from a reader which produces the JSON of an
+    /// [AuctionFile](`crate::auctions::AuctionFile`), without holding the whole
+    /// file in memory.
+    pub fn from_reader<R: Read>(timestamp: u64, reader: R) -> Result<Self, serde_json::Error> {
+        let mut snapshot = AuctionSnapshot::new(timestamp);
+        auctions::AuctionFile::for_each_auction(reader, |auction| snapshot.insert(&auction))?;
+        Ok(snapshot)
+    }
+
+    /// Adds an auction to the snapshot.
+    pub fn insert(&mut self, auction: &auctions::Auction) {
+        self.auctions.insert(
+            auction.id,
+            ListedAuction {
+                item_id: auction.item.id,
+                quantity: auction.quantity as u64,
+                time_left: auction.time_left,
+                earliest_expiry: self.timestamp + auction.time_left.min_remaining_secs(),
+            },
+        );
+    }
+
+    /// Tightens the expiry bounds of the auctions in this snapshot with what was
+    /// already known about them in an earlier snapshot.
+    pub fn carry_forward(&mut self, previous: &AuctionSnapshot) {
+        for (id, listed) in self.auctions.iter_mut() {
+            if let Some(earlier) = previous.auctions.get(id) {
+                listed.earliest_expiry = listed.earliest_expiry.max(earlier.earliest_expiry);
+            }
+        }
+    }
+
+    /// The number of auctions in the snapshot.
+    pub fn len(&self) -> usize {
+        self.auctions.len()
+    }
+
+    /// Whether the snapshot contains no auctions.
+    pub fn is_empty(&self) -> bool {
+        self.auctions.is_empty()
+    }
+}
+
+/// The likely reason an auction disappeared between two snapshots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Disappearance {
+    /// The auction disappeared before it could have expired, so it was most likely
+    /// bought (or, less commonly, cancelled).
+    LikelySold,
+
+    /// The auction could have run out of time, so it either expired or was cancelled.
+    LikelyCancelledOrExpired,
+}
+
+/// An auction which was present in one snapshot but gone in the next.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VanishedAuction {
+    /// The ID of the auction.
+    pub auction_id: u64,
+
+    /// The ID of the item which was being auctioned.
+    pub item_id: u64,
+
+    /// The quantity (stack size) of the auction.
+    pub quantity: u64,
+
+    /// Why the auction most likely disappeared.
+    pub outcome: Disappearance,
+}
+
+/// The difference between two consecutive snapshots of the same connected realm.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnapshotDiff {
+    /// The number of seconds between the two snapshots.
+    pub elapsed_secs: u64,
+
+    /// Every auction in the earlier snapshot which is missing from the later one.
+    pub vanished: Vec<VanishedAuction>,
+
+    /// The number of auctions in the later snapshot which weren't in the earlier one.
+    pub new_auctions: usize,
+}
+
+impl SnapshotDiff {
+    /// Compares two snapshots of the same connected realm and classifies every
+    /// auction which disappeared in between.
+    ///
+    /// # Arguments
+    ///
+    /// * `previous` - The earlier of the two snapshots.
+    /// * `current` - The later of the two snapshots.
+    pub fn between(previous: &AuctionSnapshot, current: &AuctionSnapshot) -> SnapshotDiff {
+        let mut vanished: Vec<VanishedAuction> = previous
+            .auctions
+            .iter()
+            .filter(|(id, _)| !current.auctions.contains_key(id))
+            .map(|(id, listed)| VanishedAuction {
+                auction_id: *id,
+                item_id: listed.item_id,
+                quantity: listed.quantity,
+                outcome: if current.timestamp < listed.earliest_expiry {
+                    Disappearance::LikelySold
+                } else {
+                    Disappearance::LikelyCancelledOrExpired
+                },
+            })
+            .collect();
+        vanished.sort_by_key(|v| v.auction_id);
+
+        let new_auctions = current
+            .auctions
+            .keys()
+            .filter(|id| !previous.auctions.contains_key(id))
+            .count();
+
+        SnapshotDiff {
+            elapsed_secs: current.timestamp.saturating_sub(previous.timestamp),
+            vanished,
+            new_auctions,
+        }
+    }
+
+    /// Totals the vanished auctions of this diff per **Item ID**.
+    pub fn item_estimates(&self) -> HashMap<u64, SaleEstimate> {
+        let mut estimates: HashMap<u64, SaleEstimate> = HashMap::new();
+        for vanished in &self.vanished {
+            let estimate = estimates.entry(vanished.item_id).or_default();
+            estimate.add(vanished);
+            estimate.observed_secs = self.elapsed_secs;
+        }
+        estimates
+    }
+}
+
+/// Sale counts for a single item over some period of observation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SaleEstimate {
+    /// The number of auctions which likely sold.
+    pub sold_auctions: u64,
+
+    /// The total quantity of the auctions which likely sold.
+    pub sold_quantity: u64,
+
+    /// The number of auctions which were likely cancelled or expired.
+    pub expired_auctions: u64,
+
+    /// The total quantity of the auctions which were likely cancelled or expired.
+    pub expired_quantity: u64,
+
+    /// The number of seconds the counts were observed over.
+    pub observed_secs: u64,
+}
+
+impl SaleEstimate {
+    fn add(&mut self, vanished: &VanishedAuction) {
+        match vanished.outcome {
+            Disappearance::LikelySold => {
+                self.sold_auctions += 1;
+                self.sold_quantity += vanished.quantity;
+            }
+            Disappearance::LikelyCancelledOrExpired => {
+                self.expired_auctions += 1;
+                self.expired_quantity += vanished.quantity;
+            }
+        }
+    }
+
+    /// The fraction of auctions which sold out of all that disappeared, between
+    /// `0.0` and `1.0`, or `None` if no auctions disappeared.
+    pub fn sale_rate(&self) -> Option<f64> {
+        let total = self.sold_auctions + self.expired_auctions;
+        if total == 0 {
+            None
+        } else {
+            Some(self.sold_auctions as f64 / total as f64)
+        }
+    }
+
+    /// The average quantity sold per day, or `None` if nothing has been observed yet.
+    pub fn sold_per_day(&self) -> Option<f64> {
+        if self.observed_secs == 0 {
+            None
+        } else {
+            Some(self.sold_quantity as f64 * SECONDS_PER_DAY as f64 / self.observed_secs as f64)
+        }
+    }
+}
+
+/// Follows the snapshots of a single connected realm over time and accumulates
+/// per-item [`SaleEstimate`]s.
+///
+/// # Example
+///
+/// ```rust
+/// use etherealpost::parse::{AuctionSnapshot, SalesTracker};
+///
+/// let before = r#"{"auctions": [{"id": 1, "quantity": 5, "item": {"id": 19019},
+///     "buyout": 100, "time_left": "VERY_LONG"}]}"#;
+/// let after = r#"{"auctions": []}"#;
+///
+/// let mut tracker = SalesTracker::new();
+/// tracker.observe(AuctionSnapshot::from_reader(0, before.as_bytes()).unwrap());
+/// tracker.observe(AuctionSnapshot::from_reader(3600, after.as_bytes()).unwrap());
+///
+/// let estimate = tracker.estimate(19019);
+/// assert_eq!(5, estimate.sold_quantity);
+/// assert_eq!(Some(120.0), estimate.sold_per_day());
+/// ```
+#[derive(Default)]
+pub struct SalesTracker {
+    previous: Option<AuctionSnapshot>,
+    observed_secs: u64,
+    estimates: HashMap<u64, SaleEstimate>,
+}
+
+impl SalesTracker {
+    /// Creates a tracker which hasn't observed any snapshots.
+    pub fn new() -> Self {
+        SalesTracker::default()
+    }
+
+    /// Records the next snapshot of the connected realm, returning the diff against
+    /// the previous one.
+    ///
+    /// Returns `None` for the first snapshot, and for any snapshot which isn't newer
+    /// than the previous one. Such snapshots are otherwise ignored.
+    pub fn observe(&mut self, mut snapshot: AuctionSnapshot) -> Option<SnapshotDiff> {
+        let previous = match self.previous.take() {
+            None => {
+                self.previous = Some(snapshot);
+                return None;
+            }
+            Some(previous) if snapshot.timestamp <= previous.timestamp => {
+                self.previous = Some(previous);
+                return None;
+            }
+            Some(previous) => previous,
+        };
+
+        snapshot.carry_forward(&previous);
+        let diff = SnapshotDiff::between(&previous, &snapshot);
+
+        self.observed_secs += diff.elapsed_secs;
+        for vanished in &diff.vanished {
+            self.estimates
+                .entry(vanished.item_id)
+                .or_default()
+                .add(vanished);
+        }
+
+        self.previous = Some(snapshot);
+        Some(diff)
+    }
+
+    /// The total number of seconds covered by the observed snapshots.
+    pub fn observed_secs(&self) -> u64 {
+        self.observed_secs
+    }
+
+    /// The sale estimate of an item over everything observed so far.
+    ///
+    /// Items which never had an auction disappear get an estimate of zero sales.
+    pub fn estimate(&self, item_id: u64) -> SaleEstimate {
+        let mut estimate = self.estimates.get(&item_id).cloned().unwrap_or_default();
+        estimate.observed_secs = self.observed_secs;
+        estimate
+    }
+
+    /// The sale estimates of every item which had at least one auction disappear.
+    pub fn estimates(&self) -> HashMap<u64, SaleEstimate> {
+        self.estimates
+            .keys()
+            .map(|item_id| (*item_id, self.estimate(*item_id)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 3600;
+
+    fn snapshot(timestamp: u64, auctions: &[(u64, u64, u16, &str)]) -> AuctionSnapshot {
+        let auctions: Vec<String> = auctions
+            .iter()
+            .map(|(id, item_id, quantity, time_left)| {
+                format!(
+                    r#"{{"id": {}, "item": {{"id": {}}}, "quantity": {}, "buyout": 100, "time_left": "{}"}}"#,
+                    id, item_id, quantity, time_left
+                )
+            })
+            .collect();
+        let json = format!(r#"{{"auctions": [{}]}}"#, auctions.join(","));
+        AuctionSnapshot::from_reader(timestamp, json.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn diff_classifies_vanished_auctions() {
+        let previous = snapshot(
+            0,
+            &[
+                (1, 10, 1, "VERY_LONG"),
+                (2, 10, 2, "SHORT"),
+                (3, 20, 5, "LONG"),
+                (4, 20, 1, "MEDIUM"),
+            ],
+        );
+        let current = snapshot(HOUR, &[(4, 20, 1, "SHORT"), (5, 20, 1, "VERY_LONG")]);
+
+        let diff = SnapshotDiff::between(&previous, &current);
+        assert_eq!(HOUR, diff.elapsed_secs);
+        assert_eq!(1, diff.new_auctions);
+
+        let outcomes: Vec<(u64, Disappearance)> = diff
+            .vanished
+            .iter()
+            .map(|v| (v.auction_id, v.outcome))
+            .collect();
+        assert_eq!(
+            vec![
+                (1, Disappearance::LikelySold),
+                (2, Disappearance::LikelyCancelledOrExpired),
+                (3, Disappearance::LikelySold),
+            ],
+            outcomes
+        );
+
+        let estimates = diff.item_estimates();
+        let item = estimates.get(&10).unwrap();
+        assert_eq!(1, item.sold_quantity);
+        assert_eq!(2, item.expired_quantity);
+        assert_eq!(Some(0.5), item.sale_rate());
+        assert_eq!(5, estimates.get(&20).unwrap().sold_quantity);
+    }
+
+    #[test]
+    fn tracker_combines_time_left_transitions() {
+        let first = snapshot(0, &[(1, 10, 1, "VERY_LONG")]);
+        let second = snapshot(11 * HOUR, &[(1, 10, 1, "MEDIUM")]);
+        let third = snapshot(11 * HOUR + 45 * 60, &[]);
+
+        // on its own, a MEDIUM auction could expire 30 minutes later
+        let diff = SnapshotDiff::between(&second, &third);
+        assert_eq!(
+            Disappearance::LikelyCancelledOrExpired,
+            diff.vanished[0].outcome
+        );
+
+        // but it was VERY_LONG 11 hours earlier, so it can't expire before 12 hours
+        let mut tracker = SalesTracker::new();
+        assert!(tracker.observe(first).is_none());
+        tracker.observe(second).unwrap();
+        let diff = tracker.observe(third).unwrap();
+        assert_eq!(Disappearance::LikelySold, diff.vanished[0].outcome);
+    }
+
+    #[test]
+    fn tracker_estimates_sold_per_day() {
+        let mut tracker = SalesTracker::new();
+        tracker.observe(snapshot(0, &[(1, 10, 1, "LONG"), (2, 10, 2, "VERY_LONG")]));
+        tracker.observe(snapshot(HOUR, &[(2, 10, 2, "VERY_LONG")]));
+        tracker.observe(snapshot(12 * HOUR, &[(3, 20, 1, "SHORT")]));
+
+        assert_eq!(12 * HOUR, tracker.observed_secs());
+        let estimate = tracker.estimate(10);
+        assert_eq!(2, estimate.sold_auctions);
+        assert_eq!(3, estimate.sold_quantity);
+        assert_eq!(Some(1.0), estimate.sale_rate());
+        assert_relative_eq!(6.0, estimate.sold_per_day().unwrap());
+
+        let never_sold = tracker.estimate(20);
+        assert_eq!(None, never_sold.sale_rate());
+        assert_eq!(Some(0.0), never_sold.sold_per_day());
+        assert_eq!(1, tracker.estimates().len());
+    }
+
+    #[test]
+    fn tracker_ignores_out_of_order_snapshots() {
+        let mut tracker = SalesTracker::new();
+        tracker.observe(snapshot(HOUR, &[(1, 10, 1, "LONG")]));
+        assert!(tracker.observe(snapshot(0, &[])).is_none());
+        assert_eq!(0, tracker.observed_secs());
+
+        let diff = tracker.observe(snapshot(2 * HOUR, &[])).unwrap();
+        assert_eq!(HOUR, diff.elapsed_secs);
+    }
+}
@@ -1,10 +1,19 @@
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::hash::Hash;
+use std::io::Read;
+use std::sync::Arc;
 
-use crate::auctions;
-use crate::parse;
+use crate::auctions::{self, PriceKind};
+use crate::parse::grouping::AuctionCollector;
+use crate::parse::item_level_trace::{resolve_item_level, ItemLevelFallback};
+use crate::parse::variant::{ItemVariant, VariantGrouping};
 use crate::stats;
-use crate::wow::data_tables;
+use crate::stats::distribution::{HistogramBucket, QtyDistribution};
+use crate::stats::estimators::MarketPriceEstimator;
+use crate::stats::MarketPriceConfig;
+use crate::wow::GameData;
 
 /// A summarized snapshot of a raw [AuctionFile](`crate::auctions::AuctionFile`) that contains
 /// various statistical data and pre-computed item mappings and associations.
@@ -44,6 +53,20 @@ pub struct AuctionsSummary<'a> {
     /// This only contains to equippable items.
     pub item_level_summaries: HashMap<u64, HashMap<u32, ItemSummary>>,
 
+    /// Nested map of **Item ID -> Item Variant** to all buyable auctions for
+    /// the items sharing the same variant.
+    ///
+    /// A variant refines the item level with the sockets and stats of the item,
+    /// as configured by the [VariantGrouping](`crate::parse::VariantGrouping`)
+    /// of the [`SummaryOptions`]. This only contains equippable items.
+    pub item_variant_auctions: HashMap<u64, HashMap<ItemVariant, Vec<&'a auctions::Auction>>>,
+
+    /// Nested map of **Item ID -> Item Variant** to the Auction Item Summary for
+    /// just the items sharing the same variant.
+    ///
+    /// This only contains equippable items.
+    pub item_variant_summaries: HashMap<u64, HashMap<ItemVariant, ItemSummary>>,
+
     /// Mapping of **Pet Species ID** to all buyable auctions for that pet.
     ///
     /// This combines pet cages and non-pet cage pet items (which can be learned)
@@ -56,23 +79,270 @@ pub struct AuctionsSummary<'a> {
     /// This combines pet cages and non-pet cage pet items (which can be learned)
     /// mapped to the same species ID.
     pub pet_summaries: HashMap<u32, ItemSummary>,
+
+    /// Mapping of **Item ID** to the bids on that item, for the items whose auctions
+    /// can only be bid on.
+    ///
+    /// These items have nothing to buy out, so they have no
+    /// [item summary](`Self::item_summaries`).
+    pub bid_only_item_summaries: HashMap<u64, BidSummary>,
+
+    /// Mapping of **Pet Species ID** to the bids on the pets of that species, for the
+    /// species whose auctions can only be bid on.
+    ///
+    /// These pets have nothing to buy out, so they have no
+    /// [pet summary](`Self::pet_summaries`).
+    pub bid_only_pet_summaries: HashMap<u32, BidSummary>,
+
+    /// How many auctions fell back to a less accurate item level because the game
+    /// data was missing something.
+    pub fallbacks: FallbackCounts,
+}
+
+/// An owned version of an [`AuctionsSummary`] which contains only the summaries,
+/// without any references back to the auctions they were calculated from.
+///
+/// Since it does not borrow from an [AuctionFile](`crate::auctions::AuctionFile`),
+/// the raw file can be dropped as soon as the summary has been built, and the summary
+/// can be cached, sent across threads, or persisted using any `serde` format
+/// (JSON, or a compact binary format such as bincode).
+///
+/// # Example
+///
+/// ```rust
+/// use etherealpost::auctions::AuctionFile;
+/// use etherealpost::parse::OwnedAuctionsSummary;
+/// use etherealpost::wow::GameData;
+///
+/// let auction_file = AuctionFile::from_json(r#"{"auctions": [{"id": 1, "quantity": 1,
+///     "item": {"id": 19019}, "buyout": 9990000, "time_left": "LONG"}]}"#).unwrap();
+/// let summary = OwnedAuctionsSummary::from_auction_file(&auction_file, &GameData::default());
+/// drop(auction_file);
+///
+/// let json = summary.to_json().unwrap();
+/// let restored = OwnedAuctionsSummary::from_json(&json).unwrap();
+/// assert_eq!(summary, restored);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct OwnedAuctionsSummary {
+    /// Mapping of **Item ID** to the Auction Item Summary information for those items.
+    ///
+    /// See [AuctionsSummary::item_summaries](`AuctionsSummary::item_summaries`).
+    pub item_summaries: HashMap<u64, ItemSummary>,
+
+    /// Nested map of **Item ID -> Item Level** to the Auction Item Summary for
+    /// just the items sharing the same item level.
+    ///
+    /// See [AuctionsSummary::item_level_summaries](`AuctionsSummary::item_level_summaries`).
+    pub item_level_summaries: HashMap<u64, HashMap<u32, ItemSummary>>,
+
+    /// Nested map of **Item ID -> Item Variant** to the Auction Item Summary for
+    /// just the items sharing the same variant.
+    ///
+    /// See [AuctionsSummary::item_variant_summaries](`AuctionsSummary::item_variant_summaries`).
+    #[serde(default)]
+    pub item_variant_summaries: HashMap<u64, HashMap<ItemVariant, ItemSummary>>,
+
+    /// Mapping of **Pet Species ID** to the Auction Item Summary for the
+    /// pets of that species.
+    ///
+    /// See [AuctionsSummary::pet_summaries](`AuctionsSummary::pet_summaries`).
+    pub pet_summaries: HashMap<u32, ItemSummary>,
+
+    /// Mapping of **Item ID** to the bids on that item, for the items whose auctions
+    /// can only be bid on.
+    ///
+    /// See [AuctionsSummary::bid_only_item_summaries](`AuctionsSummary::bid_only_item_summaries`).
+    #[serde(default)]
+    pub bid_only_item_summaries: HashMap<u64, BidSummary>,
+
+    /// Mapping of **Pet Species ID** to the bids on the pets of that species, for the
+    /// species whose auctions can only be bid on.
+    ///
+    /// See [AuctionsSummary::bid_only_pet_summaries](`AuctionsSummary::bid_only_pet_summaries`).
+    #[serde(default)]
+    pub bid_only_pet_summaries: HashMap<u32, BidSummary>,
+
+    /// How many auctions fell back to a less accurate item level.
+    ///
+    /// See [AuctionsSummary::fallbacks](`AuctionsSummary::fallbacks`).
+    #[serde(default)]
+    pub fallbacks: FallbackCounts,
+}
+
+/// Options for how auctions are grouped and summarized.
+///
+/// The default options are used by
+/// [AuctionsSummary::from_auction_file](`AuctionsSummary::from_auction_file`) and the
+/// other constructors which don't take options.
+#[derive(Clone, Debug)]
+pub struct SummaryOptions {
+    /// Which properties of equippable items are used to group them into variants.
+    pub variant_grouping: VariantGrouping,
+
+    /// The thresholds used to calculate the market prices of the buyouts and bids.
+    pub market_price: MarketPriceConfig,
+
+    /// How much a bid-only auction counts towards the market price, relative to an
+    /// auction with a buyout of the same quantity.
+    ///
+    /// The default of `0.0` leaves bids out of the market price entirely. A weight of
+    /// `0.5` counts a bid-only auction as half of an auction with a buyout, and `1.0`
+    /// treats its bid as if it were a buyout. Weights outside of `0.0` to `1.0` are
+    /// clamped to that range. Bids are always summarized in
+    /// [ItemSummary::bids](`ItemSummary::bids`), regardless of the weight.
+    pub bid_weight: f64,
+
+    /// Whether every summary also gets an [`ExtendedSummary`] with percentiles and a
+    /// histogram of its prices. Defaults to `false`.
+    pub extended_summary: bool,
+
+    /// The number of buckets in the price histogram of an [`ExtendedSummary`].
+    /// Defaults to `20`.
+    pub histogram_buckets: usize,
+
+    /// Estimators which are calculated for every summary in addition to the market
+    /// price, see [ItemSummary::estimates](`ItemSummary::estimates`). There are none
+    /// by default.
+    pub estimators: Vec<NamedEstimator>,
+}
+
+/// A [MarketPriceEstimator](`crate::stats::estimators::MarketPriceEstimator`) which
+/// is calculated for every summary under a name.
+#[derive(Clone)]
+pub struct NamedEstimator {
+    /// The name of the estimate in [ItemSummary::estimates](`ItemSummary::estimates`).
+    pub name: String,
+
+    /// The estimator, which is given the buyout prices of a summary.
+    pub estimator: Arc<dyn MarketPriceEstimator + Send + Sync>,
+}
+
+impl fmt::Debug for NamedEstimator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NamedEstimator")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The number of buckets in the price histogram of an [`ExtendedSummary`] when
+/// not configured otherwise.
+const DEFAULT_HISTOGRAM_BUCKETS: usize = 20;
+
+impl Default for SummaryOptions {
+    fn default() -> Self {
+        SummaryOptions {
+            variant_grouping: VariantGrouping::default(),
+            market_price: MarketPriceConfig::default(),
+            bid_weight: 0.0,
+            extended_summary: false,
+            histogram_buckets: DEFAULT_HISTOGRAM_BUCKETS,
+            estimators: Vec::new(),
+        }
+    }
+}
+
+impl SummaryOptions {
+    /// Sets which properties of equippable items are used to group them into variants.
+    pub fn with_variant_grouping(mut self, variant_grouping: VariantGrouping) -> Self {
+        self.variant_grouping = variant_grouping;
+        self
+    }
+
+    /// Sets the thresholds used to calculate market prices.
+    pub fn with_market_price_config(mut self, market_price: MarketPriceConfig) -> Self {
+        self.market_price = market_price;
+        self
+    }
+
+    /// Sets how much a bid-only auction counts towards the market price. The weight
+    /// is clamped between `0.0` and `1.0`.
+    pub fn with_bid_weight(mut self, bid_weight: f64) -> Self {
+        self.bid_weight = bid_weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets whether every summary also gets an [`ExtendedSummary`].
+    pub fn with_extended_summary(mut self, extended_summary: bool) -> Self {
+        self.extended_summary = extended_summary;
+        self
+    }
+
+    /// Sets the number of buckets in the price histogram of an [`ExtendedSummary`].
+    pub fn with_histogram_buckets(mut self, histogram_buckets: usize) -> Self {
+        self.histogram_buckets = histogram_buckets;
+        self
+    }
+
+    /// Adds an estimator which is calculated for every summary under `name`.
+    ///
+    /// An estimator added under the same name as an earlier one replaces it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use etherealpost::auctions::AuctionFile;
+    /// use etherealpost::parse::{OwnedAuctionsSummary, SummaryOptions};
+    /// use etherealpost::stats::estimators::{CostToBuy, TrimmedMean};
+    /// use etherealpost::wow::GameData;
+    ///
+    /// let auction_file = AuctionFile::from_json(r#"{"auctions": [
+    ///     {"id": 1, "quantity": 10, "item": {"id": 2589}, "buyout": 1000, "time_left": "LONG"},
+    ///     {"id": 2, "quantity": 10, "item": {"id": 2589}, "buyout": 2000, "time_left": "LONG"}
+    /// ]}"#).unwrap();
+    ///
+    /// let options = SummaryOptions::default()
+    ///     .with_estimator("cost_to_buy_15", CostToBuy::new(15))
+    ///     .with_estimator("trimmed_mean", TrimmedMean::default());
+    /// let summary =
+    ///     OwnedAuctionsSummary::from_auction_file_with_options(&auction_file, &GameData::default(), &options);
+    ///
+    /// let estimates = &summary.item_summaries[&2589].estimates;
+    /// assert_eq!(133, estimates["cost_to_buy_15"]);
+    /// assert_eq!(150, estimates["trimmed_mean"]);
+    /// ```
+    pub fn with_estimator<E>(mut self, name: &str, estimator: E) -> Self
+    where
+        E: MarketPriceEstimator + Send + Sync + 'static,
+    {
+        self.estimators.retain(|named| named.name != name);
+        self.estimators.push(NamedEstimator {
+            name: name.to_string(),
+            estimator: Arc::new(estimator),
+        });
+        self
+    }
 }
 
 /// Summarized information and statistics about a grouping of items on the auction house,
 /// such as an Item or Pet.
+///
+/// Only groups with at least one auction which can be bought out are summarized.
+/// Items and pets whose auctions can only be bid on are summarized by a
+/// [`BidSummary`] instead, in
+/// [bid_only_item_summaries](`AuctionsSummary::bid_only_item_summaries`) and
+/// [bid_only_pet_summaries](`AuctionsSummary::bid_only_pet_summaries`).
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
 pub struct ItemSummary {
-    /// The calculated market price for all of the items which are represented
-    /// by this summary.
+    /// The calculated market price for a single unit of the items which are
+    /// represented by this summary.
+    ///
+    /// All prices of a summary are per unit, so the buyout of a stack is divided by
+    /// its quantity. See [per_unit_price](`crate::auctions::Auction::per_unit_price`).
     ///
     /// For more information on how this is calculated, see
     /// [normalized_market_price_with_qty](`crate::stats::normalized_market_price_with_qty`).
+    /// The thresholds it uses can be changed with
+    /// [SummaryOptions::market_price](`SummaryOptions::market_price`).
     pub market_price: u64,
 
     /// The population standard deviation for all of the prices of the auctions which are
     /// represented by this summary.
     pub std_dev: f64,
 
-    /// The minimum buyout of all of the auctions that are represented by this summary.
+    /// The minimum buyout of a single unit of all of the auctions that are represented
+    /// by this summary.
     pub min_buyout: u64,
 
     /// The total quantity of an the item represented by this group available in this
@@ -86,6 +356,607 @@ pub struct ItemSummary {
     /// The total number of unique auctions (regardless of the quantity of each) that
     /// for the item represented by this group available in this auction house snapshot.
     pub num_auctions: u64,
+
+    /// Statistics about the bids on the auctions represented by this summary.
+    ///
+    /// Bid-only auctions are not part of any of the other fields, unless a
+    /// [bid weight](`SummaryOptions::bid_weight`) includes them in the market price.
+    #[serde(default)]
+    pub bids: BidSummary,
+
+    /// Percentiles and a histogram of the prices, which are only calculated when
+    /// [SummaryOptions::extended_summary](`SummaryOptions::extended_summary`) is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extended: Option<ExtendedSummary>,
+
+    /// The price given by every [named estimator](`SummaryOptions::with_estimator`),
+    /// by name. Estimators which couldn't estimate a price are left out.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub estimates: BTreeMap<String, u64>,
+}
+
+/// The distribution of the prices of a grouping of items on the auction house.
+///
+/// Every statistic is weighted by quantity, so a stack of 20 counts as 20 units
+/// at its per unit price. Bid-only auctions are not included. See
+/// [QtyDistribution](`crate::stats::distribution::QtyDistribution`) for how they
+/// are calculated.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ExtendedSummary {
+    /// The 5th percentile price.
+    pub p5: u64,
+
+    /// The 25th percentile price.
+    pub p25: u64,
+
+    /// The 50th percentile price.
+    pub p50: u64,
+
+    /// The 75th percentile price.
+    pub p75: u64,
+
+    /// The 95th percentile price.
+    pub p95: u64,
+
+    /// The weighted median price, which unlike [`p50`](`ExtendedSummary::p50`)
+    /// averages the two middle units when there is an even number of them.
+    pub median: u64,
+
+    /// The mean price of all units.
+    pub mean: f64,
+
+    /// The number of units within equally wide ranges of prices, from the lowest
+    /// price to the highest.
+    pub histogram: Vec<HistogramBucket>,
+}
+
+impl ExtendedSummary {
+    /// Describes a distribution of prices using `histogram_buckets` buckets for
+    /// its histogram.
+    pub fn from_distribution(distribution: &QtyDistribution, histogram_buckets: usize) -> Self {
+        ExtendedSummary {
+            p5: distribution.percentile(5.0),
+            p25: distribution.percentile(25.0),
+            p50: distribution.percentile(50.0),
+            p75: distribution.percentile(75.0),
+            p95: distribution.percentile(95.0),
+            median: distribution.median(),
+            mean: distribution.mean(),
+            histogram: distribution.histogram(histogram_buckets),
+        }
+    }
+}
+
+/// Summarized bids for a grouping of items on the auction house.
+///
+/// Just like buyouts, the bid on a stack is divided by its quantity so that bids are
+/// per unit, and they are weighted by the quantity of each auction.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BidSummary {
+    /// The lowest bid of all auctions with a bid, including auctions which can also
+    /// be bought out. `None` if no auction has a bid.
+    pub min_bid: Option<u64>,
+
+    /// The market price of the bids of all auctions with a bid, calculated the same
+    /// way as the market price of the buyouts.
+    pub market_price: Option<u64>,
+
+    /// The number of auctions which can only be bid on, without a buyout.
+    pub num_bid_only: u64,
+
+    /// The total quantity of the auctions which can only be bid on.
+    pub bid_only_qty: u64,
+
+    /// How much the market price of the buyouts is above the market price of the
+    /// bids, which is negative when bids are higher than buyouts.
+    ///
+    /// This is always calculated from the buyouts alone, even when bids are
+    /// weighted into [ItemSummary::market_price](`ItemSummary::market_price`).
+    pub spread: Option<i64>,
+}
+
+/// The number of auctions whose item level was resolved using a fallback, because
+/// the game data didn't have everything needed to resolve it properly.
+///
+/// Each fallback is also emitted as a `debug` [tracing](https://docs.rs/tracing) event
+/// with the Item ID, and the totals are emitted as an `info` event once a summary
+/// has been built.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FallbackCounts {
+    /// Auctions whose item has no base item level in the game data, so item level
+    /// `1` was used as the base.
+    pub missing_base_ilvl: u64,
+
+    /// Auctions for an item with a curve bonus, but without the player's level when
+    /// the item dropped, so the base item level was used.
+    pub missing_drop_level: u64,
+
+    /// Auctions for an item with a curve bonus whose curve isn't in the game data,
+    /// so the base item level was used.
+    pub missing_curve: u64,
+}
+
+impl FallbackCounts {
+    /// The total number of fallbacks taken.
+    pub fn total(&self) -> u64 {
+        self.missing_base_ilvl + self.missing_drop_level + self.missing_curve
+    }
+
+    /// Counts a fallback taken while resolving the item level of an item, emitting
+    /// an event for it.
+    pub(crate) fn record(
+        &mut self,
+        fallback: ItemLevelFallback,
+        item_id: u64,
+        curve_id: Option<u32>,
+    ) {
+        match fallback {
+            ItemLevelFallback::MissingBaseItemLevel => {
+                self.missing_base_ilvl += 1;
+                tracing::debug!(
+                    item_id,
+                    "item has no base item level, falling back to item level 1"
+                );
+            }
+            ItemLevelFallback::MissingDropLevel => {
+                self.missing_drop_level += 1;
+                tracing::debug!(
+                    item_id,
+                    curve_id,
+                    "item has a curve but no drop level, using its base item level"
+                );
+            }
+            ItemLevelFallback::MissingCurve => {
+                self.missing_curve += 1;
+                tracing::debug!(
+                    item_id,
+                    curve_id,
+                    "curve is missing from the game data, using the base item level"
+                );
+            }
+        }
+    }
+}
+
+/// The group an auction was placed into, in addition to its Item ID.
+enum AuctionGroup {
+    /// The auction is a pet cage or an item which learns a pet of this species.
+    Pet(u32),
+
+    /// The auction is any other item, with this effective item level. Equippable
+    /// items are also placed into a variant.
+    ItemLevel(u32, Option<ItemVariant>),
+}
+
+/// The auctions collected for a single group while auctions are added one at a time.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GroupAuctions {
+    /// The `(price per unit, quantity)` of every auction with a buyout or unit price.
+    prices: Vec<(u64, u64)>,
+
+    /// The `(bid per unit, quantity)` of every auction with a bid.
+    bids: Vec<(u64, u64)>,
+
+    /// The `(bid per unit, quantity)` of the auctions which can only be bid on.
+    bid_only: Vec<(u64, u64)>,
+
+    total_qty: u64,
+    num_auctions: u64,
+}
+
+/// Bids are weighted by scaling the quantities, since the market price only works
+/// with whole quantities. This is the quantity an auction with a buyout is scaled by.
+const BID_WEIGHT_SCALE: f64 = 100.0;
+
+impl GroupAuctions {
+    /// Adds a single auction, whether it has a buyout, a bid, or both.
+    pub(crate) fn add(&mut self, auction: &auctions::Auction) {
+        let qty = auction.quantity as u64;
+
+        let bid = auction.per_unit_price(PriceKind::Bid);
+        if let Some(bid) = bid {
+            self.bids.push((bid, qty));
+        }
+
+        match (auction.per_unit_price(PriceKind::Buyout), bid) {
+            (Some(price), _) => self.add_price(price, qty),
+            (None, Some(bid)) => self.bid_only.push((bid, qty)),
+            (None, None) => {}
+        }
+    }
+
+    /// Adds an auction which can be bought for `price` per unit.
+    pub(crate) fn add_price(&mut self, price: u64, qty: u64) {
+        self.prices.push((price, qty));
+        self.total_qty += qty;
+        self.num_auctions += 1;
+    }
+
+    /// Summarizes the group, or returns `None` if none of its auctions can be bought.
+    pub(crate) fn summarize(&mut self, options: &SummaryOptions) -> Option<ItemSummary> {
+        let config = &options.market_price;
+        let buyout_price =
+            stats::normalized_market_price_with_qty_and_config(&mut self.prices, config)?;
+        let bids = self.bid_summary(options, Some(buyout_price));
+
+        Some(ItemSummary {
+            market_price: self.weighted_market_price(options, buyout_price),
+            std_dev: stats::std_dev_amount_qty(&self.prices, true).unwrap_or(0.0),
+            min_buyout: self.prices.first().unwrap().0, // market_price function sorts
+            num_auctions: self.num_auctions,
+            total_qty: self.total_qty,
+            bids,
+            extended: self.extended_summary(options),
+            estimates: self.estimates(options),
+        })
+    }
+
+    /// Summarizes the bids of a group whose auctions can only be bid on, or returns
+    /// `None` if any of its auctions can be bought, or if it has no auctions at all.
+    pub(crate) fn summarize_bid_only(&mut self, options: &SummaryOptions) -> Option<BidSummary> {
+        if !self.prices.is_empty() || self.bid_only.is_empty() {
+            return None;
+        }
+        Some(self.bid_summary(options, None))
+    }
+
+    /// The bids of the group, with the spread to `buyout_price` if it has one.
+    fn bid_summary(&mut self, options: &SummaryOptions, buyout_price: Option<u64>) -> BidSummary {
+        let bid_price = stats::normalized_market_price_with_qty_and_config(
+            &mut self.bids,
+            &options.market_price,
+        );
+
+        BidSummary {
+            min_bid: self.bids.first().map(|(bid, _)| *bid), // market_price function sorts
+            market_price: bid_price,
+            num_bid_only: self.bid_only.len() as u64,
+            bid_only_qty: self.bid_only.iter().map(|(_, qty)| qty).sum(),
+            spread: buyout_price
+                .zip(bid_price)
+                .map(|(buyout_price, bid_price)| buyout_price as i64 - bid_price as i64),
+        }
+    }
+
+    /// The extended summary of the prices, if the options ask for one.
+    fn extended_summary(&mut self, options: &SummaryOptions) -> Option<ExtendedSummary> {
+        if !options.extended_summary {
+            return None;
+        }
+        QtyDistribution::new(&mut self.prices).map(|distribution| {
+            ExtendedSummary::from_distribution(&distribution, options.histogram_buckets)
+        })
+    }
+
+    /// The prices of the named estimators of the options, by name.
+    fn estimates(&mut self, options: &SummaryOptions) -> BTreeMap<String, u64> {
+        if options.estimators.is_empty() {
+            return BTreeMap::new();
+        }
+
+        self.prices.sort_unstable_by_key(|(price, _)| *price);
+        options
+            .estimators
+            .iter()
+            .filter_map(|named| {
+                let estimate = named.estimator.estimate(&self.prices)?;
+                Some((named.name.clone(), estimate))
+            })
+            .collect()
+    }
+
+    /// The market price with the bid-only auctions weighted in, or `buyout_price`
+    /// if they aren't.
+    fn weighted_market_price(&self, options: &SummaryOptions, buyout_price: u64) -> u64 {
+        // the field is public, so it is clamped again in case it was set directly
+        let bid_weight = options.bid_weight.clamp(0.0, 1.0);
+        let bid_scale = (bid_weight * BID_WEIGHT_SCALE).round() as u64;
+        if bid_scale == 0 || self.bid_only.is_empty() {
+            return buyout_price;
+        }
+
+        let buyout_scale = BID_WEIGHT_SCALE as u64;
+        let mut weighted: Vec<(u64, u64)> = self
+            .prices
+            .iter()
+            .map(|(price, qty)| (*price, qty.saturating_mul(buyout_scale)))
+            .chain(
+                self.bid_only
+                    .iter()
+                    .map(|(bid, qty)| (*bid, qty.saturating_mul(bid_scale))),
+            )
+            .collect();
+
+        stats::normalized_market_price_with_qty_and_config(&mut weighted, &options.market_price)
+            .unwrap_or(buyout_price)
+    }
+}
+
+/// Summarizes every group, leaving out the groups without any auction that can
+/// be bought.
+pub(crate) fn summarize_groups<K: Eq + Hash>(
+    groups: HashMap<K, GroupAuctions>,
+    options: &SummaryOptions,
+) -> HashMap<K, ItemSummary> {
+    groups
+        .into_iter()
+        .filter_map(|(key, mut group)| group.summarize(options).map(|summary| (key, summary)))
+        .collect()
+}
+
+/// Summarizes the bids of every group whose auctions can only be bid on.
+fn summarize_bid_only_groups<K: Eq + Hash + Copy>(
+    groups: &mut HashMap<K, GroupAuctions>,
+    options: &SummaryOptions,
+) -> HashMap<K, BidSummary> {
+    groups
+        .iter_mut()
+        .filter_map(|(key, group)| group.summarize_bid_only(options).map(|bids| (*key, bids)))
+        .collect()
+}
+
+/// Collects the auctions of every group while auctions are added one at a time.
+#[derive(Default)]
+struct SummaryAccumulator {
+    options: SummaryOptions,
+
+    items: HashMap<u64, GroupAuctions>,
+    item_levels: HashMap<(u64, u32), GroupAuctions>,
+    item_variants: HashMap<(u64, ItemVariant), GroupAuctions>,
+    pets: HashMap<u32, GroupAuctions>,
+
+    fallbacks: FallbackCounts,
+}
+
+/// Every summary computed by a [`SummaryAccumulator`].
+struct Summaries {
+    items: HashMap<u64, ItemSummary>,
+    item_levels: HashMap<u64, HashMap<u32, ItemSummary>>,
+    item_variants: HashMap<u64, HashMap<ItemVariant, ItemSummary>>,
+    pets: HashMap<u32, ItemSummary>,
+    bid_only_items: HashMap<u64, BidSummary>,
+    bid_only_pets: HashMap<u32, BidSummary>,
+    fallbacks: FallbackCounts,
+}
+
+impl SummaryAccumulator {
+    fn new(options: &SummaryOptions) -> Self {
+        SummaryAccumulator {
+            options: options.clone(),
+            ..SummaryAccumulator::default()
+        }
+    }
+
+    /// Adds a single auction to the groups it belongs to, returning the group it was
+    /// placed in besides its Item ID, or `None` if the auction has neither a price
+    /// nor a bid.
+    fn add(&mut self, auction: &auctions::Auction, game_data: &GameData) -> Option<AuctionGroup> {
+        if !AuctionsSummary::use_auction(auction) && auction.bid.is_none() {
+            return None;
+        }
+
+        self.items.entry(auction.item.id).or_default().add(auction);
+
+        // pet cages, or an item which is a pet but not in a pet cage
+        let species_id = auction
+            .item
+            .pet()
+            .map(|pet_cage| pet_cage.species)
+            .or_else(|| game_data.pet_species(&auction.item.id));
+
+        if let Some(species_id) = species_id {
+            self.pets.entry(species_id).or_default().add(auction);
+
+            return Some(AuctionGroup::Pet(species_id));
+        }
+
+        let effective_level = resolve_item_level(&auction.item, game_data, &mut self.fallbacks);
+
+        self.item_levels
+            .entry((auction.item.id, effective_level))
+            .or_default()
+            .add(auction);
+
+        // only equippable items have sockets and stats worth grouping by
+        if !game_data.is_equippable(&auction.item.id) {
+            return Some(AuctionGroup::ItemLevel(effective_level, None));
+        }
+
+        let variant =
+            self.options
+                .variant_grouping
+                .variant(&auction.item, effective_level, game_data);
+
+        self.item_variants
+            .entry((auction.item.id, variant))
+            .or_default()
+            .add(auction);
+
+        Some(AuctionGroup::ItemLevel(effective_level, Some(variant)))
+    }
+
+    /// Computes the item, item level, variant and pet summaries for everything that
+    /// was added.
+    fn into_summaries(mut self) -> Summaries {
+        let options = &self.options;
+        let bid_only_items = summarize_bid_only_groups(&mut self.items, options);
+        let bid_only_pets = summarize_bid_only_groups(&mut self.pets, options);
+        let items = summarize_groups(self.items, options);
+        let pets = summarize_groups(self.pets, options);
+
+        tracing::info!(
+            items = items.len(),
+            pets = pets.len(),
+            bid_only_items = bid_only_items.len(),
+            bid_only_pets = bid_only_pets.len(),
+            missing_base_ilvl = self.fallbacks.missing_base_ilvl,
+            missing_drop_level = self.fallbacks.missing_drop_level,
+            missing_curve = self.fallbacks.missing_curve,
+            "summarized auctions"
+        );
+
+        Summaries {
+            items,
+            item_levels: nest(summarize_groups(self.item_levels, options)),
+            item_variants: nest(summarize_groups(self.item_variants, options)),
+            pets,
+            bid_only_items,
+            bid_only_pets,
+            fallbacks: self.fallbacks,
+        }
+    }
+}
+
+/// Turns summaries keyed by **Item ID** and another key into a nested map of
+/// **Item ID -> key**.
+fn nest<K: Eq + Hash>(
+    summaries: HashMap<(u64, K), ItemSummary>,
+) -> HashMap<u64, HashMap<K, ItemSummary>> {
+    let mut nested: HashMap<u64, HashMap<K, ItemSummary>> = HashMap::new();
+    for ((item_id, key), summary) in summaries {
+        nested.entry(item_id).or_default().insert(key, summary);
+    }
+    nested
+}
+
+/// Identifies a single summary within an [`OwnedAuctionsSummary`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum SummaryKey {
+    /// The summary of all auctions for an **Item ID**.
+    Item(u64),
+
+    /// The summary of the auctions for an **Item ID** at a specific **Item Level**.
+    ItemLevel(u64, u32),
+
+    /// The summary of the auctions for an **Item ID** of a specific **Item Variant**.
+    ItemVariant(u64, ItemVariant),
+
+    /// The summary of all auctions for a **Pet Species ID**.
+    Pet(u32),
+}
+
+impl OwnedAuctionsSummary {
+    /// Looks up a single summary by its key.
+    pub fn get(&self, key: &SummaryKey) -> Option<&ItemSummary> {
+        match key {
+            SummaryKey::Item(item_id) => self.item_summaries.get(item_id),
+            SummaryKey::ItemLevel(item_id, ilvl) => self
+                .item_level_summaries
+                .get(item_id)
+                .and_then(|levels| levels.get(ilvl)),
+            SummaryKey::ItemVariant(item_id, variant) => self
+                .item_variant_summaries
+                .get(item_id)
+                .and_then(|variants| variants.get(variant)),
+            SummaryKey::Pet(species_id) => self.pet_summaries.get(species_id),
+        }
+    }
+
+    /// Inserts a single summary under its key, returning the summary it replaced.
+    pub fn insert(&mut self, key: SummaryKey, summary: ItemSummary) -> Option<ItemSummary> {
+        match key {
+            SummaryKey::Item(item_id) => self.item_summaries.insert(item_id, summary),
+            SummaryKey::ItemLevel(item_id, ilvl) => self
+                .item_level_summaries
+                .entry(item_id)
+                .or_default()
+                .insert(ilvl, summary),
+            SummaryKey::ItemVariant(item_id, variant) => self
+                .item_variant_summaries
+                .entry(item_id)
+                .or_default()
+                .insert(variant, summary),
+            SummaryKey::Pet(species_id) => self.pet_summaries.insert(species_id, summary),
+        }
+    }
+
+    /// Iterates over every summary along with its key, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (SummaryKey, &ItemSummary)> {
+        let items = self
+            .item_summaries
+            .iter()
+            .map(|(item_id, summary)| (SummaryKey::Item(*item_id), summary));
+        let item_levels = self
+            .item_level_summaries
+            .iter()
+            .flat_map(|(item_id, levels)| {
+                levels
+                    .iter()
+                    .map(move |(ilvl, summary)| (SummaryKey::ItemLevel(*item_id, *ilvl), summary))
+            });
+        let item_variants = self
+            .item_variant_summaries
+            .iter()
+            .flat_map(|(item_id, variants)| {
+                variants.iter().map(move |(variant, summary)| {
+                    (SummaryKey::ItemVariant(*item_id, *variant), summary)
+                })
+            });
+        let pets = self
+            .pet_summaries
+            .iter()
+            .map(|(species_id, summary)| (SummaryKey::Pet(*species_id), summary));
+
+        items.chain(item_levels).chain(item_variants).chain(pets)
+    }
+
+    /// Builds an owned summary from an [AuctionFile](`crate::auctions::AuctionFile`)
+    /// without collecting the per-auction references that an [`AuctionsSummary`] keeps.
+    ///
+    /// See [AuctionsSummary::from_auction_file](`AuctionsSummary::from_auction_file`)
+    /// for a description of the arguments.
+    pub fn from_auction_file(auction_file: &auctions::AuctionFile, game_data: &GameData) -> Self {
+        OwnedAuctionsSummary::from_auction_file_with_options(
+            auction_file,
+            game_data,
+            &SummaryOptions::default(),
+        )
+    }
+
+    /// Builds an owned summary like [from_auction_file](`Self::from_auction_file`),
+    /// grouping and summarizing the auctions according to `options`.
+    pub fn from_auction_file_with_options(
+        auction_file: &auctions::AuctionFile,
+        game_data: &GameData,
+        options: &SummaryOptions,
+    ) -> Self {
+        let _span =
+            tracing::info_span!("summarize_auctions", auctions = auction_file.auctions.len())
+                .entered();
+
+        let mut accumulator = SummaryAccumulator::new(options);
+        for auction in &auction_file.auctions {
+            accumulator.add(auction, game_data);
+        }
+
+        let summaries = accumulator.into_summaries();
+
+        OwnedAuctionsSummary {
+            item_summaries: summaries.items,
+            item_level_summaries: summaries.item_levels,
+            item_variant_summaries: summaries.item_variants,
+            pet_summaries: summaries.pets,
+            bid_only_item_summaries: summaries.bid_only_items,
+            bid_only_pet_summaries: summaries.bid_only_pets,
+            fallbacks: summaries.fallbacks,
+        }
+    }
+
+    /// Deserialize an instance of [`OwnedAuctionsSummary`] from a JSON string.
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+
+    /// Serialize the summary into a JSON string.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+}
+
+impl<'a> From<AuctionsSummary<'a>> for OwnedAuctionsSummary {
+    fn from(summary: AuctionsSummary<'a>) -> Self {
+        summary.into_owned()
+    }
 }
 
 /// Implementation for generating and working with an Auctions Summary.
@@ -102,296 +973,653 @@ impl<'a> AuctionsSummary<'a> {
     /// # Arguments
     ///
     /// * `auction_file` - The parsed auction file to process into a summary.
-    /// * `curve_points` - Precomputed mapping of Curve IDs to the curve that should
-    ///   be used to calculate an item's level. This value can be computed using
-    ///   a [Db2CurvePoints](`crate::wow::data_tables::Db2CurvePoints`) table that
-    ///   has been passed into [ItemLevelCurve](`super::ItemLevelCurve::for_whole_table`).
-    /// * `db2_bonuses` - Parsed DB2 table which contains Item Level Bonus IDs and their
-    ///   associated Curve IDs.
-    /// * `item_to_pet` - Precomputed mapping of **Item IDs** to the **Pet Species ID**
-    ///   which they learn.
-    /// * `equippable_items` - Precomputed set of **Item IDs** which are equippable.
+    /// * `game_data` - The item levels, bonuses, curves, pets and equippable items
+    ///   from the game's data tables. See [GameData](`crate::wow::GameData`) for how
+    ///   to load it.
     pub fn from_auction_file(
         auction_file: &'a auctions::AuctionFile,
-        curve_points: &parse::ItemLevelCurvePoints,
-        db2_bonuses: &data_tables::Db2ItemBonuses,
-        base_ilvls: &HashMap<u64, u32>,
-        item_to_pet: &HashMap<u64, u32>,
-        equippable_items: &HashSet<u64>,
+        game_data: &GameData,
+    ) -> Self {
+        AuctionsSummary::from_auction_file_with_options(
+            auction_file,
+            game_data,
+            &SummaryOptions::default(),
+        )
+    }
+
+    /// Builds an Auctions Summary like [from_auction_file](`Self::from_auction_file`),
+    /// grouping and summarizing the auctions according to `options`.
+    pub fn from_auction_file_with_options(
+        auction_file: &'a auctions::AuctionFile,
+        game_data: &GameData,
+        options: &SummaryOptions,
     ) -> Self {
+        AuctionsSummary::from_auction_file_with_groups(auction_file, game_data, options, &mut [])
+    }
+
+    /// Builds an Auctions Summary like
+    /// [from_auction_file_with_options](`Self::from_auction_file_with_options`), while
+    /// also feeding every auction to custom groupings in the same pass.
+    ///
+    /// See [AuctionGrouping](`crate::parse::AuctionGrouping`) for an example.
+    pub fn from_auction_file_with_groups(
+        auction_file: &'a auctions::AuctionFile,
+        game_data: &GameData,
+        options: &SummaryOptions,
+        groups: &mut [&mut dyn AuctionCollector],
+    ) -> Self {
+        let _span =
+            tracing::info_span!("summarize_auctions", auctions = auction_file.auctions.len())
+                .entered();
+
         let mut item_auctions: HashMap<u64, Vec<&auctions::Auction>> = HashMap::new();
         let mut item_level_auctions: HashMap<u64, HashMap<u32, Vec<&auctions::Auction>>> =
             HashMap::new();
+        let mut item_variant_auctions: HashMap<u64, HashMap<ItemVariant, Vec<&auctions::Auction>>> =
+            HashMap::new();
         let mut pet_auctions: HashMap<u32, Vec<&auctions::Auction>> = HashMap::new();
 
-        let mut all_prices: HashMap<u64, Vec<(u64, u64)>> = HashMap::new();
-        let mut ilvl_prices: HashMap<(u64, u32), Vec<(u64, u64)>> = HashMap::new();
-        let mut pet_prices: HashMap<u32, Vec<(u64, u64)>> = HashMap::new();
-
-        let mut all_qty: HashMap<u64, u64> = HashMap::new();
-        let mut ilvl_qty: HashMap<(u64, u32), u64> = HashMap::new();
-        let mut pet_qty: HashMap<u32, u64> = HashMap::new();
-
-        let mut all_num_auc: HashMap<u64, u64> = HashMap::new();
-        let mut ilvl_num_auc: HashMap<(u64, u32), u64> = HashMap::new();
-        let mut pet_num_auc: HashMap<u32, u64> = HashMap::new();
+        let mut accumulator = SummaryAccumulator::new(options);
 
         for auction in &auction_file.auctions {
-            if !Self::use_auction(&auction) {
-                continue;
+            for group in groups.iter_mut() {
+                group.add(auction, game_data);
             }
 
-            let price = Self::auction_price(&auction);
-
-            // Add to all prices
-            all_prices
-                .entry(auction.item.id)
-                .or_insert_with(Vec::new)
-                .push((price, auction.quantity as u64));
+            let group = match accumulator.add(auction, game_data) {
+                Some(group) => group,
+                None => continue,
+            };
 
-            // Increment the unit and num auctions count for all auctions
-            *(all_qty.entry(auction.item.id).or_insert(0)) += auction.quantity as u64;
-            *(all_num_auc.entry(auction.item.id).or_insert(0)) += 1;
+            // bid-only auctions are only part of the bid statistics
+            if !Self::use_auction(auction) {
+                continue;
+            }
 
             // add the auction to all auctions
             item_auctions
                 .entry(auction.item.id)
-                .or_insert_with(Vec::new)
-                .push(&auction);
-
-            // pet cages
-            if let Some(pet_cage) = auction.item.pet() {
-                Self::insert_pet_auctions(
-                    &auction,
-                    &pet_cage.species,
-                    &price,
-                    &mut pet_prices,
-                    &mut pet_auctions,
-                    &mut pet_qty,
-                    &mut pet_num_auc,
-                );
-                continue;
-            }
+                .or_default()
+                .push(auction);
 
-            // check if this item is is a pet but not in a pet cage
-            if let Some(species_id) = item_to_pet.get(&auction.item.id) {
-                Self::insert_pet_auctions(
-                    &auction,
-                    species_id,
-                    &price,
-                    &mut pet_prices,
-                    &mut pet_auctions,
-                    &mut pet_qty,
-                    &mut pet_num_auc,
-                );
+            match group {
+                AuctionGroup::Pet(species_id) => {
+                    pet_auctions.entry(species_id).or_default().push(auction);
+                }
+                AuctionGroup::ItemLevel(effective_level, variant) => {
+                    item_level_auctions
+                        .entry(auction.item.id)
+                        .or_default()
+                        .entry(effective_level)
+                        .or_default()
+                        .push(auction);
 
-                continue;
+                    if let Some(variant) = variant {
+                        item_variant_auctions
+                            .entry(auction.item.id)
+                            .or_default()
+                            .entry(variant)
+                            .or_default()
+                            .push(auction);
+                    }
+                }
             }
-
-            // if the item is not equippable, the item level is the base item level
-            let is_equippable = equippable_items.contains(&auction.item.id);
-
-            let effective_level = Self::resolve_item_level(
-                &auction.item,
-                is_equippable,
-                &db2_bonuses,
-                &base_ilvls,
-                &curve_points,
-            );
-
-            let ilvl_key = (auction.item.id, effective_level);
-
-            ilvl_prices
-                .entry(ilvl_key)
-                .or_insert_with(Vec::new)
-                .push((price, auction.quantity as u64));
-
-            item_level_auctions
-                .entry(auction.item.id)
-                .or_insert_with(HashMap::new)
-                .entry(effective_level)
-                .or_insert_with(Vec::new)
-                .push(&auction);
-
-            *(ilvl_qty.entry(ilvl_key).or_insert(0)) += auction.quantity as u64;
-            *(ilvl_num_auc.entry(ilvl_key).or_insert(0)) += 1;
         }
 
-        let mut all_items: HashMap<u64, ItemSummary> = HashMap::new();
-        let mut ilvl_items: HashMap<u64, HashMap<u32, ItemSummary>> = HashMap::new();
-        let mut pet_items: HashMap<u32, ItemSummary> = HashMap::new();
-
-        // Insert summaries for the global Item IDs
-        Self::insert_item_summary(&mut all_prices, &mut all_items, &all_num_auc, &all_qty);
-        // Insert summaries for pet species
-        Self::insert_item_summary(&mut pet_prices, &mut pet_items, &pet_num_auc, &pet_qty);
-
-        // Insert nested summaries for Item ID -> Item Level
-        for (key, prices) in &mut ilvl_prices {
-            if let Some(mp) = stats::normalized_market_price_with_qty(prices) {
-                ilvl_items.entry(key.0).or_insert_with(HashMap::new).insert(
-                    key.1,
-                    ItemSummary {
-                        market_price: mp,
-                        std_dev: stats::std_dev_amount_qty(prices, true).unwrap_or(0.0),
-                        min_buyout: prices.first().unwrap().0, // market_price function sorts
-                        num_auctions: *ilvl_num_auc.get(key).unwrap(),
-                        total_qty: *ilvl_qty.get(key).unwrap(),
-                    },
-                );
-            }
-        }
+        let summaries = accumulator.into_summaries();
 
         AuctionsSummary {
             item_auctions,
             item_level_auctions,
+            item_variant_auctions,
             pet_auctions,
-            item_summaries: all_items,
-            item_level_summaries: ilvl_items,
-            pet_summaries: pet_items,
+            item_summaries: summaries.items,
+            item_level_summaries: summaries.item_levels,
+            item_variant_summaries: summaries.item_variants,
+            pet_summaries: summaries.pets,
+            bid_only_item_summaries: summaries.bid_only_items,
+            bid_only_pet_summaries: summaries.bid_only_pets,
+            fallbacks: summaries.fallbacks,
         }
     }
 
-    /// Whether or not an auction should be included in the summary.
+    /// Builds an Auctions Summary directly from a reader which produces the JSON of an
+    /// [AuctionFile](`crate::auctions::AuctionFile`), such as an HTTP response body.
     ///
-    /// This is currently defined as having either a buyout or a unit price,
-    /// which means that the auction isn't "bid only."
-    fn use_auction(auction: &auctions::Auction) -> bool {
-        auction.buyout.is_some() || auction.unit_price.is_some()
+    /// Unlike [from_auction_file](`Self::from_auction_file`), the auction file is never
+    /// held in memory as a whole. Each auction is summarized as soon as it is parsed and
+    /// then dropped, so no [Auction](`crate::auctions::Auction`) outlives the call. Only
+    /// its `(price, quantity)` pair is kept for each group it belongs to, which still
+    /// grows with the number of auctions, but is far smaller than the parsed auctions.
+    ///
+    /// Because no auctions outlive the call, the `item_auctions`, `item_level_auctions`,
+    /// `item_variant_auctions` and `pet_auctions` maps of the returned summary are always
+    /// empty. Only the summaries are populated.
+    ///
+    /// See [from_auction_file](`Self::from_auction_file`) for a description of the
+    /// `game_data` argument.
+    pub fn from_reader<R: Read>(
+        reader: R,
+        game_data: &GameData,
+    ) -> Result<AuctionsSummary<'static>, serde_json::Error> {
+        AuctionsSummary::from_reader_with_options(reader, game_data, &SummaryOptions::default())
     }
 
-    /// Extracts the price that should be used for the auction.
-    ///
-    /// This should only ever be called after [use_auction](`Self::use_auction`)
-    /// has been called due to an unchecked unwrap.
-    fn auction_price(auction: &auctions::Auction) -> u64 {
-        auction.buyout.or(auction.unit_price).unwrap()
+    /// Builds an Auctions Summary like [from_reader](`Self::from_reader`), grouping and
+    /// summarizing the auctions according to `options`.
+    pub fn from_reader_with_options<R: Read>(
+        reader: R,
+        game_data: &GameData,
+        options: &SummaryOptions,
+    ) -> Result<AuctionsSummary<'static>, serde_json::Error> {
+        AuctionsSummary::from_reader_with_groups(reader, game_data, options, &mut [])
     }
 
-    /// Resolves the item level _adjustment_ that should be added to an item's
-    /// base item level in order to determine its actual item level.
-    ///
-    /// This could be a negative number.
-    fn find_ilvl_adjustment(
-        item: &auctions::Item,
-        db2_bonuses: &data_tables::Db2ItemBonuses,
-    ) -> Option<i32> {
-        match &item.bonus_lists {
-            Some(bonus_ids) => db2_bonuses.resolve_ilvl_adjustment(&bonus_ids),
-            _ => None,
-        }
+    /// Builds an Auctions Summary like
+    /// [from_reader_with_options](`Self::from_reader_with_options`), while also feeding
+    /// every auction to custom groupings in the same pass.
+    pub fn from_reader_with_groups<R: Read>(
+        reader: R,
+        game_data: &GameData,
+        options: &SummaryOptions,
+        groups: &mut [&mut dyn AuctionCollector],
+    ) -> Result<AuctionsSummary<'static>, serde_json::Error> {
+        let _span = tracing::info_span!("summarize_auctions_stream").entered();
+
+        let mut accumulator = SummaryAccumulator::new(options);
+        auctions::AuctionFile::for_each_auction(reader, |auction| {
+            for group in groups.iter_mut() {
+                group.add(&auction, game_data);
+            }
+            accumulator.add(&auction, game_data);
+        })?;
+
+        let summaries = accumulator.into_summaries();
+
+        Ok(AuctionsSummary {
+            item_auctions: HashMap::new(),
+            item_level_auctions: HashMap::new(),
+            item_variant_auctions: HashMap::new(),
+            pet_auctions: HashMap::new(),
+            item_summaries: summaries.items,
+            item_level_summaries: summaries.item_levels,
+            item_variant_summaries: summaries.item_variants,
+            pet_summaries: summaries.pets,
+            bid_only_item_summaries: summaries.bid_only_items,
+            bid_only_pet_summaries: summaries.bid_only_pets,
+            fallbacks: summaries.fallbacks,
+        })
     }
 
-    /// Resolves the **Curve ID** that should be used to compute an item's
-    /// level.
-    fn find_curve_id(
-        item: &auctions::Item,
-        db2_bonuses: &data_tables::Db2ItemBonuses,
-    ) -> Option<u32> {
-        match &item.bonus_lists {
-            Some(bonus_ids) => db2_bonuses.resolve_curve_id(&bonus_ids),
-            None => None,
+    /// Converts the summary into an [`OwnedAuctionsSummary`], dropping the references
+    /// to the individual auctions so that the auction file can be released.
+    pub fn into_owned(self) -> OwnedAuctionsSummary {
+        OwnedAuctionsSummary {
+            item_summaries: self.item_summaries,
+            item_level_summaries: self.item_level_summaries,
+            item_variant_summaries: self.item_variant_summaries,
+            pet_summaries: self.pet_summaries,
+            bid_only_item_summaries: self.bid_only_item_summaries,
+            bid_only_pet_summaries: self.bid_only_pet_summaries,
+            fallbacks: self.fallbacks,
         }
     }
 
-    /// Resolves the player's level when the item dropped, if
-    /// this is an item which has such data.
-    fn drop_level(item: &auctions::Item) -> Option<u32> {
-        match &item.modifiers {
-            Some(modifiers) => {
-                for modifier in modifiers {
-                    if modifier.modifier_type == 9 {
-                        return Some(modifier.value as u32);
-                    }
-                }
-                None
-            }
-            None => None,
+    /// Copies the summaries into an [`OwnedAuctionsSummary`], leaving this summary intact.
+    pub fn to_owned_summary(&self) -> OwnedAuctionsSummary {
+        OwnedAuctionsSummary {
+            item_summaries: self.item_summaries.clone(),
+            item_level_summaries: self.item_level_summaries.clone(),
+            item_variant_summaries: self.item_variant_summaries.clone(),
+            pet_summaries: self.pet_summaries.clone(),
+            bid_only_item_summaries: self.bid_only_item_summaries.clone(),
+            bid_only_pet_summaries: self.bid_only_pet_summaries.clone(),
+            fallbacks: self.fallbacks,
         }
     }
 
-    /// Shorthand method which inserts an Item Summary into the various maps
-    /// that make up an Auction Summary.
-    fn insert_item_summary<T>(
-        prices: &mut HashMap<T, Vec<(u64, u64)>>,
-        items: &mut HashMap<T, ItemSummary>,
-        num_aucs: &HashMap<T, u64>,
-        total_qty: &HashMap<T, u64>,
-    ) where
-        T: Eq + Hash + Copy,
-    {
-        for (key, prices) in prices.iter_mut() {
-            if let Some(mp) = stats::normalized_market_price_with_qty(prices) {
-                items.insert(
-                    *key,
-                    ItemSummary {
-                        market_price: mp,
-                        std_dev: stats::std_dev_amount_qty(prices, true).unwrap_or(0.0),
-                        min_buyout: prices.first().unwrap().0, // market_price function sorts
-                        num_auctions: *num_aucs.get(key).unwrap(),
-                        total_qty: *total_qty.get(key).unwrap(),
-                    },
-                );
-            }
+    /// Whether or not an auction can be bought, and is included in the prices of
+    /// the summary.
+    ///
+    /// This is currently defined as having either a buyout or a unit price,
+    /// which means that the auction isn't "bid only." Bid-only auctions are only
+    /// included in the [bid statistics](`ItemSummary::bids`).
+    pub(crate) fn use_auction(auction: &auctions::Auction) -> bool {
+        auction.per_unit_price(PriceKind::Buyout).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wow::data_tables;
+
+    const AUCTIONS_JSON: &str = r#"{"auctions": [
+        {"id": 1, "quantity": 1, "item": {"id": 19019}, "buyout": 1000, "time_left": "LONG"},
+        {"id": 2, "quantity": 1, "item": {"id": 19019}, "buyout": 1100, "time_left": "LONG"},
+        {"id": 3, "quantity": 1, "item": {"id": 19019, "bonus_lists": [58]}, "buyout": 5000, "time_left": "SHORT"},
+        {"id": 4, "quantity": 1, "item": {"id": 82800, "pet_breed_id": 4, "pet_level": 1,
+            "pet_quality_id": 3, "pet_species_id": 85}, "buyout": 300, "time_left": "LONG"},
+        {"id": 5, "quantity": 1, "item": {"id": 11826}, "buyout": 200, "time_left": "LONG"},
+        {"id": 6, "quantity": 1, "item": {"id": 11826}, "bid": 10, "time_left": "LONG"}
+    ]}"#;
+
+    /// Stacks of a non-commodity item, whose buyouts and bids are for the whole stack.
+    const STACKED_AUCTIONS_JSON: &str = r#"{"auctions": [
+        {"id": 1, "quantity": 20, "item": {"id": 2589}, "buyout": 2000, "time_left": "LONG"},
+        {"id": 2, "quantity": 1, "item": {"id": 2589}, "buyout": 120, "time_left": "LONG"},
+        {"id": 3, "quantity": 5, "item": {"id": 2589}, "bid": 400, "buyout": 600, "time_left": "LONG"},
+        {"id": 4, "quantity": 10, "item": {"id": 2589}, "bid": 700, "time_left": "SHORT"}
+    ]}"#;
+
+    fn game_data() -> GameData {
+        GameData {
+            item_bonuses: data_tables::Db2ItemBonuses::from_csv(
+                "ID,Value[0],Value[1],Value[2],Value[3],ParentItemBonusListID,Type,OrderIndex\n5,10,0,0,0,58,1,0",
+                data_tables::LoadMode::Strict,
+            )
+            .unwrap()
+            .0,
+            base_ilvls: vec![(19019, 80)].into_iter().collect(),
+            item_to_pet: vec![(11826, 85)].into_iter().collect(),
+            equippable_items: vec![19019].into_iter().collect(),
+            ..GameData::default()
         }
     }
 
-    /// Shorthand method which inserts Pet Auctions and Prices into the various maps
-    /// that make up an Auction Summary.
-    fn insert_pet_auctions(
-        auction: &'a auctions::Auction,
-        species_id: &u32,
-        price: &u64,
-        pet_prices: &mut HashMap<u32, Vec<(u64, u64)>>,
-        pet_auctions: &mut HashMap<u32, Vec<&'a auctions::Auction>>,
-        pet_qty: &mut HashMap<u32, u64>,
-        pet_num_auc: &mut HashMap<u32, u64>,
-    ) {
-        pet_prices
-            .entry(*species_id)
-            .or_insert_with(Vec::new)
-            .push((*price, auction.quantity as u64));
-
-        pet_auctions
-            .entry(*species_id)
-            .or_insert_with(Vec::new)
-            .push(&auction);
-
-        *(pet_qty.entry(*species_id).or_insert(0)) += auction.quantity as u64;
-        *(pet_num_auc.entry(*species_id).or_insert(0)) += 1;
-    }
-
-    /// Resolves the actual item level of an item using its bonuses, curves,
-    /// and player drop level, if it applies to this item.
-    ///
-    /// TODO(seputaes): Holy eyesore, Batman! Make this cleaner...
-    fn resolve_item_level(
-        item: &auctions::Item,
-        is_equippable: bool,
-        db2_bonuses: &data_tables::Db2ItemBonuses,
-        base_ilvls: &HashMap<u64, u32>,
-        curve_points: &parse::ItemLevelCurvePoints,
-    ) -> u32 {
-        match is_equippable {
-            true => match AuctionsSummary::find_curve_id(&item, &db2_bonuses) {
-                Some(curve_id) => match AuctionsSummary::drop_level(&item) {
-                    Some(drop_level) => match curve_points.get(&curve_id) {
-                        Some(curve) => curve.calc_ilvl(&drop_level),
-                        // if this is none, it's not in the table that was pre-cached
-                        None => *(base_ilvls.get(&item.id).unwrap_or(&1)),
-                    },
-                    None => *(base_ilvls.get(&item.id).unwrap_or(&1)),
-                },
-                // there's no curve associated with this item, check for a standard ilvl adjustment
-                None => match AuctionsSummary::find_ilvl_adjustment(&item, &db2_bonuses) {
-                    Some(adjustment) => {
-                        let base_level = *(base_ilvls.get(&item.id).unwrap_or(&1)) as i32;
-                        (base_level + adjustment) as u32
-                    }
-                    None => *(base_ilvls.get(&item.id).unwrap_or(&1)),
-                },
+    #[test]
+    fn from_auction_file_groups_items_levels_and_pets() {
+        let file = auctions::AuctionFile::from_json(AUCTIONS_JSON).unwrap();
+        let game_data = game_data();
+
+        let summary = AuctionsSummary::from_auction_file(&file, &game_data);
+
+        assert_eq!(3, summary.item_auctions[&19019].len());
+        assert_eq!(2, summary.item_level_auctions[&19019][&80].len());
+        assert_eq!(1, summary.item_level_auctions[&19019][&90].len());
+        assert_eq!(2, summary.pet_auctions[&85].len());
+        assert_eq!(2, summary.pet_summaries[&85].num_auctions);
+        assert_eq!(200, summary.pet_summaries[&85].min_buyout);
+        // the bid only auction is only part of the bid statistics
+        assert_eq!(1, summary.item_summaries[&11826].num_auctions);
+        assert_eq!(1, summary.item_auctions[&11826].len());
+    }
+
+    #[test]
+    fn summarizes_bids() {
+        let file = auctions::AuctionFile::from_json(AUCTIONS_JSON).unwrap();
+        let summary = AuctionsSummary::from_auction_file(&file, &game_data());
+
+        let expected = BidSummary {
+            min_bid: Some(10),
+            market_price: Some(10),
+            num_bid_only: 1,
+            bid_only_qty: 1,
+            spread: Some(190),
+        };
+        assert_eq!(expected, summary.item_summaries[&11826].bids);
+        assert_eq!(1, summary.pet_summaries[&85].bids.num_bid_only);
+        assert_eq!(BidSummary::default(), summary.item_summaries[&19019].bids);
+        // every group with a bid-only auction can also be bought out
+        assert!(summary.bid_only_item_summaries.is_empty());
+        assert!(summary.bid_only_pet_summaries.is_empty());
+    }
+
+    #[test]
+    fn weights_bid_only_auctions_into_the_market_price() {
+        let json = r#"{"auctions": [
+            {"id": 1, "quantity": 1, "item": {"id": 19019}, "buyout": 1000, "time_left": "LONG"},
+            {"id": 2, "quantity": 1, "item": {"id": 19019}, "bid": 500, "buyout": 1100, "time_left": "LONG"},
+            {"id": 3, "quantity": 2, "item": {"id": 19019}, "bid": 1200, "time_left": "LONG"},
+            {"id": 4, "quantity": 1, "item": {"id": 19019}, "bid": 700, "time_left": "LONG"}
+        ]}"#;
+        let file = auctions::AuctionFile::from_json(json).unwrap();
+        let game_data = GameData::default();
+
+        let unweighted = OwnedAuctionsSummary::from_auction_file(&file, &game_data);
+        let summary = &unweighted.item_summaries[&19019];
+        assert_eq!(1000, summary.market_price);
+        assert_eq!(2, summary.num_auctions);
+        assert_eq!(2, summary.bids.num_bid_only);
+        assert_eq!(3, summary.bids.bid_only_qty);
+        assert_eq!(Some(500), summary.bids.min_bid);
+
+        let zero = SummaryOptions::default().with_bid_weight(0.0);
+        assert_eq!(
+            unweighted,
+            OwnedAuctionsSummary::from_auction_file_with_options(&file, &game_data, &zero)
+        );
+
+        let options = SummaryOptions::default().with_bid_weight(1.0);
+        let weighted =
+            OwnedAuctionsSummary::from_auction_file_with_options(&file, &game_data, &options);
+        let weighted = &weighted.item_summaries[&19019];
+        assert_eq!(600, weighted.market_price);
+        assert_eq!(summary.min_buyout, weighted.min_buyout);
+        assert_eq!(summary.bids, weighted.bids);
+
+        // weights outside of 0.0 to 1.0 are clamped, even when set directly
+        assert_eq!(
+            0.0,
+            SummaryOptions::default().with_bid_weight(-1.0).bid_weight
+        );
+        assert_eq!(
+            1.0,
+            SummaryOptions::default().with_bid_weight(1e30).bid_weight
+        );
+        let options = SummaryOptions {
+            bid_weight: 1e30,
+            ..SummaryOptions::default()
+        };
+        let clamped =
+            OwnedAuctionsSummary::from_auction_file_with_options(&file, &game_data, &options);
+        assert_eq!(600, clamped.item_summaries[&19019].market_price);
+    }
+
+    #[test]
+    fn summarizes_groups_without_a_buyout() {
+        let json = r#"{"auctions": [
+            {"id": 1, "quantity": 2, "item": {"id": 2589}, "bid": 300, "time_left": "LONG"},
+            {"id": 2, "quantity": 1, "item": {"id": 2589}, "bid": 200, "time_left": "LONG"},
+            {"id": 3, "quantity": 1, "item": {"id": 82800, "pet_breed_id": 4, "pet_level": 1,
+                "pet_quality_id": 3, "pet_species_id": 39}, "bid": 10, "time_left": "LONG"}
+        ]}"#;
+        let file = auctions::AuctionFile::from_json(json).unwrap();
+        let game_data = GameData::default();
+
+        let summary = AuctionsSummary::from_auction_file(&file, &game_data);
+        // nothing can be bought out, so there is no buyout summary
+        assert!(summary.item_summaries.is_empty());
+        assert!(summary.item_level_summaries.is_empty());
+        assert!(summary.pet_summaries.is_empty());
+        let expected = BidSummary {
+            min_bid: Some(150),
+            market_price: Some(150),
+            num_bid_only: 2,
+            bid_only_qty: 3,
+            spread: None,
+        };
+        assert_eq!(expected, summary.bid_only_item_summaries[&2589]);
+        // bid-only auctions still aren't referenced as buyable auctions
+        assert!(summary.item_auctions.is_empty());
+
+        let pet = &summary.bid_only_pet_summaries[&39];
+        assert_eq!(Some(10), pet.min_bid);
+        assert_eq!(1, pet.num_bid_only);
+
+        let owned = summary.into_owned();
+        assert_eq!(expected, owned.bid_only_item_summaries[&2589]);
+        assert_eq!(
+            owned,
+            OwnedAuctionsSummary::from_json(&owned.to_json().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn stacked_listings_are_priced_per_unit() {
+        let file = auctions::AuctionFile::from_json(STACKED_AUCTIONS_JSON).unwrap();
+        let game_data = GameData::default();
+
+        let expected = ItemSummary {
+            market_price: 100,
+            std_dev: stats::std_dev_amount_qty(&[(100, 20), (120, 1), (120, 5)], true).unwrap(),
+            min_buyout: 100,
+            total_qty: 26,
+            num_auctions: 3,
+            bids: BidSummary {
+                min_bid: Some(70),
+                market_price: Some(70),
+                num_bid_only: 1,
+                bid_only_qty: 10,
+                spread: Some(30),
             },
-            false => *(base_ilvls.get(&item.id).unwrap_or(&1)),
+            ..ItemSummary::default()
+        };
+
+        let summary = AuctionsSummary::from_auction_file(&file, &game_data);
+        assert_eq!(expected, summary.item_summaries[&2589]);
+        assert_eq!(expected, summary.item_level_summaries[&2589][&1]);
+
+        let streamed =
+            AuctionsSummary::from_reader(STACKED_AUCTIONS_JSON.as_bytes(), &game_data).unwrap();
+        assert_eq!(expected, streamed.item_summaries[&2589]);
+    }
+
+    #[test]
+    fn extended_summary_describes_the_price_distribution() {
+        let file = auctions::AuctionFile::from_json(STACKED_AUCTIONS_JSON).unwrap();
+        let game_data = GameData::default();
+
+        let plain = OwnedAuctionsSummary::from_auction_file(&file, &game_data);
+        assert_eq!(None, plain.item_summaries[&2589].extended);
+        assert!(!plain.to_json().unwrap().contains("extended"));
+
+        let options = SummaryOptions::default()
+            .with_extended_summary(true)
+            .with_histogram_buckets(2);
+        let summary =
+            OwnedAuctionsSummary::from_auction_file_with_options(&file, &game_data, &options);
+        let extended = summary.item_summaries[&2589].extended.as_ref().unwrap();
+
+        assert_eq!(100, extended.p5);
+        assert_eq!(100, extended.p50);
+        assert_eq!(100, extended.p75);
+        assert_eq!(120, extended.p95);
+        assert_eq!(100, extended.median);
+        assert_relative_eq!(2720.0 / 26.0, extended.mean);
+        let histogram: Vec<(u64, u64, u64)> = extended
+            .histogram
+            .iter()
+            .map(|bucket| (bucket.min_price, bucket.max_price, bucket.qty))
+            .collect();
+        assert_eq!(vec![(100, 111, 20), (111, 122, 6)], histogram);
+
+        let restored = OwnedAuctionsSummary::from_json(&summary.to_json().unwrap()).unwrap();
+        let restored = restored.item_summaries[&2589].extended.as_ref().unwrap();
+        assert_eq!(extended.histogram, restored.histogram);
+        assert_relative_eq!(extended.mean, restored.mean);
+    }
+
+    #[test]
+    fn calculates_named_estimators() {
+        use crate::stats::estimators::{CostToBuy, NormalizedMarketPrice};
+
+        let file = auctions::AuctionFile::from_json(STACKED_AUCTIONS_JSON).unwrap();
+        let game_data = GameData::default();
+
+        let plain = OwnedAuctionsSummary::from_auction_file(&file, &game_data);
+        assert!(plain.item_summaries[&2589].estimates.is_empty());
+        assert!(!plain.to_json().unwrap().contains("estimates"));
+
+        let options = SummaryOptions::default()
+            .with_estimator("normalized", NormalizedMarketPrice::default())
+            .with_estimator("cost_to_buy_25", CostToBuy::new(25))
+            .with_estimator("cost_to_buy_100", CostToBuy::new(100))
+            .with_estimator("most_expensive", |price_qty: &[(u64, u64)]| {
+                price_qty.last().map(|(price, _)| *price)
+            });
+        let summary =
+            OwnedAuctionsSummary::from_auction_file_with_options(&file, &game_data, &options);
+        let item = &summary.item_summaries[&2589];
+
+        let expected: BTreeMap<String, u64> = vec![
+            ("normalized".to_string(), item.market_price),
+            // 20 at 100 and 5 at 120
+            ("cost_to_buy_25".to_string(), 104),
+            ("most_expensive".to_string(), 120),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(expected, item.estimates);
+        assert_eq!(expected, summary.item_level_summaries[&2589][&1].estimates);
+
+        let restored = OwnedAuctionsSummary::from_json(&summary.to_json().unwrap()).unwrap();
+        assert_eq!(summary, restored);
+
+        let replaced = options.with_estimator("normalized", CostToBuy::new(1));
+        assert_eq!(4, replaced.estimators.len());
+        assert_eq!("normalized", replaced.estimators[3].name);
+    }
+
+    #[test]
+    fn owned_summary_matches_and_round_trips() {
+        let file = auctions::AuctionFile::from_json(AUCTIONS_JSON).unwrap();
+        let game_data = game_data();
+
+        let borrowed = AuctionsSummary::from_auction_file(&file, &game_data);
+        let owned = OwnedAuctionsSummary::from_auction_file(&file, &game_data);
+        assert_eq!(borrowed.to_owned_summary(), owned);
+        assert_eq!(owned, borrowed.into_owned());
+
+        let restored = OwnedAuctionsSummary::from_json(&owned.to_json().unwrap()).unwrap();
+        assert_eq!(owned, restored);
+        assert_eq!(
+            1000,
+            restored.item_level_summaries[&19019][&80].market_price
+        );
+    }
+
+    #[test]
+    fn counts_item_level_fallbacks() {
+        let json = r#"{"auctions": [
+            {"id": 1, "quantity": 1, "item": {"id": 19019, "bonus_lists": [6707],
+                "modifiers": [{"type": 9, "value": 50}]}, "buyout": 1000, "time_left": "LONG"},
+            {"id": 2, "quantity": 1, "item": {"id": 19019, "bonus_lists": [6708]},
+                "buyout": 1000, "time_left": "LONG"},
+            {"id": 3, "quantity": 1, "item": {"id": 19019, "bonus_lists": [6708],
+                "modifiers": [{"type": 9, "value": 37}]}, "buyout": 1000, "time_left": "LONG"},
+            {"id": 4, "quantity": 1, "item": {"id": 2589}, "buyout": 10, "time_left": "LONG"},
+            {"id": 5, "quantity": 1, "item": {"id": 2589}, "buyout": 10, "time_left": "LONG"}
+        ]}"#;
+        let mut game_data = game_data();
+        game_data.item_bonuses.curve_ids.insert(6707, 1748);
+        game_data.item_bonuses.curve_ids.insert(6708, 1749);
+        game_data.curve_points.insert(
+            1749,
+            crate::parse::ItemLevelCurve::from_points(&[(1.0, 6.0), (25.0, 31.0), (60.0, 146.0)]),
+        );
+        let file = auctions::AuctionFile::from_json(json).unwrap();
+
+        let summary = AuctionsSummary::from_auction_file(&file, &game_data);
+
+        let expected = FallbackCounts {
+            missing_base_ilvl: 2,
+            missing_drop_level: 1,
+            missing_curve: 1,
+        };
+        assert_eq!(expected, summary.fallbacks);
+        assert_eq!(4, summary.fallbacks.total());
+        assert_eq!(2, summary.item_level_auctions[&19019][&80].len());
+        assert_eq!(1, summary.item_level_auctions[&19019][&70].len());
+        assert_eq!(2, summary.item_level_auctions[&2589][&1].len());
+
+        let owned = OwnedAuctionsSummary::from_auction_file(&file, &game_data);
+        assert_eq!(expected, owned.fallbacks);
+        let streamed = AuctionsSummary::from_reader(json.as_bytes(), &game_data).unwrap();
+        assert_eq!(expected, streamed.fallbacks);
+    }
+
+    #[test]
+    fn groups_equippable_items_by_variant() {
+        let json = r#"{"auctions": [
+            {"id": 1, "quantity": 1, "item": {"id": 19019}, "buyout": 1000, "time_left": "LONG"},
+            {"id": 2, "quantity": 1, "item": {"id": 19019}, "buyout": 1100, "time_left": "LONG"},
+            {"id": 3, "quantity": 1, "item": {"id": 19019, "bonus_lists": [6514]},
+                "buyout": 9000, "time_left": "LONG"},
+            {"id": 4, "quantity": 1, "item": {"id": 2589}, "buyout": 10, "time_left": "LONG"}
+        ]}"#;
+        let game_data = GameData {
+            item_bonuses: data_tables::Db2ItemBonuses::from_csv(
+                "ID,Value[0],Value[1],Value[2],Value[3],ParentItemBonusListID,Type,OrderIndex\n1,1,8,0,0,6514,6,0",
+                data_tables::LoadMode::Strict,
+            )
+            .unwrap()
+            .0,
+            ..game_data()
+        };
+        let file = auctions::AuctionFile::from_json(json).unwrap();
+
+        let summary = AuctionsSummary::from_auction_file(&file, &game_data);
+
+        let plain = ItemVariant::new(80);
+        let socketed = ItemVariant::new(80).with_socket(true);
+        assert_eq!(3, summary.item_level_summaries[&19019][&80].num_auctions);
+        assert_eq!(2, summary.item_variant_auctions[&19019][&plain].len());
+        assert_eq!(1, summary.item_variant_auctions[&19019][&socketed].len());
+        assert_eq!(
+            1000,
+            summary.item_variant_summaries[&19019][&plain].market_price
+        );
+        assert_eq!(
+            9000,
+            summary.item_variant_summaries[&19019][&socketed].min_buyout
+        );
+        // only equippable items have variants
+        assert!(!summary.item_variant_summaries.contains_key(&2589));
+
+        let owned = summary.into_owned();
+        let restored = OwnedAuctionsSummary::from_json(&owned.to_json().unwrap()).unwrap();
+        assert_eq!(owned, restored);
+        assert_eq!(
+            1,
+            restored
+                .get(&SummaryKey::ItemVariant(19019, socketed))
+                .unwrap()
+                .num_auctions
+        );
+
+        let options =
+            SummaryOptions::default().with_variant_grouping(VariantGrouping::item_level_only());
+        let ilvl_only =
+            OwnedAuctionsSummary::from_auction_file_with_options(&file, &game_data, &options);
+        assert_eq!(1, ilvl_only.item_variant_summaries[&19019].len());
+        assert_eq!(
+            ilvl_only.item_level_summaries[&19019][&80],
+            ilvl_only.item_variant_summaries[&19019][&plain]
+        );
+    }
+
+    #[test]
+    fn owned_summary_can_be_sent_across_threads() {
+        let summary = AuctionsSummary::from_reader(AUCTIONS_JSON.as_bytes(), &GameData::default())
+            .unwrap()
+            .into_owned();
+
+        let handle = std::thread::spawn(move || summary.item_summaries.len());
+        assert_eq!(3, handle.join().unwrap());
+    }
+
+    #[test]
+    fn from_reader_matches_from_auction_file() {
+        let file = auctions::AuctionFile::from_json(AUCTIONS_JSON).unwrap();
+        let game_data = game_data();
+
+        let from_file = AuctionsSummary::from_auction_file(&file, &game_data);
+        let streamed = AuctionsSummary::from_reader(AUCTIONS_JSON.as_bytes(), &game_data).unwrap();
+
+        assert!(streamed.item_auctions.is_empty());
+        assert!(streamed.item_level_auctions.is_empty());
+        assert!(streamed.pet_auctions.is_empty());
+
+        assert_eq!(
+            from_file.item_summaries.len(),
+            streamed.item_summaries.len()
+        );
+        for (id, expected) in &from_file.item_summaries {
+            let actual = &streamed.item_summaries[id];
+            assert_eq!(expected.market_price, actual.market_price);
+            assert_eq!(expected.num_auctions, actual.num_auctions);
+            assert_eq!(expected.total_qty, actual.total_qty);
+        }
+        for (id, levels) in &from_file.item_level_summaries {
+            for (ilvl, expected) in levels {
+                let actual = &streamed.item_level_summaries[id][ilvl];
+                assert_eq!(expected.market_price, actual.market_price);
+            }
         }
+        assert_eq!(
+            from_file.pet_summaries[&85].market_price,
+            streamed.pet_summaries[&85].market_price
+        );
     }
 }
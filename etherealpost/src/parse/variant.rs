@@ -0,0 +1,370 @@
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::auctions;
+use crate::wow::data_tables::{SecondaryStat, TertiaryStat};
+use crate::wow::GameData;
+
+// Item modifier types which hold the stat types of the secondary stats chosen
+// when an item was crafted.
+const CRAFTING_STAT_1_MODIFIER_TYPE: u16 = 29;
+const CRAFTING_STAT_2_MODIFIER_TYPE: u16 = 30;
+
+/// A variant of an equippable item: its item level, along with the properties
+/// which make otherwise identical copies of an item sell for very different prices.
+///
+/// Which properties are set depends on the [`VariantGrouping`] the summary was
+/// built with. Properties which aren't grouped by are always `false` or `None`.
+///
+/// A variant is written as its item level, followed by each property which is set,
+/// separated by `:`. For example, `226:socket:speed:crit+haste` is an item level
+/// `226` copy with a socket, Speed, and Critical Strike and Haste as its secondary
+/// stats. This is also how variants are serialized, so they can be used as map keys
+/// in JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ItemVariant {
+    /// The effective item level of the item.
+    pub item_level: u32,
+
+    /// Whether or not the item has at least one socket.
+    pub socket: bool,
+
+    /// The tertiary stat of the item. Should an item have several, the first in the
+    /// order of [`TertiaryStat`] is used.
+    pub tertiary_stat: Option<TertiaryStat>,
+
+    /// The two secondary stats of the item, in the order of [`SecondaryStat`]. This
+    /// is only set for items with exactly two distinct secondary stats on their
+    /// bonuses or crafting modifiers, since items with fixed stats all share them.
+    pub secondary_stats: Option<(SecondaryStat, SecondaryStat)>,
+}
+
+impl ItemVariant {
+    /// A variant which is only its item level.
+    pub fn new(item_level: u32) -> Self {
+        ItemVariant {
+            item_level,
+            socket: false,
+            tertiary_stat: None,
+            secondary_stats: None,
+        }
+    }
+
+    /// Sets whether or not the variant has a socket.
+    pub fn with_socket(mut self, socket: bool) -> Self {
+        self.socket = socket;
+        self
+    }
+
+    /// Sets the tertiary stat of the variant.
+    pub fn with_tertiary_stat(mut self, stat: TertiaryStat) -> Self {
+        self.tertiary_stat = Some(stat);
+        self
+    }
+
+    /// Sets the secondary stats of the variant. They are put in order, so the
+    /// order they're given in doesn't matter.
+    pub fn with_secondary_stats(mut self, first: SecondaryStat, second: SecondaryStat) -> Self {
+        self.secondary_stats = Some((first.min(second), first.max(second)));
+        self
+    }
+}
+
+/// Which properties of an equippable item are used to group it into an [`ItemVariant`].
+///
+/// By default, items are grouped by all of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VariantGrouping {
+    /// Group by whether or not the item has a socket.
+    pub sockets: bool,
+
+    /// Group by the tertiary stat of the item.
+    pub tertiary_stats: bool,
+
+    /// Group by the pair of secondary stats of the item.
+    pub secondary_stats: bool,
+}
+
+impl Default for VariantGrouping {
+    fn default() -> Self {
+        VariantGrouping {
+            sockets: true,
+            tertiary_stats: true,
+            secondary_stats: true,
+        }
+    }
+}
+
+impl VariantGrouping {
+    /// A grouping by item level alone, which makes the variant summaries the same as
+    /// the item level summaries.
+    pub fn item_level_only() -> Self {
+        VariantGrouping {
+            sockets: false,
+            tertiary_stats: false,
+            secondary_stats: false,
+        }
+    }
+
+    /// Sets whether or not items are grouped by sockets.
+    pub fn with_sockets(mut self, sockets: bool) -> Self {
+        self.sockets = sockets;
+        self
+    }
+
+    /// Sets whether or not items are grouped by their tertiary stat.
+    pub fn with_tertiary_stats(mut self, tertiary_stats: bool) -> Self {
+        self.tertiary_stats = tertiary_stats;
+        self
+    }
+
+    /// Sets whether or not items are grouped by their pair of secondary stats.
+    pub fn with_secondary_stats(mut self, secondary_stats: bool) -> Self {
+        self.secondary_stats = secondary_stats;
+        self
+    }
+
+    /// Determines the variant of an auction item at an already resolved item level.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - The item of an auction.
+    /// * `item_level` - The effective item level of the item, see
+    ///   [ItemLevelTrace](`crate::parse::ItemLevelTrace`).
+    /// * `game_data` - The game data holding the item bonuses.
+    pub fn variant(
+        &self,
+        item: &auctions::Item,
+        item_level: u32,
+        game_data: &GameData,
+    ) -> ItemVariant {
+        let bonuses = &game_data.item_bonuses;
+        let bonus_ids: &[u32] = item.bonus_lists.as_deref().unwrap_or(&[]);
+        let mut variant = ItemVariant::new(item_level);
+
+        if self.sockets {
+            variant.socket = bonuses.resolve_sockets(bonus_ids) > 0;
+        }
+        if self.tertiary_stats {
+            variant.tertiary_stat = bonuses.resolve_tertiary_stats(bonus_ids).first().copied();
+        }
+        if self.secondary_stats {
+            let mut stats = bonuses.resolve_secondary_stats(bonus_ids);
+            stats.extend(crafting_stats(item));
+            stats.sort_unstable();
+            stats.dedup();
+            if let [first, second] = stats.as_slice() {
+                variant.secondary_stats = Some((*first, *second));
+            }
+        }
+
+        variant
+    }
+}
+
+/// The secondary stats chosen when the item was crafted, if it was.
+fn crafting_stats(item: &auctions::Item) -> impl Iterator<Item = SecondaryStat> + '_ {
+    item.modifiers
+        .iter()
+        .flatten()
+        .filter(|modifier| {
+            modifier.modifier_type == CRAFTING_STAT_1_MODIFIER_TYPE
+                || modifier.modifier_type == CRAFTING_STAT_2_MODIFIER_TYPE
+        })
+        .filter_map(|modifier| SecondaryStat::from_stat_type(modifier.value as i32))
+}
+
+fn tertiary_name(stat: TertiaryStat) -> &'static str {
+    match stat {
+        TertiaryStat::Speed => "speed",
+        TertiaryStat::Leech => "leech",
+        TertiaryStat::Avoidance => "avoidance",
+        TertiaryStat::Indestructible => "indestructible",
+    }
+}
+
+fn secondary_name(stat: SecondaryStat) -> &'static str {
+    match stat {
+        SecondaryStat::CriticalStrike => "crit",
+        SecondaryStat::Haste => "haste",
+        SecondaryStat::Mastery => "mastery",
+        SecondaryStat::Versatility => "versatility",
+    }
+}
+
+fn parse_secondary(name: &str) -> Option<SecondaryStat> {
+    match name {
+        "crit" => Some(SecondaryStat::CriticalStrike),
+        "haste" => Some(SecondaryStat::Haste),
+        "mastery" => Some(SecondaryStat::Mastery),
+        "versatility" => Some(SecondaryStat::Versatility),
+        _ => None,
+    }
+}
+
+impl fmt::Display for ItemVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.item_level)?;
+        if self.socket {
+            write!(f, ":socket")?;
+        }
+        if let Some(stat) = self.tertiary_stat {
+            write!(f, ":{}", tertiary_name(stat))?;
+        }
+        if let Some((first, second)) = self.secondary_stats {
+            write!(f, ":{}+{}", secondary_name(first), secondary_name(second))?;
+        }
+        Ok(())
+    }
+}
+
+/// The error returned when an [`ItemVariant`] can't be parsed from a string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseVariantError(String);
+
+impl fmt::Display for ParseVariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid item variant: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseVariantError {}
+
+impl FromStr for ItemVariant {
+    type Err = ParseVariantError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseVariantError(s.to_string());
+        let mut parts = s.split(':');
+
+        let item_level = parts
+            .next()
+            .and_then(|ilvl| ilvl.parse().ok())
+            .ok_or_else(invalid)?;
+        let mut variant = ItemVariant::new(item_level);
+
+        for part in parts {
+            match part {
+                "socket" => variant.socket = true,
+                "speed" => variant.tertiary_stat = Some(TertiaryStat::Speed),
+                "leech" => variant.tertiary_stat = Some(TertiaryStat::Leech),
+                "avoidance" => variant.tertiary_stat = Some(TertiaryStat::Avoidance),
+                "indestructible" => variant.tertiary_stat = Some(TertiaryStat::Indestructible),
+                pair => {
+                    let mut stats = pair.split('+').map(parse_secondary);
+                    match (stats.next(), stats.next(), stats.next()) {
+                        (Some(Some(first)), Some(Some(second)), None) => {
+                            variant = variant.with_secondary_stats(first, second)
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+            }
+        }
+
+        Ok(variant)
+    }
+}
+
+impl Serialize for ItemVariant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemVariant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wow::data_tables::{Db2ItemBonuses, LoadMode};
+
+    fn game_data() -> GameData {
+        let csv = "ID,Value[0],Value[1],Value[2],Value[3],ParentItemBonusListID,Type,OrderIndex\n\
+            1,1,8,0,0,6514,6,0\n\
+            2,61,2223,0,0,6646,2,0\n\
+            3,63,2223,0,0,6647,2,0\n\
+            4,32,3000,0,0,6648,2,0\n\
+            5,36,3000,0,0,6648,2,1";
+        GameData {
+            item_bonuses: Db2ItemBonuses::from_csv(csv, LoadMode::Strict).unwrap().0,
+            ..GameData::default()
+        }
+    }
+
+    fn item(json: &str) -> auctions::Item {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn groups_by_sockets_tertiaries_and_secondaries() {
+        let game_data = game_data();
+        let grouping = VariantGrouping::default();
+
+        let plain = item(r#"{"id": 1, "bonus_lists": [1]}"#);
+        assert_eq!(
+            ItemVariant::new(226),
+            grouping.variant(&plain, 226, &game_data)
+        );
+
+        let fancy = item(r#"{"id": 1, "bonus_lists": [6647, 6514, 6646, 6648]}"#);
+        assert_eq!(
+            ItemVariant::new(226)
+                .with_socket(true)
+                .with_tertiary_stat(TertiaryStat::Speed)
+                .with_secondary_stats(SecondaryStat::Haste, SecondaryStat::CriticalStrike),
+            grouping.variant(&fancy, 226, &game_data)
+        );
+
+        let without_sockets = grouping.with_sockets(false).with_secondary_stats(false);
+        assert_eq!(
+            ItemVariant::new(226).with_tertiary_stat(TertiaryStat::Speed),
+            without_sockets.variant(&fancy, 226, &game_data)
+        );
+        assert_eq!(
+            ItemVariant::new(226),
+            VariantGrouping::item_level_only().variant(&fancy, 226, &game_data)
+        );
+    }
+
+    #[test]
+    fn crafted_items_use_their_modifiers() {
+        let crafted = item(
+            r#"{"id": 1, "modifiers": [{"type": 29, "value": 49}, {"type": 30, "value": 40}]}"#,
+        );
+        assert_eq!(
+            Some((SecondaryStat::Mastery, SecondaryStat::Versatility)),
+            VariantGrouping::default()
+                .variant(&crafted, 190, &game_data())
+                .secondary_stats
+        );
+    }
+
+    #[test]
+    fn round_trips_through_strings() {
+        let variant = ItemVariant::new(226)
+            .with_socket(true)
+            .with_tertiary_stat(TertiaryStat::Avoidance)
+            .with_secondary_stats(SecondaryStat::Versatility, SecondaryStat::Haste);
+        assert_eq!(
+            "226:socket:avoidance:haste+versatility",
+            variant.to_string()
+        );
+        assert_eq!(Ok(variant), variant.to_string().parse());
+        assert_eq!(Ok(ItemVariant::new(90)), "90".parse());
+
+        assert!("".parse::<ItemVariant>().is_err());
+        assert!("226:sparkly".parse::<ItemVariant>().is_err());
+        assert!("226:crit+haste+mastery".parse::<ItemVariant>().is_err());
+
+        let json = serde_json::to_string(&variant).unwrap();
+        assert_eq!(variant, serde_json::from_str::<ItemVariant>(&json).unwrap());
+    }
+}
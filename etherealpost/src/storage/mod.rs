@@ -0,0 +1,3 @@
+pub mod price_store;
+
+pub use price_store::{PriceStore, Snapshot};
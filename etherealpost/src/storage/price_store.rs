@@ -0,0 +1,671 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::parse::{ItemSummary, OwnedAuctionsSummary, SummaryKey};
+
+const SNAPSHOT_EXTENSION: &str = "snapshots";
+
+/// A single summarized auction house scan, as stored in a [`PriceStore`].
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Snapshot {
+    /// When the scan took place, as seconds since the Unix epoch.
+    pub timestamp: u64,
+
+    /// The summary of the scan.
+    pub summary: OwnedAuctionsSummary,
+}
+
+/// An embedded, on-disk store of historical [`OwnedAuctionsSummary`] snapshots.
+///
+/// The store is a directory containing one append-only file per realm. Each
+/// snapshot is written as a block of lines: a header made up of its timestamp, the
+/// number of summaries, and the fallback counts as JSON, followed by one line per
+/// summary holding its [`SummaryKey`] and the [`ItemSummary`] as JSON. Only the
+/// summaries which can be looked up by a [`SummaryKey`] are stored, so the bid-only
+/// summaries are left out. Snapshots are never rewritten, so a crash can at worst
+/// leave a truncated final block. Reads ignore it, and the next
+/// [append](`Self::append`) cuts it off before writing.
+///
+/// Realms are identified by a caller-chosen key (eg, `us-3676` for a connected
+/// realm, or `us-commodities` for a region's commodities). Keys are used as file
+/// names, so they may only contain ASCII letters, digits, `-` and `_`.
+///
+/// The offset of every snapshot, and of every summary within it, is indexed in
+/// memory the first time a realm is accessed. Queries only read and parse what they
+/// return, so the [history](`Self::history`) of a single item doesn't depend on how
+/// many other items were listed.
+///
+/// The index only sees snapshots which were appended before it was built, or which
+/// were appended through the same `PriceStore`. Snapshots appended by another process
+/// are picked up by opening a new store. Only one process should append to a realm
+/// at a time.
+///
+/// # Example
+///
+/// ```no_run
+/// use etherealpost::parse::{OwnedAuctionsSummary, SummaryKey};
+/// use etherealpost::storage::PriceStore;
+///
+/// let mut store = PriceStore::open("/var/lib/etherealpost/history").unwrap();
+/// # let summary = OwnedAuctionsSummary::default();
+/// store.append("us-3676", 1615921392, &summary).unwrap();
+///
+/// // market value of Arcanite Bar over the last 14 days
+/// let now = 1615921392;
+/// let history = store
+///     .history("us-3676", &SummaryKey::Item(12360), now - 14 * 86400, now)
+///     .unwrap();
+/// for (timestamp, item) in history {
+///     println!("{}: {}", timestamp, item.market_price);
+/// }
+/// ```
+pub struct PriceStore {
+    dir: PathBuf,
+    indexes: HashMap<String, RealmIndex>,
+}
+
+/// The locations of the snapshots and summaries within a realm's file.
+#[derive(Default)]
+struct RealmIndex {
+    snapshots: Vec<IndexEntry>,
+    keys: HashMap<SummaryKey, Vec<IndexEntry>>,
+    /// Where the last complete snapshot ends in the file.
+    end: u64,
+}
+
+/// The location of a single snapshot block, or a single summary line, within a
+/// realm's file.
+#[derive(Clone, Copy)]
+struct IndexEntry {
+    timestamp: u64,
+    offset: u64,
+    len: u64,
+}
+
+impl PriceStore {
+    /// Opens the store in the directory `dir`, creating it if it doesn't exist.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<PriceStore> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(PriceStore {
+            dir,
+            indexes: HashMap::new(),
+        })
+    }
+
+    /// The keys of all of the realms which have at least one snapshot stored.
+    pub fn realms(&self) -> io::Result<Vec<String>> {
+        let mut realms = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(SNAPSHOT_EXTENSION) {
+                continue;
+            }
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                realms.push(stem.to_string());
+            }
+        }
+        realms.sort_unstable();
+        Ok(realms)
+    }
+
+    /// Appends a snapshot for a realm.
+    ///
+    /// Snapshots must be appended in chronological order, so `timestamp` has to be
+    /// later than that of the latest snapshot already stored for the realm.
+    pub fn append(
+        &mut self,
+        realm: &str,
+        timestamp: u64,
+        summary: &OwnedAuctionsSummary,
+    ) -> io::Result<()> {
+        let path = self.realm_path(realm)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        // the file changed since it was indexed, either because another store
+        // appended to it or because it ends in a partially written snapshot
+        let file_len = file.metadata()?.len();
+        if self.indexes.get(realm).map(|index| index.end) != Some(file_len) {
+            let index = self.build_index(realm)?;
+            // cut off a partially written snapshot, so the next one starts on a
+            // fresh line
+            if index.end < file_len {
+                file.set_len(index.end)?;
+            }
+            self.indexes.insert(realm.to_string(), index);
+        }
+        let index = &self.indexes[realm];
+
+        if let Some(last) = index.snapshots.last() {
+            if timestamp <= last.timestamp {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "snapshot at {} is not after the latest snapshot for {} at {}",
+                        timestamp, realm, last.timestamp
+                    ),
+                ));
+            }
+        }
+
+        let summaries: Vec<(SummaryKey, &ItemSummary)> = summary.iter().collect();
+        let mut block = format!(
+            "{}\t{}\t{}\n",
+            timestamp,
+            summaries.len(),
+            serde_json::to_string(&summary.fallbacks)?
+        );
+        // the offsets of the summary lines, relative to the start of the block
+        let mut lines = Vec::with_capacity(summaries.len());
+        for (key, item_summary) in summaries {
+            let start = block.len();
+            block.push_str(&serde_json::to_string(&key)?);
+            block.push('\t');
+            block.push_str(&serde_json::to_string(item_summary)?);
+            block.push('\n');
+            lines.push((key, start as u64, (block.len() - start) as u64));
+        }
+
+        let offset = file.seek(SeekFrom::End(0))?;
+        file.write_all(block.as_bytes())?;
+        file.sync_data()?;
+
+        let index = self.indexes.get_mut(realm).unwrap();
+        index.end = offset + block.len() as u64;
+        index.snapshots.push(IndexEntry {
+            timestamp,
+            offset,
+            len: block.len() as u64,
+        });
+        for (key, start, len) in lines {
+            index.keys.entry(key).or_default().push(IndexEntry {
+                timestamp,
+                offset: offset + start,
+                len,
+            });
+        }
+        Ok(())
+    }
+
+    /// The timestamps of every snapshot stored for a realm, oldest first.
+    pub fn timestamps(&mut self, realm: &str) -> io::Result<Vec<u64>> {
+        Ok(self
+            .index(realm)?
+            .snapshots
+            .iter()
+            .map(|e| e.timestamp)
+            .collect())
+    }
+
+    /// The most recent snapshot for a realm.
+    pub fn latest(&mut self, realm: &str) -> io::Result<Option<Snapshot>> {
+        let entry = self.index(realm)?.snapshots.last().copied();
+        self.read_snapshots(realm, entry)
+            .map(|mut snapshots| snapshots.pop())
+    }
+
+    /// The snapshot which was current at `timestamp`, meaning the latest snapshot
+    /// taken at or before that time.
+    pub fn at(&mut self, realm: &str, timestamp: u64) -> io::Result<Option<Snapshot>> {
+        let snapshots = &self.index(realm)?.snapshots;
+        let position = snapshots.partition_point(|e| e.timestamp <= timestamp);
+        let entry = match position {
+            0 => None,
+            _ => Some(snapshots[position - 1]),
+        };
+        self.read_snapshots(realm, entry)
+            .map(|mut snapshots| snapshots.pop())
+    }
+
+    /// All snapshots for a realm taken between `from` and `to`, inclusive, oldest first.
+    pub fn range(&mut self, realm: &str, from: u64, to: u64) -> io::Result<Vec<Snapshot>> {
+        let entries = between(&self.index(realm)?.snapshots, from, to).to_vec();
+        self.read_snapshots(realm, entries)
+    }
+
+    /// The history of a single item, item level or pet species summary for a realm
+    /// between `from` and `to`, inclusive, oldest first.
+    ///
+    /// Snapshots in which the key has no summary (for example because nothing was
+    /// listed at the time) are skipped. Only the summaries of `key` are read.
+    pub fn history(
+        &mut self,
+        realm: &str,
+        key: &SummaryKey,
+        from: u64,
+        to: u64,
+    ) -> io::Result<Vec<(u64, ItemSummary)>> {
+        let entries = match self.index(realm)?.keys.get(key) {
+            Some(entries) => between(entries, from, to).to_vec(),
+            None => return Ok(Vec::new()),
+        };
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut file = File::open(self.realm_path(realm)?)?;
+        let mut history = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let line = read_at(&mut file, &entry)?;
+            let (_, summary) = split_summary(&line).ok_or_else(|| corrupt(realm, entry.offset))?;
+            history.push((entry.timestamp, summary));
+        }
+        Ok(history)
+    }
+
+    /// Reads and parses snapshots, opening the realm's file only once.
+    fn read_snapshots<I>(&self, realm: &str, entries: I) -> io::Result<Vec<Snapshot>>
+    where
+        I: IntoIterator<Item = IndexEntry>,
+    {
+        let mut entries = entries.into_iter().peekable();
+        if entries.peek().is_none() {
+            return Ok(Vec::new());
+        }
+
+        let mut file = File::open(self.realm_path(realm)?)?;
+        entries
+            .map(|entry| {
+                let block = read_at(&mut file, &entry)?;
+                parse_block(&block).ok_or_else(|| corrupt(realm, entry.offset))
+            })
+            .collect()
+    }
+
+    /// Returns the index of a realm's snapshots, building it from the realm's
+    /// file if this is the first time the realm is accessed.
+    fn index(&mut self, realm: &str) -> io::Result<&RealmIndex> {
+        if !self.indexes.contains_key(realm) {
+            let index = self.build_index(realm)?;
+            self.indexes.insert(realm.to_string(), index);
+        }
+        Ok(&self.indexes[realm])
+    }
+
+    fn build_index(&self, realm: &str) -> io::Result<RealmIndex> {
+        let path = self.realm_path(realm)?;
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(RealmIndex::default()),
+            Err(e) => return Err(e),
+        };
+
+        let mut reader = BufReader::new(file);
+        let mut index = RealmIndex::default();
+        let mut offset = 0;
+        let mut line = String::new();
+
+        'blocks: loop {
+            let block_offset = offset;
+            let (timestamp, num_summaries) = match read_full_line(&mut reader, &mut line)? {
+                Some(len) => {
+                    offset += len;
+                    split_header(&line)
+                        .map(|(timestamp, num_summaries, _)| (timestamp, num_summaries))
+                        .ok_or_else(|| corrupt(realm, block_offset))?
+                }
+                None => break,
+            };
+
+            let mut summaries = Vec::new();
+            for _ in 0..num_summaries {
+                let len = match read_full_line(&mut reader, &mut line)? {
+                    Some(len) => len,
+                    None => break 'blocks,
+                };
+                // only the key is parsed, the summary is read when it is queried
+                let key: SummaryKey = line
+                    .split_once('\t')
+                    .and_then(|(key, _)| serde_json::from_str(key).ok())
+                    .ok_or_else(|| corrupt(realm, offset))?;
+                summaries.push((
+                    key,
+                    IndexEntry {
+                        timestamp,
+                        offset,
+                        len,
+                    },
+                ));
+                offset += len;
+            }
+
+            index.snapshots.push(IndexEntry {
+                timestamp,
+                offset: block_offset,
+                len: offset - block_offset,
+            });
+            for (key, entry) in summaries {
+                index.keys.entry(key).or_default().push(entry);
+            }
+        }
+
+        // a block cut short is left alone here, since this is also used by reads
+        // and the block could still be being written
+        index.end = index.snapshots.last().map_or(0, |e| e.offset + e.len);
+        Ok(index)
+    }
+
+    fn realm_path(&self, realm: &str) -> io::Result<PathBuf> {
+        let valid = !realm.is_empty()
+            && realm
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid realm key '{}'", realm),
+            ));
+        }
+        Ok(self.dir.join(format!("{}.{}", realm, SNAPSHOT_EXTENSION)))
+    }
+}
+
+/// The entries of a chronological index taken between `from` and `to`, inclusive.
+fn between(entries: &[IndexEntry], from: u64, to: u64) -> &[IndexEntry] {
+    let start = entries.partition_point(|e| e.timestamp < from);
+    let end = entries.partition_point(|e| e.timestamp <= to);
+    &entries[start..end.max(start)]
+}
+
+/// Reads the bytes of a single index entry.
+fn read_at(file: &mut File, entry: &IndexEntry) -> io::Result<String> {
+    file.seek(SeekFrom::Start(entry.offset))?;
+    let mut text = String::with_capacity(entry.len as usize);
+    file.take(entry.len).read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Reads the next line, returning its length, or `None` at the end of the file or
+/// when the final line was only partially written.
+fn read_full_line<R: BufRead>(reader: &mut R, line: &mut String) -> io::Result<Option<u64>> {
+    line.clear();
+    let len = reader.read_line(line)?;
+    if len == 0 || !line.ends_with('\n') {
+        return Ok(None);
+    }
+    Ok(Some(len as u64))
+}
+
+/// Splits the header line of a snapshot block into its timestamp, the number of
+/// summary lines which follow it, and the fallback counts JSON.
+fn split_header(line: &str) -> Option<(u64, usize, &str)> {
+    let mut parts = line.splitn(3, '\t');
+    let timestamp = parts.next()?.parse().ok()?;
+    let num_summaries = parts.next()?.parse().ok()?;
+    Some((timestamp, num_summaries, parts.next()?))
+}
+
+/// Splits and parses a summary line into its key and summary.
+fn split_summary(line: &str) -> Option<(SummaryKey, ItemSummary)> {
+    let (key, summary) = line.split_once('\t')?;
+    Some((
+        serde_json::from_str(key).ok()?,
+        serde_json::from_str(summary).ok()?,
+    ))
+}
+
+/// Parses a whole snapshot block back into a snapshot.
+fn parse_block(block: &str) -> Option<Snapshot> {
+    let mut lines = block.lines();
+    let (timestamp, num_summaries, fallbacks) = split_header(lines.next()?)?;
+
+    let mut summary = OwnedAuctionsSummary {
+        fallbacks: serde_json::from_str(fallbacks).ok()?,
+        ..OwnedAuctionsSummary::default()
+    };
+    for _ in 0..num_summaries {
+        let (key, item_summary) = split_summary(lines.next()?)?;
+        summary.insert(key, item_summary);
+    }
+
+    Some(Snapshot { timestamp, summary })
+}
+
+fn corrupt(realm: &str, offset: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("corrupt snapshot for {} at byte {}", realm, offset),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn temp_store(name: &str) -> (PathBuf, PriceStore) {
+        let dir = env::temp_dir().join(format!("etherealpost-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = PriceStore::open(&dir).unwrap();
+        (dir, store)
+    }
+
+    fn summary_with_price(item_id: u64, market_price: u64) -> OwnedAuctionsSummary {
+        let mut summary = OwnedAuctionsSummary::default();
+        summary.item_summaries.insert(
+            item_id,
+            ItemSummary {
+                market_price,
+                min_buyout: market_price,
+                total_qty: 1,
+                num_auctions: 1,
+                ..ItemSummary::default()
+            },
+        );
+        summary
+    }
+
+    #[test]
+    fn point_in_time_and_latest() {
+        let (dir, mut store) = temp_store("store-point");
+        store
+            .append("us-3676", 100, &summary_with_price(5, 10))
+            .unwrap();
+        store
+            .append("us-3676", 200, &summary_with_price(5, 20))
+            .unwrap();
+        store
+            .append("us-3676", 300, &summary_with_price(5, 30))
+            .unwrap();
+
+        assert_eq!(300, store.latest("us-3676").unwrap().unwrap().timestamp);
+        assert!(store.at("us-3676", 99).unwrap().is_none());
+        assert_eq!(100, store.at("us-3676", 100).unwrap().unwrap().timestamp);
+        assert_eq!(200, store.at("us-3676", 299).unwrap().unwrap().timestamp);
+        assert_eq!(300, store.at("us-3676", 1000).unwrap().unwrap().timestamp);
+        assert!(store.latest("us-11").unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn range_and_history() {
+        let (dir, mut store) = temp_store("store-range");
+        store
+            .append("us-3676", 100, &summary_with_price(5, 10))
+            .unwrap();
+        store
+            .append("us-3676", 200, &summary_with_price(6, 99))
+            .unwrap();
+        store
+            .append("us-3676", 300, &summary_with_price(5, 30))
+            .unwrap();
+        store
+            .append("us-3676", 400, &summary_with_price(5, 40))
+            .unwrap();
+
+        let range = store.range("us-3676", 150, 300).unwrap();
+        assert_eq!(
+            vec![200, 300],
+            range.iter().map(|s| s.timestamp).collect::<Vec<_>>()
+        );
+
+        let history = store
+            .history("us-3676", &SummaryKey::Item(5), 0, 350)
+            .unwrap();
+        assert_eq!(
+            vec![(100, 10), (300, 30)],
+            history
+                .iter()
+                .map(|(t, s)| (*t, s.market_price))
+                .collect::<Vec<_>>()
+        );
+        assert!(store.range("us-3676", 500, 100).unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reopened_store_reads_existing_snapshots() {
+        let (dir, mut store) = temp_store("store-reopen");
+        store
+            .append("eu-1084", 100, &summary_with_price(5, 10))
+            .unwrap();
+        store
+            .append("us-3676", 150, &summary_with_price(5, 15))
+            .unwrap();
+        store
+            .append("eu-1084", 200, &summary_with_price(5, 20))
+            .unwrap();
+        drop(store);
+
+        let mut store = PriceStore::open(&dir).unwrap();
+        assert_eq!(vec!["eu-1084", "us-3676"], store.realms().unwrap());
+        assert_eq!(vec![100, 200], store.timestamps("eu-1084").unwrap());
+        let latest = store.latest("eu-1084").unwrap().unwrap();
+        assert_eq!(summary_with_price(5, 20), latest.summary);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn truncated_final_snapshot_is_ignored() {
+        let (dir, mut store) = temp_store("store-truncated");
+        store
+            .append("us-3676", 100, &summary_with_price(5, 10))
+            .unwrap();
+        drop(store);
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.join("us-3676.snapshots"))
+            .unwrap();
+        file.write_all(b"200\t{\"item_summ").unwrap();
+        let len = file.metadata().unwrap().len();
+
+        let mut store = PriceStore::open(&dir).unwrap();
+        assert_eq!(vec![100], store.timestamps("us-3676").unwrap());
+        assert!(store.latest("us-3676").unwrap().is_some());
+        // reads leave the partial snapshot alone, only appending cuts it off
+        assert_eq!(len, file.metadata().unwrap().len());
+
+        store
+            .append("us-3676", 200, &summary_with_price(5, 20))
+            .unwrap();
+        let mut store = PriceStore::open(&dir).unwrap();
+        assert_eq!(vec![100, 200], store.timestamps("us-3676").unwrap());
+        assert_eq!(
+            summary_with_price(5, 20),
+            store.latest("us-3676").unwrap().unwrap().summary
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn every_kind_of_summary_round_trips() {
+        let (dir, mut store) = temp_store("store-kinds");
+        let mut summary = summary_with_price(5, 10);
+        let level = summary.item_summaries[&5].clone();
+        summary.insert(SummaryKey::ItemLevel(5, 200), level.clone());
+        summary.insert(SummaryKey::Pet(39), level);
+        summary.fallbacks.missing_base_ilvl = 3;
+        store.append("us-3676", 100, &summary).unwrap();
+        store
+            .append("us-3676", 200, &summary_with_price(5, 20))
+            .unwrap();
+        drop(store);
+
+        let mut store = PriceStore::open(&dir).unwrap();
+        assert_eq!(summary, store.at("us-3676", 150).unwrap().unwrap().summary);
+        assert_eq!(
+            vec![100],
+            store
+                .history("us-3676", &SummaryKey::Pet(39), 0, 300)
+                .unwrap()
+                .iter()
+                .map(|(t, _)| *t)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            2,
+            store
+                .history("us-3676", &SummaryKey::Item(5), 0, 300)
+                .unwrap()
+                .len()
+        );
+        assert!(store
+            .history("us-3676", &SummaryKey::Item(6), 0, 300)
+            .unwrap()
+            .is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn partially_written_snapshot_is_ignored() {
+        let (dir, mut store) = temp_store("store-partial");
+        store
+            .append("us-3676", 100, &summary_with_price(5, 10))
+            .unwrap();
+        drop(store);
+
+        // the header promises two summaries, but only one made it to disk
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.join("us-3676.snapshots"))
+            .unwrap();
+        file.write_all(b"200\t2\t{}\n{\"Item\":5}\t{}\n").unwrap();
+
+        let mut store = PriceStore::open(&dir).unwrap();
+        assert_eq!(vec![100], store.timestamps("us-3676").unwrap());
+        assert_eq!(
+            1,
+            store
+                .history("us-3676", &SummaryKey::Item(5), 0, 300)
+                .unwrap()
+                .len()
+        );
+
+        store
+            .append("us-3676", 200, &summary_with_price(5, 20))
+            .unwrap();
+        let mut store = PriceStore::open(&dir).unwrap();
+        assert_eq!(vec![100, 200], store.timestamps("us-3676").unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn out_of_order_and_invalid_keys_are_rejected() {
+        let (dir, mut store) = temp_store("store-invalid");
+        store
+            .append("us-3676", 200, &summary_with_price(5, 10))
+            .unwrap();
+        assert!(store
+            .append("us-3676", 200, &summary_with_price(5, 10))
+            .is_err());
+        assert!(store
+            .append("us-3676", 100, &summary_with_price(5, 10))
+            .is_err());
+        assert!(store
+            .append("../us", 300, &summary_with_price(5, 10))
+            .is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
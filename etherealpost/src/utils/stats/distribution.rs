@@ -0,0 +1,227 @@
+//! Quantity-weighted percentiles, medians, means and histograms.
+//!
+//! Like [std_dev_amount_qty](`crate::stats::std_dev_amount_qty`), everything here
+//! works on `(price, quantity)` pairs where each pair stands for `quantity` units at
+//! the same `price`. The pairs are never expanded into one price per unit, so an
+//! auction of 200 units costs no more than an auction of 1.
+
+use serde::{Deserialize, Serialize};
+
+/// The distribution of the units of a set of `(price, quantity)` pairs, sorted by price.
+///
+/// # Example
+///
+/// ```rust
+/// use etherealpost::stats::distribution::QtyDistribution;
+///
+/// let mut data = [(60000, 5), (50000, 10), (54500, 15), (40000, 1)];
+/// let distribution = QtyDistribution::new(&mut data).unwrap();
+///
+/// assert_eq!(31, distribution.total_qty());
+/// assert_eq!(40000, distribution.percentile(0.0));
+/// assert_eq!(50000, distribution.percentile(25.0));
+/// assert_eq!(54500, distribution.median());
+/// assert_eq!(60000, distribution.percentile(95.0));
+/// ```
+pub struct QtyDistribution<'a> {
+    price_qty: &'a [(u64, u64)],
+    total_qty: u64,
+}
+
+/// A range of prices in a histogram, along with how many units are priced within it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HistogramBucket {
+    /// The lowest price in the bucket, inclusive.
+    pub min_price: u64,
+
+    /// The highest price in the bucket, exclusive.
+    pub max_price: u64,
+
+    /// The total quantity of units priced within the bucket.
+    pub qty: u64,
+}
+
+impl<'a> QtyDistribution<'a> {
+    /// Sorts the `(price, quantity)` pairs by price, returning `None` if there
+    /// isn't a single unit to describe.
+    pub fn new(price_qty: &'a mut [(u64, u64)]) -> Option<Self> {
+        price_qty.sort_unstable_by_key(|(price, _)| *price);
+        QtyDistribution::from_sorted(price_qty)
+    }
+
+    /// Describes `(price, quantity)` pairs which are already sorted by price,
+    /// returning `None` if there isn't a single unit to describe.
+    pub fn from_sorted(price_qty: &'a [(u64, u64)]) -> Option<Self> {
+        let total_qty = price_qty.iter().map(|(_, qty)| qty).sum();
+        if total_qty == 0 {
+            return None;
+        }
+
+        Some(QtyDistribution {
+            price_qty,
+            total_qty,
+        })
+    }
+
+    /// The total quantity of units.
+    pub fn total_qty(&self) -> u64 {
+        self.total_qty
+    }
+
+    /// The price of the unit at the given percentile, from `0.0` to `100.0`.
+    ///
+    /// This uses the nearest rank: the price of the cheapest unit which has at least
+    /// `percentile`% of all units priced at or below it. Percentiles outside of the
+    /// range are clamped to it.
+    pub fn percentile(&self, percentile: f64) -> u64 {
+        let percentile = percentile.clamp(0.0, 100.0);
+        let rank = ((percentile / 100.0) * self.total_qty as f64).ceil() as u64;
+        self.price_at_rank(rank.max(1))
+    }
+
+    /// The weighted median price.
+    ///
+    /// When there is an even number of units and the two middle units have different
+    /// prices, this is the average of the two, rounded down.
+    pub fn median(&self) -> u64 {
+        let lower = self.price_at_rank(self.total_qty.div_ceil(2));
+        if self.total_qty.is_multiple_of(2) {
+            let upper = self.price_at_rank(self.total_qty / 2 + 1);
+            (lower + upper) / 2
+        } else {
+            lower
+        }
+    }
+
+    /// The mean price of all units.
+    pub fn mean(&self) -> f64 {
+        let price_sum: f64 = self
+            .price_qty
+            .iter()
+            .map(|(price, qty)| *price as f64 * *qty as f64)
+            .sum();
+        price_sum / self.total_qty as f64
+    }
+
+    /// Divides the range between the lowest and highest price into `num_buckets`
+    /// buckets of equal width, and counts the units priced within each of them.
+    ///
+    /// Fewer buckets are returned when the range has fewer prices than there are
+    /// buckets, since each bucket covers at least one copper. No buckets are
+    /// returned when `num_buckets` is `0`.
+    pub fn histogram(&self, num_buckets: usize) -> Vec<HistogramBucket> {
+        if num_buckets == 0 {
+            return Vec::new();
+        }
+
+        let min_price = self.price_qty.first().unwrap().0;
+        let max_price = self.price_qty.last().unwrap().0;
+        let width = (max_price - min_price + 1).div_ceil(num_buckets as u64);
+        let num_buckets = (max_price - min_price + 1).div_ceil(width);
+
+        let mut buckets: Vec<HistogramBucket> = (0..num_buckets)
+            .map(|i| HistogramBucket {
+                min_price: min_price + i * width,
+                max_price: min_price + (i + 1) * width,
+                qty: 0,
+            })
+            .collect();
+
+        for (price, qty) in self.price_qty {
+            buckets[((price - min_price) / width) as usize].qty += qty;
+        }
+        buckets
+    }
+
+    /// The price of the unit at a 1-based `rank`, counting from the cheapest unit.
+    fn price_at_rank(&self, rank: u64) -> u64 {
+        let mut seen_qty: u64 = 0;
+        for (price, qty) in self.price_qty {
+            seen_qty += qty;
+            if seen_qty >= rank {
+                return *price;
+            }
+        }
+        self.price_qty.last().unwrap().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_weigh_by_quantity() {
+        let mut data = [(100, 1), (10, 98), (50, 1)];
+        let distribution = QtyDistribution::new(&mut data).unwrap();
+
+        assert_eq!(10, distribution.percentile(5.0));
+        assert_eq!(10, distribution.percentile(98.0));
+        assert_eq!(50, distribution.percentile(99.0));
+        assert_eq!(100, distribution.percentile(99.5));
+        assert_eq!(100, distribution.percentile(100.0));
+        assert_eq!(10, distribution.percentile(-5.0));
+        assert_eq!(100, distribution.percentile(150.0));
+    }
+
+    #[test]
+    fn median_averages_the_middle_units() {
+        let mut even = [(10, 2), (20, 2)];
+        assert_eq!(15, QtyDistribution::new(&mut even).unwrap().median());
+
+        let mut odd = [(10, 2), (20, 1), (30, 2)];
+        assert_eq!(20, QtyDistribution::new(&mut odd).unwrap().median());
+
+        let mut single = [(42, 1)];
+        assert_eq!(42, QtyDistribution::new(&mut single).unwrap().median());
+    }
+
+    #[test]
+    fn mean_weighs_by_quantity() {
+        let mut data = [(1, 4), (3, 2), (4, 1)];
+        assert_eq!(2.0, QtyDistribution::new(&mut data).unwrap().mean());
+    }
+
+    #[test]
+    fn empty_distribution() {
+        assert!(QtyDistribution::new(&mut []).is_none());
+        assert!(QtyDistribution::new(&mut [(10, 0)]).is_none());
+    }
+
+    #[test]
+    fn histogram_buckets() {
+        let mut data = [(100, 3), (149, 1), (150, 2), (199, 5)];
+        let distribution = QtyDistribution::new(&mut data).unwrap();
+
+        let expected = vec![
+            HistogramBucket {
+                min_price: 100,
+                max_price: 150,
+                qty: 4,
+            },
+            HistogramBucket {
+                min_price: 150,
+                max_price: 200,
+                qty: 7,
+            },
+        ];
+        assert_eq!(expected, distribution.histogram(2));
+        assert!(distribution.histogram(0).is_empty());
+
+        let hist = distribution.histogram(3);
+        assert_eq!(3, hist.len());
+        assert_eq!(11, hist.iter().map(|bucket| bucket.qty).sum::<u64>());
+    }
+
+    #[test]
+    fn histogram_has_at_least_a_copper_per_bucket() {
+        let mut data = [(10, 1), (12, 1)];
+        let hist = QtyDistribution::new(&mut data).unwrap().histogram(10);
+
+        assert_eq!(3, hist.len());
+        assert_eq!(
+            vec![1, 0, 1],
+            hist.iter().map(|b| b.qty).collect::<Vec<_>>()
+        );
+    }
+}
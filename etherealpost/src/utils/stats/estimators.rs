@@ -0,0 +1,327 @@
+//! Estimators which reduce the `(price, quantity)` pairs of a group of auctions to
+//! a single price.
+//!
+//! The [normalized market price](`crate::stats::normalized_market_price_with_qty`)
+//! is the estimator used for [ItemSummary::market_price](`crate::parse::ItemSummary::market_price`),
+//! but it isn't the only reasonable one. Any number of named estimators can be
+//! calculated alongside it with
+//! [SummaryOptions::with_estimator](`crate::parse::SummaryOptions::with_estimator`),
+//! so that they can be compared on real data.
+
+use crate::stats::distribution::QtyDistribution;
+use crate::stats::{normalized_market_price_with_qty_and_config, MarketPriceConfig};
+
+/// Scales the median absolute deviation so that it estimates the standard deviation
+/// of normally distributed prices.
+const MAD_SCALE: f64 = 1.4826;
+
+/// A way of estimating the price of a group of auctions.
+///
+/// Besides the estimators in this module, any closure taking a slice of
+/// `(price, quantity)` pairs and returning an `Option` of a price is an estimator.
+///
+/// # Example
+///
+/// ```rust
+/// use etherealpost::stats::estimators::{CostToBuy, MarketPriceEstimator, TrimmedMean};
+///
+/// let price_qty = [(100, 2), (110, 5), (120, 2), (900, 1)];
+///
+/// assert_eq!(Some(106), CostToBuy::new(5).estimate(&price_qty));
+/// assert_eq!(Some(111), TrimmedMean::new(10.0).estimate(&price_qty));
+///
+/// let cheapest = |price_qty: &[(u64, u64)]| price_qty.first().map(|(price, _)| *price);
+/// assert_eq!(Some(100), cheapest.estimate(&price_qty));
+/// ```
+pub trait MarketPriceEstimator {
+    /// Estimates a price from `(price, quantity)` pairs, which are sorted by price.
+    ///
+    /// Returns `None` if no price can be estimated, such as when there are no units.
+    fn estimate(&self, price_qty: &[(u64, u64)]) -> Option<u64>;
+}
+
+impl<F> MarketPriceEstimator for F
+where
+    F: Fn(&[(u64, u64)]) -> Option<u64>,
+{
+    fn estimate(&self, price_qty: &[(u64, u64)]) -> Option<u64> {
+        self(price_qty)
+    }
+}
+
+/// The normalized market price, which averages the cheapest 15% to 30% of units.
+///
+/// See [normalized_market_price_with_qty](`crate::stats::normalized_market_price_with_qty`).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NormalizedMarketPrice {
+    config: MarketPriceConfig,
+}
+
+impl NormalizedMarketPrice {
+    /// Creates an estimator which uses the thresholds of `config`.
+    pub fn new(config: MarketPriceConfig) -> Self {
+        NormalizedMarketPrice { config }
+    }
+}
+
+impl MarketPriceEstimator for NormalizedMarketPrice {
+    fn estimate(&self, price_qty: &[(u64, u64)]) -> Option<u64> {
+        normalized_market_price_with_qty_and_config(&mut price_qty.to_vec(), &self.config)
+    }
+}
+
+/// The mean price of the units which aren't outliers, where an outlier is too many
+/// median absolute deviations away from the median.
+///
+/// The median absolute deviation is scaled to be comparable to a standard deviation,
+/// so a threshold of `3.0` rejects prices roughly 3 standard deviations away from
+/// the median. Unlike the standard deviation, a few extreme prices barely move it.
+#[derive(Clone, Copy, Debug)]
+pub struct MedianAbsoluteDeviation {
+    threshold: f64,
+}
+
+impl MedianAbsoluteDeviation {
+    /// Creates an estimator which rejects prices more than `threshold` scaled median
+    /// absolute deviations away from the median.
+    pub fn new(threshold: f64) -> Self {
+        MedianAbsoluteDeviation { threshold }
+    }
+}
+
+impl Default for MedianAbsoluteDeviation {
+    fn default() -> Self {
+        MedianAbsoluteDeviation::new(3.0)
+    }
+}
+
+impl MarketPriceEstimator for MedianAbsoluteDeviation {
+    fn estimate(&self, price_qty: &[(u64, u64)]) -> Option<u64> {
+        let median = QtyDistribution::from_sorted(price_qty)?.median();
+
+        let mut deviations: Vec<(u64, u64)> = price_qty
+            .iter()
+            .map(|(price, qty)| (abs_diff(*price, median), *qty))
+            .collect();
+        let mad = QtyDistribution::new(&mut deviations)?.median();
+
+        let max_deviation = self.threshold * MAD_SCALE * mad as f64;
+        mean_within(price_qty, |price| {
+            abs_diff(price, median) as f64 <= max_deviation
+        })
+    }
+}
+
+/// The mean price of the units within the fences of the interquartile range.
+///
+/// With a factor of `1.5`, these are Tukey's fences: every price more than 1.5
+/// times the interquartile range below the 25th or above the 75th percentile is
+/// left out.
+#[derive(Clone, Copy, Debug)]
+pub struct InterquartileRange {
+    factor: f64,
+}
+
+impl InterquartileRange {
+    /// Creates an estimator which leaves out prices more than `factor` times the
+    /// interquartile range outside of it.
+    pub fn new(factor: f64) -> Self {
+        InterquartileRange { factor }
+    }
+}
+
+impl Default for InterquartileRange {
+    fn default() -> Self {
+        InterquartileRange::new(1.5)
+    }
+}
+
+impl MarketPriceEstimator for InterquartileRange {
+    fn estimate(&self, price_qty: &[(u64, u64)]) -> Option<u64> {
+        let distribution = QtyDistribution::from_sorted(price_qty)?;
+        let q1 = distribution.percentile(25.0) as f64;
+        let q3 = distribution.percentile(75.0) as f64;
+        let fence = self.factor * (q3 - q1);
+
+        mean_within(price_qty, |price| {
+            let price = price as f64;
+            price >= q1 - fence && price <= q3 + fence
+        })
+    }
+}
+
+/// The average price per unit of buying the cheapest `quantity` units.
+///
+/// Returns `None` when fewer than `quantity` units are listed.
+#[derive(Clone, Copy, Debug)]
+pub struct CostToBuy {
+    quantity: u64,
+}
+
+impl CostToBuy {
+    /// Creates an estimator for buying `quantity` units. A quantity of `0` is
+    /// treated as `1`.
+    pub fn new(quantity: u64) -> Self {
+        CostToBuy {
+            quantity: quantity.max(1),
+        }
+    }
+}
+
+impl MarketPriceEstimator for CostToBuy {
+    fn estimate(&self, price_qty: &[(u64, u64)]) -> Option<u64> {
+        let mut bought: u64 = 0;
+        let mut cost: u64 = 0;
+        for (price, qty) in price_qty {
+            let buy = (self.quantity - bought).min(*qty);
+            bought += buy;
+            cost += price * buy;
+            if bought == self.quantity {
+                return Some((cost as f64 / bought as f64).round() as u64);
+            }
+        }
+        None
+    }
+}
+
+/// The mean price after leaving out a percentage of the units at both the cheap and
+/// the expensive end.
+///
+/// Trimming `50.0` percent or more leaves only the median.
+#[derive(Clone, Copy, Debug)]
+pub struct TrimmedMean {
+    trim_percent: f64,
+}
+
+impl TrimmedMean {
+    /// Creates an estimator which leaves out `trim_percent` percent of units at
+    /// each end.
+    pub fn new(trim_percent: f64) -> Self {
+        TrimmedMean {
+            trim_percent: trim_percent.clamp(0.0, 50.0),
+        }
+    }
+}
+
+impl Default for TrimmedMean {
+    fn default() -> Self {
+        TrimmedMean::new(10.0)
+    }
+}
+
+impl MarketPriceEstimator for TrimmedMean {
+    fn estimate(&self, price_qty: &[(u64, u64)]) -> Option<u64> {
+        let distribution = QtyDistribution::from_sorted(price_qty)?;
+        let total_qty = distribution.total_qty();
+        let trim = (total_qty as f64 * self.trim_percent / 100.0).floor() as u64;
+        let keep = total_qty - 2 * trim;
+        if keep == 0 {
+            return Some(distribution.median());
+        }
+
+        let mut skipped: u64 = 0;
+        let mut kept: u64 = 0;
+        let mut price_sum: f64 = 0.0;
+        for (price, qty) in price_qty {
+            let skip = (trim - skipped).min(*qty);
+            skipped += skip;
+
+            let take = (keep - kept).min(qty - skip);
+            kept += take;
+            price_sum += *price as f64 * take as f64;
+
+            if kept == keep {
+                break;
+            }
+        }
+        Some((price_sum / keep as f64).round() as u64)
+    }
+}
+
+/// The mean price of the units whose price is accepted by `include`.
+fn mean_within<F: Fn(u64) -> bool>(price_qty: &[(u64, u64)], include: F) -> Option<u64> {
+    let mut price_sum: f64 = 0.0;
+    let mut qty_sum: u64 = 0;
+    for (price, qty) in price_qty.iter().filter(|(price, _)| include(*price)) {
+        price_sum += *price as f64 * *qty as f64;
+        qty_sum += qty;
+    }
+
+    if qty_sum == 0 {
+        None
+    } else {
+        Some((price_sum / qty_sum as f64).round() as u64)
+    }
+}
+
+fn abs_diff(a: u64, b: u64) -> u64 {
+    a.max(b) - a.min(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mostly around 100, with one unit far too cheap and a few far too expensive.
+    const PRICE_QTY: [(u64, u64); 6] = [(5, 1), (98, 4), (100, 10), (103, 4), (110, 1), (5000, 2)];
+
+    #[test]
+    fn normalized_market_price_matches_the_stats_function() {
+        let default = NormalizedMarketPrice::default();
+        assert_eq!(
+            crate::stats::normalized_market_price_with_qty(&mut PRICE_QTY.to_vec()),
+            default.estimate(&PRICE_QTY)
+        );
+        assert_eq!(None, default.estimate(&[]));
+    }
+
+    #[test]
+    fn median_absolute_deviation_rejects_outliers() {
+        // the median is 100 and the median absolute deviation 2, so prices further
+        // than 3 * 1.4826 * 2 from 100 are left out
+        assert_eq!(
+            Some(100),
+            MedianAbsoluteDeviation::default().estimate(&PRICE_QTY)
+        );
+
+        // every deviation is 0, so only the median itself is kept
+        assert_eq!(
+            Some(100),
+            MedianAbsoluteDeviation::default().estimate(&[(100, 3), (900, 1)])
+        );
+        assert_eq!(None, MedianAbsoluteDeviation::default().estimate(&[]));
+    }
+
+    #[test]
+    fn interquartile_range_trims_outside_the_fences() {
+        // q1 is 100 and q3 is 103, so the fences are 95.5 and 107.5
+        assert_eq!(
+            Some(100),
+            InterquartileRange::default().estimate(&PRICE_QTY)
+        );
+        // without fences, only the units between the quartiles are kept
+        assert_eq!(Some(101), InterquartileRange::new(0.0).estimate(&PRICE_QTY));
+    }
+
+    #[test]
+    fn cost_to_buy_walks_the_cheapest_units() {
+        assert_eq!(Some(5), CostToBuy::new(1).estimate(&PRICE_QTY));
+        // 5 + 4 * 98 + 10 * 100 = 1397 for 15 units
+        assert_eq!(Some(93), CostToBuy::new(15).estimate(&PRICE_QTY));
+        assert_eq!(Some(5), CostToBuy::new(0).estimate(&PRICE_QTY));
+        assert_eq!(None, CostToBuy::new(23).estimate(&PRICE_QTY));
+    }
+
+    #[test]
+    fn trimmed_mean_trims_both_ends() {
+        // 22 units, trimming 2 units at each end leaves 3 at 98, 10 at 100, 4 at 103
+        // and 1 at 110
+        assert_eq!(Some(101), TrimmedMean::default().estimate(&PRICE_QTY));
+        assert_eq!(Some(100), TrimmedMean::new(50.0).estimate(&PRICE_QTY));
+        assert_eq!(
+            Some(1000),
+            TrimmedMean::new(0.0).estimate(&[(500, 1), (1500, 1)])
+        );
+        assert_eq!(None, TrimmedMean::default().estimate(&[]));
+    }
+}
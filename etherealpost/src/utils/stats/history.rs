@@ -0,0 +1,455 @@
+//! Rolling aggregates over a history of timestamped [`ItemSummary`] values.
+//!
+//! A single [`ItemSummary`] only describes one auction house scan. These functions
+//! combine many scans into values which are more stable over time, similar to
+//! the `DBMarket`, `DBHistorical` and `DBMinBuyout` price sources of Trade Skill
+//! Master:
+//!
+//! 1. Every scan taken on the same (UTC) day is combined into a single
+//!    [`DailyValue`], using a weighted average of the scans' market prices.
+//! 2. The daily values of the last `market_days` days are combined into a
+//!    weighted **market value**, and the daily values of the last `historical_days`
+//!    days into a weighted **historical value**.
+//! 3. The **minimum buyout** is the minimum buyout of the most recent scan, as long
+//!    as the item was still listed in the latest scan of the realm.
+//!
+//! The history passed to these functions is a slice of `(timestamp, summary)` pairs,
+//! where `timestamp` is seconds since the Unix epoch, as returned by
+//! [PriceStore::history](`crate::storage::PriceStore::history`).
+
+use crate::parse::ItemSummary;
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// How the market prices of the scans taken during a single day are weighted
+/// when they are combined into a [`DailyValue`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScanWeighting {
+    /// Every scan counts equally.
+    Equal,
+
+    /// Scans are weighted by the total quantity available at the time.
+    Quantity,
+
+    /// Scans are weighted by the number of auctions listed at the time.
+    Auctions,
+}
+
+/// How the daily values within an aggregation window are weighted, based on how
+/// many days before the current day they are.
+///
+/// Days which have no scans are skipped, and the remaining weights are
+/// normalized so that they always add up to 1.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DayWeighting {
+    /// Every day counts equally.
+    Uniform,
+
+    /// The weight decreases linearly with age: in an `n` day window, the current
+    /// day has a weight of `n`, the day before `n - 1`, and the oldest day `1`.
+    Linear,
+
+    /// The weight halves every `half_life_days` days.
+    Exponential {
+        /// The number of days after which a daily value counts half as much.
+        half_life_days: f64,
+    },
+
+    /// Explicit weights, where the first element is the weight of the current day,
+    /// the second the weight of the day before, and so on. Days beyond the end of
+    /// the vector have a weight of `0`.
+    Custom(Vec<f64>),
+}
+
+impl DayWeighting {
+    /// The weight of a daily value which is `age` days old, in a window of
+    /// `window_days` days.
+    pub fn weight(&self, age: u32, window_days: u32) -> f64 {
+        match self {
+            DayWeighting::Uniform => 1.0,
+            DayWeighting::Linear => window_days.saturating_sub(age) as f64,
+            DayWeighting::Exponential { half_life_days } => {
+                0.5f64.powf(age as f64 / half_life_days)
+            }
+            DayWeighting::Custom(weights) => *weights.get(age as usize).unwrap_or(&0.0),
+        }
+    }
+}
+
+/// Configuration of the windows and weights used by [`aggregate`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistoryConfig {
+    /// How scans within a day are combined. Defaults to [`ScanWeighting::Equal`].
+    pub scan_weighting: ScanWeighting,
+
+    /// The number of days, including the current one, which make up the market
+    /// value. Defaults to `14`.
+    pub market_days: u32,
+
+    /// How the days of the market value window are weighted.
+    /// Defaults to [`DayWeighting::Linear`].
+    pub market_weighting: DayWeighting,
+
+    /// The number of days, including the current one, which make up the historical
+    /// value. Defaults to `60`.
+    pub historical_days: u32,
+
+    /// How the days of the historical value window are weighted.
+    /// Defaults to [`DayWeighting::Uniform`].
+    pub historical_weighting: DayWeighting,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            scan_weighting: ScanWeighting::Equal,
+            market_days: 14,
+            market_weighting: DayWeighting::Linear,
+            historical_days: 60,
+            historical_weighting: DayWeighting::Uniform,
+        }
+    }
+}
+
+/// The combined value of all scans taken during a single UTC day.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DailyValue {
+    /// The day, as the number of whole days since the Unix epoch.
+    pub day: u64,
+
+    /// The weighted average market price of the day's scans.
+    pub market_price: f64,
+
+    /// The lowest minimum buyout seen in any of the day's scans.
+    pub min_buyout: u64,
+
+    /// The number of scans taken during the day.
+    pub num_scans: u32,
+}
+
+/// The aggregated values for an item over its history. See the
+/// [module documentation](`self`) for how each one is calculated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistoryAggregates {
+    /// The weighted average of the daily market prices over the market window.
+    /// Comparable to TSM's `DBMarket`.
+    pub market_value: Option<u64>,
+
+    /// The weighted average of the daily market prices over the historical window.
+    /// Comparable to TSM's `DBHistorical`.
+    pub historical_value: Option<u64>,
+
+    /// The minimum buyout of the most recent scan, or `None` if the item wasn't
+    /// listed in the latest scan of the realm. Comparable to TSM's `DBMinBuyout`.
+    pub min_buyout: Option<u64>,
+}
+
+/// Combines the scans in `history` into one [`DailyValue`] per UTC day, oldest first.
+///
+/// `history` does not need to be sorted.
+pub fn daily_values(history: &[(u64, ItemSummary)], weighting: &ScanWeighting) -> Vec<DailyValue> {
+    let mut sorted: Vec<&(u64, ItemSummary)> = history.iter().collect();
+    sorted.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let mut days: Vec<DailyValue> = Vec::new();
+    let mut weighted_sum = 0.0;
+    let mut weight_sum = 0.0;
+
+    for (timestamp, summary) in sorted {
+        let day = timestamp / SECONDS_PER_DAY;
+        let weight = match weighting {
+            ScanWeighting::Equal => 1.0,
+            ScanWeighting::Quantity => summary.total_qty as f64,
+            ScanWeighting::Auctions => summary.num_auctions as f64,
+        };
+
+        match days.last_mut() {
+            Some(current) if current.day == day => {
+                current.min_buyout = current.min_buyout.min(summary.min_buyout);
+                current.num_scans += 1;
+            }
+            _ => {
+                weighted_sum = 0.0;
+                weight_sum = 0.0;
+                days.push(DailyValue {
+                    day,
+                    market_price: 0.0,
+                    min_buyout: summary.min_buyout,
+                    num_scans: 1,
+                });
+            }
+        }
+
+        weighted_sum += weight * summary.market_price as f64;
+        weight_sum += weight;
+
+        let current = days.last_mut().unwrap();
+        current.market_price = if weight_sum > 0.0 {
+            weighted_sum / weight_sum
+        } else {
+            // every scan had a weight of 0 (eg, no quantity), fall back to the latest
+            summary.market_price as f64
+        };
+    }
+
+    days
+}
+
+/// Calculates the weighted average of the daily values which fall within a window
+/// of `window_days` days ending on `today`.
+///
+/// Returns `None` if there are no daily values in the window, or if all of them
+/// have a weight of `0`.
+pub fn weighted_window_average(
+    days: &[DailyValue],
+    today: u64,
+    window_days: u32,
+    weighting: &DayWeighting,
+) -> Option<f64> {
+    let mut weighted_sum = 0.0;
+    let mut weight_sum = 0.0;
+
+    for daily in days {
+        if daily.day > today {
+            continue;
+        }
+        let age = today - daily.day;
+        if age >= window_days as u64 {
+            continue;
+        }
+
+        let weight = weighting.weight(age as u32, window_days);
+        weighted_sum += weight * daily.market_price;
+        weight_sum += weight;
+    }
+
+    if weight_sum > 0.0 {
+        Some(weighted_sum / weight_sum)
+    } else {
+        None
+    }
+}
+
+/// Calculates the market value, historical value and minimum buyout of an item
+/// as of `now` from its history of scans.
+///
+/// Scans taken after `now` are ignored.
+///
+/// `latest_scan` is the timestamp of the latest scan of the realm at or before `now`,
+/// such as the last of [PriceStore::timestamps](`crate::storage::PriceStore::timestamps`).
+/// [PriceStore::history](`crate::storage::PriceStore::history`) skips the scans in
+/// which the item wasn't listed, so if the most recent scan in `history` is older
+/// than `latest_scan`, the item is no longer listed and the minimum buyout is `None`.
+///
+/// # Example
+///
+/// ```rust
+/// use etherealpost::parse::ItemSummary;
+/// use etherealpost::stats::history::{aggregate, HistoryConfig};
+///
+/// fn scan(market_price: u64) -> ItemSummary {
+///     ItemSummary {
+///         market_price,
+///         min_buyout: market_price,
+///         total_qty: 1,
+///         num_auctions: 1,
+///         ..ItemSummary::default()
+///     }
+/// }
+///
+/// let day = 86400;
+/// let history = vec![(day, scan(100)), (2 * day, scan(200))];
+/// let aggregates = aggregate(&history, 2 * day, 2 * day, &HistoryConfig::default());
+///
+/// // with linear weights, today counts 14 and yesterday 13
+/// assert_eq!(Some(152), aggregates.market_value);
+/// assert_eq!(Some(150), aggregates.historical_value);
+/// assert_eq!(Some(200), aggregates.min_buyout);
+/// ```
+pub fn aggregate(
+    history: &[(u64, ItemSummary)],
+    now: u64,
+    latest_scan: u64,
+    config: &HistoryConfig,
+) -> HistoryAggregates {
+    let past: Vec<(u64, ItemSummary)> = history
+        .iter()
+        .filter(|(timestamp, _)| *timestamp <= now)
+        .cloned()
+        .collect();
+
+    let days = daily_values(&past, &config.scan_weighting);
+    let today = now / SECONDS_PER_DAY;
+
+    let market_value =
+        weighted_window_average(&days, today, config.market_days, &config.market_weighting);
+    let historical_value = weighted_window_average(
+        &days,
+        today,
+        config.historical_days,
+        &config.historical_weighting,
+    );
+    // a history without the latest scan means the item is no longer listed
+    let min_buyout = past
+        .iter()
+        .max_by_key(|(timestamp, _)| *timestamp)
+        .filter(|(timestamp, _)| *timestamp >= latest_scan)
+        .map(|(_, summary)| summary.min_buyout);
+
+    HistoryAggregates {
+        market_value: market_value.map(|v| v.round() as u64),
+        historical_value: historical_value.map(|v| v.round() as u64),
+        min_buyout,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = SECONDS_PER_DAY;
+    const HOUR: u64 = 3600;
+    /// The timestamp of the most recent scan in [`history`].
+    const LATEST_SCAN: u64 = 10 * DAY + 12 * HOUR;
+
+    fn scan(market_price: u64, min_buyout: u64, total_qty: u64) -> ItemSummary {
+        ItemSummary {
+            market_price,
+            min_buyout,
+            total_qty,
+            num_auctions: 1,
+            ..ItemSummary::default()
+        }
+    }
+
+    /// Day 10 has two scans (100 and 120), day 9 one (200), day 8 none
+    /// and day 7 one (50).
+    fn history() -> Vec<(u64, ItemSummary)> {
+        vec![
+            (10 * DAY + 12 * HOUR, scan(120, 80, 3)),
+            (7 * DAY + 5 * HOUR, scan(50, 40, 1)),
+            (10 * DAY, scan(100, 90, 1)),
+            (9 * DAY + 23 * HOUR, scan(200, 150, 1)),
+        ]
+    }
+
+    #[test]
+    fn daily_values_equal_weighting() {
+        let days = daily_values(&history(), &ScanWeighting::Equal);
+        assert_eq!(
+            vec![7, 9, 10],
+            days.iter().map(|d| d.day).collect::<Vec<_>>()
+        );
+        assert_relative_eq!(110.0, days[2].market_price);
+        assert_eq!(80, days[2].min_buyout);
+        assert_eq!(2, days[2].num_scans);
+        assert_relative_eq!(200.0, days[1].market_price);
+    }
+
+    #[test]
+    fn daily_values_quantity_weighting() {
+        let days = daily_values(&history(), &ScanWeighting::Quantity);
+        // (100 * 1 + 120 * 3) / 4
+        assert_relative_eq!(115.0, days[2].market_price);
+    }
+
+    #[test]
+    fn aggregate_default_config() {
+        let aggregates = aggregate(
+            &history(),
+            10 * DAY + 13 * HOUR,
+            LATEST_SCAN,
+            &HistoryConfig::default(),
+        );
+
+        // linear weights of 14, 13 and 11 for ages 0, 1 and 3:
+        // (14 * 110 + 13 * 200 + 11 * 50) / 38 = 123.42
+        assert_eq!(Some(123), aggregates.market_value);
+        // (110 + 200 + 50) / 3
+        assert_eq!(Some(120), aggregates.historical_value);
+        assert_eq!(Some(80), aggregates.min_buyout);
+    }
+
+    #[test]
+    fn aggregate_ignores_future_scans() {
+        let aggregates = aggregate(
+            &history(),
+            10 * DAY + HOUR,
+            10 * DAY,
+            &HistoryConfig::default(),
+        );
+
+        // day 10 only has the 100 scan so far:
+        // (14 * 100 + 13 * 200 + 11 * 50) / 38 = 119.74
+        assert_eq!(Some(120), aggregates.market_value);
+        assert_eq!(Some(90), aggregates.min_buyout);
+    }
+
+    #[test]
+    fn aggregate_short_market_window() {
+        let config = HistoryConfig {
+            market_days: 2,
+            ..HistoryConfig::default()
+        };
+        let aggregates = aggregate(&history(), 10 * DAY + 13 * HOUR, LATEST_SCAN, &config);
+
+        // linear weights of 2 and 1: (2 * 110 + 1 * 200) / 3
+        assert_eq!(Some(140), aggregates.market_value);
+        assert_eq!(Some(120), aggregates.historical_value);
+    }
+
+    #[test]
+    fn aggregate_exponential_weighting() {
+        let config = HistoryConfig {
+            market_weighting: DayWeighting::Exponential {
+                half_life_days: 1.0,
+            },
+            ..HistoryConfig::default()
+        };
+        let aggregates = aggregate(&history(), 10 * DAY + 13 * HOUR, LATEST_SCAN, &config);
+
+        // weights of 1, 0.5 and 0.125: (110 + 100 + 6.25) / 1.625 = 133.08
+        assert_eq!(Some(133), aggregates.market_value);
+    }
+
+    #[test]
+    fn aggregate_custom_weighting() {
+        let config = HistoryConfig {
+            market_weighting: DayWeighting::Custom(vec![1.0, 0.0, 0.0, 1.0]),
+            ..HistoryConfig::default()
+        };
+        let aggregates = aggregate(&history(), 10 * DAY + 13 * HOUR, LATEST_SCAN, &config);
+
+        // (110 + 50) / 2
+        assert_eq!(Some(80), aggregates.market_value);
+    }
+
+    #[test]
+    fn aggregate_min_buyout_requires_the_latest_scan() {
+        // the item wasn't listed in the scans since
+        let aggregates = aggregate(
+            &history(),
+            11 * DAY,
+            LATEST_SCAN + 6 * HOUR,
+            &HistoryConfig::default(),
+        );
+        assert_eq!(None, aggregates.min_buyout);
+        assert!(aggregates.market_value.is_some());
+    }
+
+    #[test]
+    fn aggregate_empty_history() {
+        let aggregates = aggregate(&[], 10 * DAY, 10 * DAY, &HistoryConfig::default());
+        assert_eq!(None, aggregates.market_value);
+        assert_eq!(None, aggregates.historical_value);
+        assert_eq!(None, aggregates.min_buyout);
+    }
+
+    #[test]
+    fn aggregate_history_outside_window() {
+        let history = vec![(DAY, scan(100, 100, 1))];
+        let aggregates = aggregate(&history, 30 * DAY, DAY, &HistoryConfig::default());
+        assert_eq!(None, aggregates.market_value);
+        assert_eq!(Some(100), aggregates.historical_value);
+    }
+}
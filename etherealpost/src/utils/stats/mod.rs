@@ -1,7 +1,103 @@
+pub mod distribution;
+pub mod estimators;
+pub mod history;
+
+use serde::{Deserialize, Serialize};
+
 /// 15.87 represents -1 standard deviation from the mean of a normal distribution curve
 const MINIMUM_PRICES_PERCENTILE: f64 = 15.0;
 const FIRST_STANDARD_DEV_PERCENTILE: f64 = 15.87;
 const MAX_PRICE_DIFF_FACTOR: f64 = 1.2;
+const STD_DEV_CUTOFF: f64 = 1.5;
+
+/// The thresholds used to calculate market prices.
+///
+/// The defaults are the values used by [market_price](`self::market_price`),
+/// [normalized_market_price](`self::normalized_market_price`) and
+/// [normalized_market_price_with_qty](`self::normalized_market_price_with_qty`).
+/// Each of those has a `_with_config` variant which takes a config instead.
+///
+/// # Example
+///
+/// ```rust
+/// use etherealpost::stats::{normalized_market_price_with_qty_and_config, MarketPriceConfig};
+///
+/// // a wider window and a larger jump factor for volatile commodities
+/// let volatile = MarketPriceConfig::default()
+///     .with_percentiles(10.0, 25.0)
+///     .with_max_price_diff_factor(1.3);
+///
+/// let mut data = [(10000, 3), (40000, 1), (50000, 10), (54500, 15), (60000, 5), (150000, 2)];
+/// assert_eq!(35556, normalized_market_price_with_qty_and_config(&mut data, &volatile).unwrap());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct MarketPriceConfig {
+    /// The percentage of the cheapest prices which are always considered by the
+    /// normalized market price. Defaults to `15.0`.
+    pub min_percentile: f64,
+
+    /// The percentage of the cheapest prices which the normalized market price
+    /// considers at most, as long as no price jumps by more than the
+    /// [max_price_diff_factor](`Self::max_price_diff_factor`). Defaults to `30.0`.
+    pub max_percentile: f64,
+
+    /// How much a price may be higher than the one before it and still be considered
+    /// between the [min_percentile](`Self::min_percentile`) and the
+    /// [max_percentile](`Self::max_percentile`). Defaults to `1.2`, so up to 20% higher.
+    pub max_price_diff_factor: f64,
+
+    /// The number of standard deviations from the mean that the considered prices must
+    /// be within to be part of the normalized market price. Defaults to `1.5`.
+    pub std_dev_cutoff: f64,
+
+    /// The percentage of the cheapest prices averaged by the plain
+    /// [market_price](`self::market_price`). Defaults to `15.87`, which is one standard
+    /// deviation below the mean of a normal distribution.
+    pub market_percentile: f64,
+}
+
+impl Default for MarketPriceConfig {
+    fn default() -> Self {
+        MarketPriceConfig {
+            min_percentile: MINIMUM_PRICES_PERCENTILE,
+            max_percentile: MINIMUM_PRICES_PERCENTILE * 2.0,
+            max_price_diff_factor: MAX_PRICE_DIFF_FACTOR,
+            std_dev_cutoff: STD_DEV_CUTOFF,
+            market_percentile: FIRST_STANDARD_DEV_PERCENTILE,
+        }
+    }
+}
+
+impl MarketPriceConfig {
+    /// Sets the range of percentiles considered by the normalized market price.
+    ///
+    /// Both are clamped between `0.0` and `100.0`, and `max` is raised to `min` if
+    /// it is lower.
+    pub fn with_percentiles(mut self, min: f64, max: f64) -> Self {
+        self.min_percentile = min.clamp(0.0, 100.0);
+        self.max_percentile = max.clamp(self.min_percentile, 100.0);
+        self
+    }
+
+    /// Sets how much a price may be higher than the one before it.
+    pub fn with_max_price_diff_factor(mut self, max_price_diff_factor: f64) -> Self {
+        self.max_price_diff_factor = max_price_diff_factor;
+        self
+    }
+
+    /// Sets the number of standard deviations from the mean that prices must be within.
+    pub fn with_std_dev_cutoff(mut self, std_dev_cutoff: f64) -> Self {
+        self.std_dev_cutoff = std_dev_cutoff;
+        self
+    }
+
+    /// Sets the percentage of the cheapest prices averaged by the plain market price.
+    /// It is clamped between `0.0` and `100.0`.
+    pub fn with_market_percentile(mut self, market_percentile: f64) -> Self {
+        self.market_percentile = market_percentile.clamp(0.0, 100.0);
+        self
+    }
+}
 
 /// Calculates the Market Price given an array of item buyout our unit prices.
 ///
@@ -45,13 +141,20 @@ const MAX_PRICE_DIFF_FACTOR: f64 = 1.2;
 ///   will return `None` when `prices` contains fewer than 2 items.
 ///
 pub fn market_price(prices: &[u64]) -> Option<u64> {
+    market_price_with_config(prices, &MarketPriceConfig::default())
+}
+
+/// Calculates the Market Price like [market_price](`self::market_price`), averaging
+/// the cheapest [market_percentile](`MarketPriceConfig::market_percentile`) of prices
+/// given by `config`.
+pub fn market_price_with_config(prices: &[u64], config: &MarketPriceConfig) -> Option<u64> {
     if prices.is_empty() {
         return None;
     } else if prices.len() == 1 {
         return Some(prices[0]);
     }
 
-    let p_index = percentile_index(FIRST_STANDARD_DEV_PERCENTILE, prices.len());
+    let p_index = percentile_index(config.market_percentile, prices.len());
 
     let mut v = prices.to_vec();
     v.sort_unstable();
@@ -127,6 +230,16 @@ pub fn market_price(prices: &[u64]) -> Option<u64> {
 ///   `None` when `prices` contains 0 items. If it contains 1 item, it will return
 ///   `Some(u64)` for that single item.
 pub fn normalized_market_price(prices: &mut [u64]) -> Option<u64> {
+    normalized_market_price_with_config(prices, &MarketPriceConfig::default())
+}
+
+/// Calculates the normalized market price like
+/// [normalized_market_price](`self::normalized_market_price`), using the thresholds
+/// given by `config`.
+pub fn normalized_market_price_with_config(
+    prices: &mut [u64],
+    config: &MarketPriceConfig,
+) -> Option<u64> {
     if prices.is_empty() {
         return None;
     } else if prices.len() == 1 {
@@ -136,8 +249,8 @@ pub fn normalized_market_price(prices: &mut [u64]) -> Option<u64> {
     prices.sort_unstable();
 
     // calculate the theoretical index ranges
-    let p0_index = percentile_index(MINIMUM_PRICES_PERCENTILE, prices.len());
-    let p1_index = percentile_index(MINIMUM_PRICES_PERCENTILE * 2.0, prices.len());
+    let p0_index = percentile_index(config.min_percentile, prices.len());
+    let p1_index = percentile_index(config.max_percentile, prices.len());
 
     // start with the first 15%, which will be used as the minimum
     let mut target_index = p0_index;
@@ -146,7 +259,7 @@ pub fn normalized_market_price(prices: &mut [u64]) -> Option<u64> {
     if p1_index > p0_index {
         let mut last_num = prices[p0_index];
         for p in &prices[p0_index + 1..=p1_index] {
-            let max = (last_num as f64) * MAX_PRICE_DIFF_FACTOR;
+            let max = (last_num as f64) * config.max_price_diff_factor;
             if (*p as f64) < max {
                 // also include this number
                 target_index += 1;
@@ -164,7 +277,7 @@ pub fn normalized_market_price(prices: &mut [u64]) -> Option<u64> {
         return Some((sum as f64 / calc_prices.len() as f64).round() as u64);
     }
 
-    let filtered_prices = normalize_from_std_dev(calc_prices, 1.5);
+    let filtered_prices = normalize_from_std_dev(calc_prices, config.std_dev_cutoff);
 
     Some(mean(filtered_prices).round() as u64)
 }
@@ -198,6 +311,16 @@ pub fn normalized_market_price(prices: &mut [u64]) -> Option<u64> {
 /// assert_eq!(48571, normalized_market_price_with_qty(&mut data).unwrap());
 /// ```
 pub fn normalized_market_price_with_qty(price_qty: &mut [(u64, u64)]) -> Option<u64> {
+    normalized_market_price_with_qty_and_config(price_qty, &MarketPriceConfig::default())
+}
+
+/// Calculates the normalized market price of `(price, quantity)` pairs like
+/// [normalized_market_price_with_qty](`self::normalized_market_price_with_qty`),
+/// using the thresholds given by `config`.
+pub fn normalized_market_price_with_qty_and_config(
+    price_qty: &mut [(u64, u64)],
+    config: &MarketPriceConfig,
+) -> Option<u64> {
     let mut price_sum: u64 = 0;
     let mut qty_sum: u64 = 0;
     price_qty.iter().for_each(|(amount, qty)| {
@@ -216,8 +339,8 @@ pub fn normalized_market_price_with_qty(price_qty: &mut [(u64, u64)]) -> Option<
 
     // calculate the theoretical index ranges if the prices
     // were extrapolated into a full set
-    let p0_index = percentile_index(MINIMUM_PRICES_PERCENTILE, qty_sum as usize);
-    let p1_index = percentile_index(MINIMUM_PRICES_PERCENTILE * 2.0, qty_sum as usize);
+    let p0_index = percentile_index(config.min_percentile, qty_sum as usize);
+    let p1_index = percentile_index(config.max_percentile, qty_sum as usize);
 
     // how many prices should there be for each of the 15% and 30% marks?
     let p0_target = (p0_index + 1) as u64;
@@ -246,7 +369,7 @@ pub fn normalized_market_price_with_qty(price_qty: &mut [(u64, u64)]) -> Option<
         else if added_qty > p0_target {
             // everything from here on out can trigger a short circuit
             let lp = last_price.unwrap();
-            let max = (lp as f64) * MAX_PRICE_DIFF_FACTOR;
+            let max = (lp as f64) * config.max_price_diff_factor;
             if (price as f64) < max {
                 // can we add all of it?
                 if qty_if_added <= p1_target {
@@ -323,7 +446,7 @@ pub fn normalized_market_price_with_qty(price_qty: &mut [(u64, u64)]) -> Option<
     }
 
     // filter out large deviations from the mean
-    let filtered_prices = normalize_from_std_dev_price_qty(new_prices, 1.5);
+    let filtered_prices = normalize_from_std_dev_price_qty(new_prices, config.std_dev_cutoff);
 
     // return the average price of the result
     Some(mean_amount_qty(filtered_prices).round() as u64)
@@ -604,6 +727,83 @@ mod tests {
         assert_eq!(1585, percentile_index(15.87, 10000))
     }
 
+    /// Deterministic pseudo-random `(price, quantity)` pairs.
+    fn data_set(seed: u64, len: usize) -> Vec<(u64, u64)> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            state >> 33
+        };
+        (0..len)
+            .map(|_| (1000 + next() % 50000, 1 + next() % 20))
+            .collect()
+    }
+
+    #[test]
+    fn default_config_reproduces_the_original_market_prices() {
+        // (normalized with qty, normalized, market price) as calculated before the
+        // thresholds became configurable
+        let expected: [(u64, u64, u64); 8] = [
+            (12034, 12034, 12034),
+            (8749, 8752, 8480),
+            (5405, 3335, 3335),
+            (2827, 3510, 2470),
+            (5366, 4086, 4086),
+            (12233, 12082, 6883),
+            (5111, 6202, 3320),
+            (15986, 16867, 10472),
+        ];
+        let config = MarketPriceConfig::default();
+
+        for (seed, expected) in (1..=8u64).zip(expected.iter()) {
+            let data = data_set(seed, seed as usize * 7);
+            let prices: Vec<u64> = data.iter().map(|(price, _)| *price).collect();
+
+            let actual = (
+                normalized_market_price_with_qty(&mut data.clone()).unwrap(),
+                normalized_market_price(&mut prices.clone()).unwrap(),
+                market_price(&prices).unwrap(),
+            );
+            assert_eq!(*expected, actual, "data set {}", seed);
+
+            let with_config = (
+                normalized_market_price_with_qty_and_config(&mut data.clone(), &config).unwrap(),
+                normalized_market_price_with_config(&mut prices.clone(), &config).unwrap(),
+                market_price_with_config(&prices, &config).unwrap(),
+            );
+            assert_eq!(*expected, with_config, "data set {}", seed);
+        }
+    }
+
+    #[test]
+    fn config_changes_the_market_price() {
+        let mut data = [
+            (10000, 3),
+            (40000, 1),
+            (50000, 10),
+            (54500, 15),
+            (60000, 5),
+            (150000, 2),
+        ];
+        assert_eq!(
+            48571,
+            normalized_market_price_with_qty(&mut data.clone()).unwrap()
+        );
+
+        // without the standard deviation cutoff, the 3 cheap units are included
+        let no_cutoff = MarketPriceConfig::default().with_std_dev_cutoff(f64::MAX);
+        assert_eq!(
+            37000,
+            normalized_market_price_with_qty_and_config(&mut data, &no_cutoff).unwrap()
+        );
+
+        let config = MarketPriceConfig::default().with_percentiles(40.0, 20.0);
+        assert_eq!(40.0, config.min_percentile);
+        assert_eq!(40.0, config.max_percentile);
+    }
+
     #[test]
     fn mean_simple_ordered() {
         assert_eq!(2.0, mean(&[1, 2, 3]));
@@ -630,6 +830,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn std_dev_population_odd_len() {
         assert_relative_eq!(
             0.81649658092,
@@ -639,6 +840,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn std_dev_population_even_len() {
         assert_relative_eq!(
             0.82915619758,
@@ -648,11 +850,13 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn std_dev_sample_odd_len() {
         assert_eq!(1.0, std_dev(&mut [1, 2, 3], false).unwrap())
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn std_dev_sample_even_len() {
         assert_relative_eq!(
             0.95742710775,
@@ -662,6 +866,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn std_dev_population_large_arr() {
         let mut arr: [u64; 20] = [
             9, 30, 51, 66, 139, 159, 179, 181, 196, 249, 282, 296, 301, 356, 384, 410, 455, 461,
@@ -676,6 +881,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn std_dev_sample_large_arr() {
         let mut arr: [u64; 20] = [
             9, 30, 51, 66, 139, 159, 179, 181, 196, 249, 282, 296, 301, 356, 384, 410, 455, 461,
@@ -686,6 +892,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn std_dev_invalid_array_size() {
         let mut arr0: [u64; 0] = [];
         let mut arr1: [u64; 1] = [1];
@@ -1,6 +1,17 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 
+use super::load::{self, LoadMode, LoadReport};
+use crate::wow::db2::Db2File;
+use crate::Error;
+
+const TABLE_NAME: &str = "BattlePetSpecies";
+
+// Field indices of the BattlePetSpecies DB2 as of 9.0.5. The ID is stored in the ID list.
+// The description and source text are strings, which hotfixed records store inline.
+const DB2_INLINE_STRING_FIELDS: [usize; 2] = [0, 1];
+const DB2_SUMMON_SPELL_ID_FIELD: usize = 3;
+
 /// Container struct for all of the [`Db2BattlePetSpecies`] rows.
 pub struct Db2BattlePetSpeciesTable {
     // TODO(seputaes) Do we need to keep this data in memory?
@@ -34,25 +45,56 @@ pub struct Db2BattlePetSpecies {
 impl Db2BattlePetSpeciesTable {
     /// Deserializes a CSV string which represents the DB2 BattlePetSpecies table
     /// in World of Warcraft.
-    pub fn from_csv(csv: &str) -> Db2BattlePetSpeciesTable {
-        let mut reader = csv::Reader::from_reader(csv.as_bytes());
-        let iter = reader.deserialize::<Db2BattlePetSpecies>();
+    ///
+    /// Rows which can't be deserialized fail the load, or are skipped and listed in the
+    /// returned [`LoadReport`] when loading with [LoadMode::Lenient](`LoadMode::Lenient`).
+    pub fn from_csv(
+        csv: &str,
+        mode: LoadMode,
+    ) -> Result<(Db2BattlePetSpeciesTable, LoadReport), Error> {
+        let (rows, report) = load::read_csv::<Db2BattlePetSpecies>(TABLE_NAME, csv, mode)?;
+        Ok((Db2BattlePetSpeciesTable::from_rows(rows), report))
+    }
 
-        let mut spell_to_species = HashMap::new();
+    /// Reads the raw `BattlePetSpecies.db2` file of World of Warcraft.
+    ///
+    /// See [from_csv](`Self::from_csv`) for how `mode` is used.
+    pub fn from_db2(
+        bytes: &[u8],
+        mode: LoadMode,
+    ) -> Result<(Db2BattlePetSpeciesTable, LoadReport), Error> {
+        Db2BattlePetSpeciesTable::from_db2_file(Db2File::parse(bytes)?, mode)
+    }
 
-        for row in iter {
-            // TODO(seputaes): Logging for the error
-            if row.is_err() {
-                continue;
-            }
+    /// Reads an already parsed `BattlePetSpecies.db2` file, such as one with
+    /// [hotfixes applied](`crate::wow::db2::Db2File::with_hotfixes`).
+    pub fn from_db2_file(
+        file: Db2File,
+        mode: LoadMode,
+    ) -> Result<(Db2BattlePetSpeciesTable, LoadReport), Error> {
+        let file = file.with_inline_strings(&DB2_INLINE_STRING_FIELDS);
+        let (rows, report) = load::read_db2(TABLE_NAME, &file, mode, |record| {
+            Ok(Db2BattlePetSpecies {
+                id: record.id(),
+                summon_spell_id: record.u32(DB2_SUMMON_SPELL_ID_FIELD)?,
+            })
+        })?;
+
+        Ok((Db2BattlePetSpeciesTable::from_rows(rows), report))
+    }
 
-            let row = row.unwrap();
+    fn from_rows<I: IntoIterator<Item = Db2BattlePetSpecies>>(rows: I) -> Db2BattlePetSpeciesTable {
+        let mut spell_to_species = HashMap::new();
 
+        for row in rows {
             // If the summon spell ID is 0, the current theory is that it
-            // is not able to be in a pet cage, and thus can't be sold on the AH
-            // TODO(seputaes): Maybe have some logging around this to confirm this theory
-            //                 using real AH data.
+            // is not able to be in a pet cage, and thus can't be sold on the AH.
+            // The species is logged so this theory can be confirmed using real AH data.
             if row.summon_spell_id == 0 {
+                tracing::trace!(
+                    species_id = row.id,
+                    "species has no summon spell, skipping it"
+                );
                 continue;
             }
 
@@ -71,6 +113,11 @@ impl Db2BattlePetSpeciesTable {
                 // 149810
                 // 170272
                 // 291537
+                tracing::trace!(
+                    species_id = row.id,
+                    spell_id = row.summon_spell_id,
+                    "summon spell already belongs to another species, skipping it"
+                );
                 continue;
             }
 
@@ -84,6 +131,7 @@ impl Db2BattlePetSpeciesTable {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::wow::db2::builder::{Db2Builder, Storage, Value};
 
     const BATTLE_PET_SPECIES_CSV_HEADER: &str =
         "Description_lang,SourceText_lang,ID,CreatureID,SummonSpellID,IconFileDataID,PetTypeEnum,Flags,SourceTypeEnum,CardUIModelSceneID,LoadoutUIModelSceneID,CovenantID";
@@ -94,7 +142,32 @@ mod tests {
         csv.push_str("\n\"Possibly explosive, definitely adorable. Keep away from open flame.\",|cFFFFD200Profession: |rEngineering,85,9656,15048,133712,9,2,3,6,7,0");
         csv.push_str("\n\"The first bombling created in the Underhold, Siegecrafter Blackfuse couldn't bear to see it destroyed, and kept it as a friendly, if explosive, pet.\",|cFFFFD200Drop:|r Siegecrafter Blackfuse|n|cFFFFD200Raid:|r Siege of Orgrimmar,1322,73352,15048,897633,9,2,0,6,7,0");
 
-        let table = Db2BattlePetSpeciesTable::from_csv(&csv);
+        let (table, _) = Db2BattlePetSpeciesTable::from_csv(&csv, LoadMode::Strict).unwrap();
+        assert_eq!(85, *table.spell_to_species.get(&15048).unwrap());
+    }
+
+    #[test]
+    fn battle_pet_species_from_db2() {
+        let species = |description: &str, creature_id: u64, spell_id: u64| {
+            vec![
+                Value::Str(description.to_string()),
+                Value::Str(String::new()),
+                Value::Int(vec![creature_id]),
+                Value::Int(vec![spell_id]),
+            ]
+        };
+        let bytes = Db2Builder::new()
+            .field(Storage::String)
+            .field(Storage::String)
+            .field(Storage::None { bits: 32, count: 1 })
+            .field(Storage::None { bits: 32, count: 1 })
+            .row(85, species("Possibly explosive", 9656, 15048))
+            .row(1322, species("The first bombling", 73352, 15048))
+            .row(39, species("Not cageable", 2671, 0))
+            .build();
+
+        let (table, _) = Db2BattlePetSpeciesTable::from_db2(&bytes, LoadMode::Strict).unwrap();
         assert_eq!(85, *table.spell_to_species.get(&15048).unwrap());
+        assert_eq!(1, table.spell_to_species.len());
     }
 }
@@ -1,6 +1,18 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 
+use super::load::{self, LoadMode, LoadReport};
+use crate::wow::db2::Db2File;
+use crate::Error;
+
+const TABLE_NAME: &str = "CurvePoint";
+
+// Field indices of the CurvePoint DB2 as of 9.0.5. The ID is stored in the ID list
+// and CurveID in the relationship map.
+const DB2_POS_FIELD: usize = 0;
+const DB2_POS_PRE_SQUISH_FIELD: usize = 1;
+const DB2_ORDER_INDEX_FIELD: usize = 2;
+
 /// Container struct for all of the [`Db2CurvePoints`] rows.
 pub struct Db2CurvePoints {
     // TODO(seputaes) Do we need to keep this data in memory?
@@ -53,24 +65,55 @@ pub struct Db2CurvePoint {
 impl Db2CurvePoints {
     /// Deserializes a CSV string which represents the DB2 CurvePoints table
     /// in World of Warcraft.
-    pub fn from_csv(csv: &str) -> Db2CurvePoints {
-        let mut reader = csv::Reader::from_reader(csv.as_bytes());
-        let iter = reader.deserialize::<Db2CurvePoint>();
+    ///
+    /// Rows which can't be deserialized fail the load, or are skipped and listed in the
+    /// returned [`LoadReport`] when loading with [LoadMode::Lenient](`LoadMode::Lenient`).
+    pub fn from_csv(csv: &str, mode: LoadMode) -> Result<(Db2CurvePoints, LoadReport), Error> {
+        let (rows, report) = load::read_csv::<Db2CurvePoint>(TABLE_NAME, csv, mode)?;
+        Ok((Db2CurvePoints::from_rows(rows), report))
+    }
+
+    /// Reads the raw `CurvePoint.db2` file of World of Warcraft.
+    ///
+    /// See [from_csv](`Self::from_csv`) for how `mode` is used.
+    pub fn from_db2(bytes: &[u8], mode: LoadMode) -> Result<(Db2CurvePoints, LoadReport), Error> {
+        Db2CurvePoints::from_db2_file(Db2File::parse(bytes)?, mode)
+    }
 
+    /// Reads an already parsed `CurvePoint.db2` file, such as one with
+    /// [hotfixes applied](`crate::wow::db2::Db2File::with_hotfixes`).
+    pub fn from_db2_file(
+        file: Db2File,
+        mode: LoadMode,
+    ) -> Result<(Db2CurvePoints, LoadReport), Error> {
+        let (rows, report) = load::read_db2(TABLE_NAME, &file, mode, |record| {
+            let pos = record.f32_array(DB2_POS_FIELD)?;
+            let pre_squish = record.f32_array(DB2_POS_PRE_SQUISH_FIELD)?;
+            let coordinate = |values: &[f32], i: usize| *values.get(i).unwrap_or(&0.0) as f64;
+            Ok(Db2CurvePoint {
+                id: record.id(),
+                x: coordinate(&pos, 0),
+                y: coordinate(&pos, 1),
+                x_pre_squish: coordinate(&pre_squish, 0),
+                y_pre_squish: coordinate(&pre_squish, 1),
+                curve_id: record.relation().unwrap_or(0),
+                order_index: record.u32(DB2_ORDER_INDEX_FIELD)? as u16,
+            })
+        })?;
+
+        Ok((Db2CurvePoints::from_rows(rows), report))
+    }
+
+    fn from_rows<I: IntoIterator<Item = Db2CurvePoint>>(rows: I) -> Db2CurvePoints {
         // TODO(seputaes): Some rows contain floats for their `x` and `y` coordinates
         // I have no idea if they're ever used for our context, but for now
         // we're just casting them into u32 which is _not_ safe.
         let mut curve_ids: HashMap<u32, Vec<(f64, f64)>> = HashMap::new();
 
-        for point in iter {
-            if point.is_err() {
-                continue;
-            }
-            let point = point.unwrap();
-
+        for point in rows {
             curve_ids
                 .entry(point.curve_id)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push((point.x, point.y));
         }
 
@@ -91,6 +134,7 @@ impl Db2CurvePoints {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::wow::db2::builder::{Db2Builder, Storage, Value};
 
     const CURVE_CSV_HEADER: &str =
         "ID,Pos[0],Pos[1],PosPreSquish[0],PosPreSquish[1],CurveID,OrderIndex";
@@ -100,7 +144,7 @@ mod tests {
         let mut csv = String::from(CURVE_CSV_HEADER);
         csv.push_str("\n5,1,6,0,1,5,0");
 
-        let table = Db2CurvePoints::from_csv(&csv);
+        let (table, _) = Db2CurvePoints::from_csv(&csv, LoadMode::Strict).unwrap();
         assert_eq!(vec![(1.0f64, 6.0f64)], *table.points(&5).unwrap());
     }
 
@@ -109,7 +153,7 @@ mod tests {
         let mut csv = String::from(CURVE_CSV_HEADER);
         csv.push_str("\n5,1,6,0,1,5,0\n9,25,31,0,1,5,0");
 
-        let table = Db2CurvePoints::from_csv(&csv);
+        let (table, _) = Db2CurvePoints::from_csv(&csv, LoadMode::Strict).unwrap();
         assert_eq!(vec![(1.0, 6.0), (25.0, 31.0)], *table.points(&5).unwrap());
     }
 
@@ -118,7 +162,7 @@ mod tests {
         let mut csv = String::from(CURVE_CSV_HEADER);
         csv.push_str("\n5,1,6,0,1,5,0\n2,3,4,0,1,9,0\n9,25,31,0,1,5,0");
 
-        let table = Db2CurvePoints::from_csv(&csv);
+        let (table, _) = Db2CurvePoints::from_csv(&csv, LoadMode::Strict).unwrap();
         assert_eq!(vec![(1.0, 6.0), (25.0, 31.0)], *table.points(&5).unwrap());
     }
 
@@ -127,7 +171,36 @@ mod tests {
         let mut csv = String::from(CURVE_CSV_HEADER);
         csv.push_str("\n5,1,6,0,1,5,0\n2,3,4,0,1,9,0\n9,25,31,0,1,5,0");
 
-        let table = Db2CurvePoints::from_csv(&csv);
+        let (table, _) = Db2CurvePoints::from_csv(&csv, LoadMode::Strict).unwrap();
         assert!(table.points(&12).is_none());
     }
+
+    #[test]
+    fn curve_from_db2() {
+        let pos = |x: f32, y: f32| Value::Int(vec![x.to_bits() as u64, y.to_bits() as u64]);
+        let bytes = Db2Builder::new()
+            .field(Storage::None { bits: 32, count: 2 })
+            .field(Storage::None { bits: 32, count: 2 })
+            .field(Storage::None { bits: 8, count: 1 })
+            .related_row(
+                5,
+                5,
+                vec![pos(1.0, 6.0), pos(0.0, 1.0), Value::Int(vec![0])],
+            )
+            .related_row(
+                2,
+                9,
+                vec![pos(3.0, 4.0), pos(0.0, 1.0), Value::Int(vec![0])],
+            )
+            .related_row(
+                9,
+                5,
+                vec![pos(25.0, 31.5), pos(0.0, 1.0), Value::Int(vec![1])],
+            )
+            .build();
+
+        let (table, _) = Db2CurvePoints::from_db2(&bytes, LoadMode::Strict).unwrap();
+        assert_eq!(vec![(1.0, 6.0), (25.0, 31.5)], *table.points(&5).unwrap());
+        assert_eq!(vec![(3.0, 4.0)], *table.points(&9).unwrap());
+    }
 }
@@ -1,8 +1,20 @@
 use serde::Deserialize;
 
+use super::load::{self, LoadMode, LoadReport};
+use crate::wow::db2::Db2File;
+use crate::Error;
+
+const TABLE_NAME: &str = "Item";
+
+const WEAPON_CLASS_ID: u32 = 2;
+const ARMOR_CLASS_ID: u32 = 4;
 const MISC_CLASS_ID: u32 = 15;
 const PET_SUBCLASS_ID: u32 = 2;
 
+// Field indices of the Item DB2 as of 9.0.5. The ID is stored in the ID list.
+const DB2_CLASS_ID_FIELD: usize = 0;
+const DB2_SUBCLASS_ID_FIELD: usize = 1;
+
 /// Container struct for all of the [`Db2Item`] rows.
 pub struct Db2Items {
     // TODO(seputaes) Do we need to keep this data in memory?
@@ -18,6 +30,11 @@ pub struct Db2Items {
     /// and [Db2BattlePetSpeciesTable](`super::Db2BattlePetSpeciesTable`)
     /// tables to determine what spell triggers the pet to be learned.
     pub pet_item_ids: Vec<u32>,
+
+    /// Contains a list of all items which can be equipped, which are all
+    /// **Weapons** and **Armor**. These are the only items whose item level can
+    /// vary between two copies of the same item.
+    pub equippable_item_ids: Vec<u32>,
 }
 
 /// A struct representation of a single row in the Item DB2 table
@@ -32,8 +49,8 @@ pub struct Db2Item {
     pub id: u32,
 
     /// The class ID associated with the item.
-    /// For the purposes of this library, the only class currently used
-    /// is `15` which is **Miscellaneous**.
+    /// For the purposes of this library, the classes currently used are
+    /// `2` (**Weapon**), `4` (**Armor**) and `15` (**Miscellaneous**).
     #[serde(rename = "ClassID")]
     pub class_id: u32,
 
@@ -50,28 +67,50 @@ pub struct Db2Item {
 
 /// Functionality for working with Items from the DB2 table.
 ///
-/// Currently, all this does is extract out Pet items and place them into their own vector
-/// and enables us to keep a relatively small amount of semi-static data in memory
-/// so there is no need to hit the API to get this information.
+/// Currently, all this does is extract out Pet items and equippable items and place them
+/// into their own vectors, which enables us to keep a relatively small amount of
+/// semi-static data in memory so there is no need to hit the API to get this information.
 impl Db2Items {
     /// Deserializes a CSV string which represents the DB2 Item table
     /// in World of Warcraft.
-    pub fn from_csv(csv: &str) -> Db2Items {
-        let mut reader = csv::Reader::from_reader(csv.as_bytes());
-        let iter = reader.deserialize::<Db2Item>();
+    ///
+    /// Rows which can't be deserialized fail the load, or are skipped and listed in the
+    /// returned [`LoadReport`] when loading with [LoadMode::Lenient](`LoadMode::Lenient`).
+    pub fn from_csv(csv: &str, mode: LoadMode) -> Result<(Db2Items, LoadReport), Error> {
+        let (rows, report) = load::read_csv::<Db2Item>(TABLE_NAME, csv, mode)?;
+        Ok((Db2Items::from_rows(rows), report))
+    }
 
-        let mut pet_item_ids = Vec::new();
+    /// Reads the raw `Item.db2` file of World of Warcraft.
+    ///
+    /// See [from_csv](`Self::from_csv`) for how `mode` is used.
+    pub fn from_db2(bytes: &[u8], mode: LoadMode) -> Result<(Db2Items, LoadReport), Error> {
+        Db2Items::from_db2_file(Db2File::parse(bytes)?, mode)
+    }
 
-        for row in iter {
-            // TODO(seputaes): Logging for the error
-            if row.is_err() {
-                continue;
-            }
+    /// Reads an already parsed `Item.db2` file, such as one with
+    /// [hotfixes applied](`crate::wow::db2::Db2File::with_hotfixes`).
+    pub fn from_db2_file(file: Db2File, mode: LoadMode) -> Result<(Db2Items, LoadReport), Error> {
+        let (rows, report) = load::read_db2(TABLE_NAME, &file, mode, |record| {
+            Ok(Db2Item {
+                id: record.id(),
+                class_id: record.u32(DB2_CLASS_ID_FIELD)?,
+                subclass_id: record.u32(DB2_SUBCLASS_ID_FIELD)?,
+            })
+        })?;
 
-            let row = row.unwrap();
+        Ok((Db2Items::from_rows(rows), report))
+    }
+
+    fn from_rows<I: IntoIterator<Item = Db2Item>>(rows: I) -> Db2Items {
+        let mut pet_item_ids = Vec::new();
+        let mut equippable_item_ids = Vec::new();
 
+        for row in rows {
             if row.class_id == MISC_CLASS_ID && row.subclass_id == PET_SUBCLASS_ID {
                 pet_item_ids.push(row.id);
+            } else if row.class_id == WEAPON_CLASS_ID || row.class_id == ARMOR_CLASS_ID {
+                equippable_item_ids.push(row.id);
             }
         }
 
@@ -79,6 +118,7 @@ impl Db2Items {
             // TODO(seputaes) Do we need to keep this data in memory?
             // items: HashMap::new(),
             pet_item_ids,
+            equippable_item_ids,
         }
     }
 }
@@ -1,16 +1,39 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 
+use super::load::{self, LoadMode, LoadReport};
+use crate::wow::db2::Db2File;
+use crate::Error;
+
+const TABLE_NAME: &str = "ItemBonus";
+
+// Field indices of the ItemBonus DB2 as of 9.0.5. The ID is stored in the ID list
+// and ParentItemBonusListID in the relationship map.
+const DB2_VALUE_FIELD: usize = 0;
+const DB2_TYPE_FIELD: usize = 1;
+const DB2_ORDER_INDEX_FIELD: usize = 2;
+
+// Stat types of the secondary and tertiary stats, as used by `ItemBonusEffect::Stat`.
+const CRIT_STAT_TYPE: i32 = 32;
+const HASTE_STAT_TYPE: i32 = 36;
+const VERSATILITY_STAT_TYPE: i32 = 40;
+const MASTERY_STAT_TYPE: i32 = 49;
+const SPEED_STAT_TYPE: i32 = 61;
+const LEECH_STAT_TYPE: i32 = 62;
+const AVOIDANCE_STAT_TYPE: i32 = 63;
+const INDESTRUCTIBLE_STAT_TYPE: i32 = 64;
+
 /// Container struct for all of the [`Db2ItemBonus`] rows.
+#[derive(Default)]
 pub struct Db2ItemBonuses {
-    // TODO(seputaes) Do we need to keep this data in memory?
-    // bonuses: Vec<DB2ItemBonus>,
+    // Mapping of Bonus IDs to the effects of every bonus in the list, by order index.
+    pub(crate) effects: HashMap<u32, Vec<ItemBonusEffect>>,
 
     // Mapping of Bonus IDs to Curve IDs (for bonuses which are curves).
-    curve_ids: HashMap<u32, u32>,
+    pub(crate) curve_ids: HashMap<u32, u32>,
 
     // Mapping of Bonus IDs to Item Level Adjustments (for bonuses which are adjustments).
-    ilvl_adjustments: HashMap<u32, i32>,
+    pub(crate) ilvl_adjustments: HashMap<u32, i32>,
 }
 
 /// A struct representation of a single row in the ItemBonus DB2 table of
@@ -50,7 +73,8 @@ pub struct Db2ItemBonus {
 
     /// The type of the bonus. In the context of auctions, types
     /// `1`, `11`, and `13` are the ones that matter most since these
-    /// impact the final level of the item.
+    /// impact the final level of the item. See [`ItemBonusEffect`] for
+    /// every type which is understood.
     ///
     /// * `1` - A simple item level adjustment. The adjustment value
     ///   will be contained in the `value0` field.
@@ -69,11 +93,253 @@ pub struct Db2ItemBonus {
     pub order_index: u16,
 }
 
+impl Db2ItemBonus {
+    /// The typed effect of the bonus.
+    pub fn effect(&self) -> ItemBonusEffect {
+        ItemBonusEffect::from_raw(
+            self.type_id,
+            [self.value0, self.value1, self.value2, self.value3],
+        )
+    }
+}
+
+/// The effect a single bonus has on an item, by the type of the bonus.
+///
+/// The values of a bonus mean something different for every type. Types which
+/// aren't covered by a variant are kept as [Other](`ItemBonusEffect::Other`) with
+/// their raw values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemBonusEffect {
+    /// Type `1`: adjusts the base item level of the item.
+    ItemLevel(i32),
+
+    /// Type `2`: adds a stat to the item, such as one of the
+    /// [tertiary stats](`TertiaryStat`).
+    Stat {
+        /// The type of the stat.
+        stat_type: i32,
+        /// The budget allocated to the stat.
+        allocation: i32,
+    },
+
+    /// Type `3`: overrides the quality of the item, such as `3` for rare or
+    /// `4` for epic.
+    Quality(i32),
+
+    /// Type `4`: a label shown under the name of the item, such as "Heroic".
+    NameDescription {
+        /// The ID in the `ItemNameDescription` table.
+        description_id: i32,
+        /// Which label is shown when the item has several.
+        priority: i32,
+    },
+
+    /// Type `5`: a suffix added to the name of the item, such as "of the Fireflash".
+    NameSuffix {
+        /// The ID in the `ItemNameDescription` table.
+        description_id: i32,
+        /// Which suffix is shown when the item has several.
+        priority: i32,
+    },
+
+    /// Type `6`: adds sockets to the item.
+    Socket {
+        /// The number of sockets added.
+        count: i32,
+        /// The type of the sockets, such as `1` for meta or `8` for prismatic.
+        socket_type: i32,
+    },
+
+    /// Type `7`: changes the appearance of the item.
+    Appearance(i32),
+
+    /// Type `8`: adjusts the level required to equip the item.
+    RequiredLevel(i32),
+
+    /// Type `11`: scales the item on a curve by the player's level when it dropped.
+    ScalingStatDistribution {
+        /// The ID in the `ScalingStatDistribution` table.
+        distribution_id: i32,
+        /// The Curve ID of the item level curve.
+        curve_id: u32,
+    },
+
+    /// Type `13`: like [ScalingStatDistribution](`ItemBonusEffect::ScalingStatDistribution`),
+    /// but the item doesn't scale any further once it dropped.
+    ScalingStatDistributionFixed {
+        /// The ID in the `ScalingStatDistribution` table.
+        distribution_id: i32,
+        /// The Curve ID of the item level curve.
+        curve_id: u32,
+    },
+
+    /// Type `15`: a random enchantment, the random stat suffixes of older items.
+    RandomEnchantment(i32),
+
+    /// Type `18`: replaces the level required to equip the item.
+    OverrideRequiredLevel(i32),
+
+    /// Any other type of bonus, with its raw values.
+    Other {
+        /// The type of the bonus.
+        type_id: u16,
+        /// The four values of the bonus.
+        values: [i32; 4],
+    },
+}
+
+/// The tertiary stats, which add a small bonus on top of an item's primary and
+/// secondary stats. Items with a tertiary stat often sell for much more than the
+/// same item without one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TertiaryStat {
+    /// Increases movement speed.
+    Speed,
+    /// Heals for a portion of damage and healing done.
+    Leech,
+    /// Reduces area of effect damage taken.
+    Avoidance,
+    /// The item takes no durability damage.
+    Indestructible,
+}
+
+impl TertiaryStat {
+    /// The tertiary stat with a stat type, if it is one.
+    pub fn from_stat_type(stat_type: i32) -> Option<TertiaryStat> {
+        match stat_type {
+            SPEED_STAT_TYPE => Some(TertiaryStat::Speed),
+            LEECH_STAT_TYPE => Some(TertiaryStat::Leech),
+            AVOIDANCE_STAT_TYPE => Some(TertiaryStat::Avoidance),
+            INDESTRUCTIBLE_STAT_TYPE => Some(TertiaryStat::Indestructible),
+            _ => None,
+        }
+    }
+}
+
+/// The secondary stats. Items whose secondary stats vary, such as crafted gear or
+/// some world drops, are priced differently for every pair of stats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SecondaryStat {
+    /// Critical Strike.
+    CriticalStrike,
+    /// Haste.
+    Haste,
+    /// Mastery.
+    Mastery,
+    /// Versatility.
+    Versatility,
+}
+
+impl SecondaryStat {
+    /// The secondary stat with a stat type, if it is one.
+    pub fn from_stat_type(stat_type: i32) -> Option<SecondaryStat> {
+        match stat_type {
+            CRIT_STAT_TYPE => Some(SecondaryStat::CriticalStrike),
+            HASTE_STAT_TYPE => Some(SecondaryStat::Haste),
+            MASTERY_STAT_TYPE => Some(SecondaryStat::Mastery),
+            VERSATILITY_STAT_TYPE => Some(SecondaryStat::Versatility),
+            _ => None,
+        }
+    }
+}
+
+impl ItemBonusEffect {
+    /// Creates the effect of a bonus from its type and raw values.
+    pub fn from_raw(type_id: u16, values: [i32; 4]) -> ItemBonusEffect {
+        match type_id {
+            1 => ItemBonusEffect::ItemLevel(values[0]),
+            2 => ItemBonusEffect::Stat {
+                stat_type: values[0],
+                allocation: values[1],
+            },
+            3 => ItemBonusEffect::Quality(values[0]),
+            4 => ItemBonusEffect::NameDescription {
+                description_id: values[0],
+                priority: values[1],
+            },
+            5 => ItemBonusEffect::NameSuffix {
+                description_id: values[0],
+                priority: values[1],
+            },
+            6 => ItemBonusEffect::Socket {
+                count: values[0],
+                socket_type: values[1],
+            },
+            7 => ItemBonusEffect::Appearance(values[0]),
+            8 => ItemBonusEffect::RequiredLevel(values[0]),
+            11 => ItemBonusEffect::ScalingStatDistribution {
+                distribution_id: values[0],
+                curve_id: values[3] as u32,
+            },
+            13 => ItemBonusEffect::ScalingStatDistributionFixed {
+                distribution_id: values[0],
+                curve_id: values[3] as u32,
+            },
+            15 => ItemBonusEffect::RandomEnchantment(values[0]),
+            18 => ItemBonusEffect::OverrideRequiredLevel(values[0]),
+            _ => ItemBonusEffect::Other { type_id, values },
+        }
+    }
+
+    /// The type and raw values of the bonus. This is the inverse of
+    /// [from_raw](`Self::from_raw`).
+    pub fn to_raw(&self) -> (u16, [i32; 4]) {
+        match *self {
+            ItemBonusEffect::ItemLevel(adjustment) => (1, [adjustment, 0, 0, 0]),
+            ItemBonusEffect::Stat {
+                stat_type,
+                allocation,
+            } => (2, [stat_type, allocation, 0, 0]),
+            ItemBonusEffect::Quality(quality) => (3, [quality, 0, 0, 0]),
+            ItemBonusEffect::NameDescription {
+                description_id,
+                priority,
+            } => (4, [description_id, priority, 0, 0]),
+            ItemBonusEffect::NameSuffix {
+                description_id,
+                priority,
+            } => (5, [description_id, priority, 0, 0]),
+            ItemBonusEffect::Socket { count, socket_type } => (6, [count, socket_type, 0, 0]),
+            ItemBonusEffect::Appearance(modifier) => (7, [modifier, 0, 0, 0]),
+            ItemBonusEffect::RequiredLevel(adjustment) => (8, [adjustment, 0, 0, 0]),
+            ItemBonusEffect::ScalingStatDistribution {
+                distribution_id,
+                curve_id,
+            } => (11, [distribution_id, 0, 0, curve_id as i32]),
+            ItemBonusEffect::ScalingStatDistributionFixed {
+                distribution_id,
+                curve_id,
+            } => (13, [distribution_id, 0, 0, curve_id as i32]),
+            ItemBonusEffect::RandomEnchantment(enchantment) => (15, [enchantment, 0, 0, 0]),
+            ItemBonusEffect::OverrideRequiredLevel(level) => (18, [level, 0, 0, 0]),
+            ItemBonusEffect::Other { type_id, values } => (type_id, values),
+        }
+    }
+
+    /// The tertiary stat the bonus adds, if it adds one.
+    pub fn tertiary_stat(&self) -> Option<TertiaryStat> {
+        match self {
+            ItemBonusEffect::Stat { stat_type, .. } => TertiaryStat::from_stat_type(*stat_type),
+            _ => None,
+        }
+    }
+
+    /// The secondary stat the bonus adds, if it adds one.
+    pub fn secondary_stat(&self) -> Option<SecondaryStat> {
+        match self {
+            ItemBonusEffect::Stat { stat_type, .. } => SecondaryStat::from_stat_type(*stat_type),
+            _ => None,
+        }
+    }
+}
+
 /// Functionality for working with item bonuses an their effect on items.
 ///
 /// In addition to mapping the rows into a [`Db2ItemBonus`],
 /// parsing is done which maps all Bonus IDs (ParentItemLevelBonus)
 /// to their corresponding Curve ID and Item Level Adjustment values.
+/// Every bonus is also kept as an [`ItemBonusEffect`], which can be looked up
+/// with [effects](#method.effects).
 ///
 /// This these IDs and adjustments can be gathered by using the
 /// associated [resolve_ilvl_adjustment](#method.resolve_ilvl_adjustment)
@@ -122,18 +388,58 @@ pub struct Db2ItemBonus {
 impl Db2ItemBonuses {
     /// Deserializes a CSV string which represents the DB2 ItemBonus table
     /// in World of Warcraft.
-    pub fn from_csv(csv: &str) -> Db2ItemBonuses {
-        let mut reader = csv::Reader::from_reader(csv.as_bytes());
-        let iter = reader.deserialize::<Db2ItemBonus>();
+    ///
+    /// Rows which can't be deserialized fail the load, or are skipped and listed in the
+    /// returned [`LoadReport`] when loading with [LoadMode::Lenient](`LoadMode::Lenient`).
+    pub fn from_csv(csv: &str, mode: LoadMode) -> Result<(Db2ItemBonuses, LoadReport), Error> {
+        let (rows, report) = load::read_csv::<Db2ItemBonus>(TABLE_NAME, csv, mode)?;
+        Ok((Db2ItemBonuses::from_rows(rows), report))
+    }
+
+    /// Reads the raw `ItemBonus.db2` file of World of Warcraft.
+    ///
+    /// See [from_csv](`Self::from_csv`) for how `mode` is used.
+    pub fn from_db2(bytes: &[u8], mode: LoadMode) -> Result<(Db2ItemBonuses, LoadReport), Error> {
+        Db2ItemBonuses::from_db2_file(Db2File::parse(bytes)?, mode)
+    }
 
+    /// Reads an already parsed `ItemBonus.db2` file, such as one with
+    /// [hotfixes applied](`crate::wow::db2::Db2File::with_hotfixes`).
+    pub fn from_db2_file(
+        file: Db2File,
+        mode: LoadMode,
+    ) -> Result<(Db2ItemBonuses, LoadReport), Error> {
+        let (rows, report) = load::read_db2(TABLE_NAME, &file, mode, |record| {
+            let values = record.i32_array(DB2_VALUE_FIELD)?;
+            let value = |i: usize| *values.get(i).unwrap_or(&0);
+            Ok(Db2ItemBonus {
+                id: record.id(),
+                value0: value(0),
+                value1: value(1),
+                value2: value(2),
+                value3: value(3),
+                parent_item_bonus_list_id: record.relation().unwrap_or(0),
+                type_id: record.u32(DB2_TYPE_FIELD)? as u16,
+                order_index: record.u32(DB2_ORDER_INDEX_FIELD)? as u16,
+            })
+        })?;
+
+        Ok((Db2ItemBonuses::from_rows(rows), report))
+    }
+
+    fn from_rows<I: IntoIterator<Item = Db2ItemBonus>>(rows: I) -> Db2ItemBonuses {
+        let mut effects: HashMap<u32, Vec<ItemBonusEffect>> = HashMap::new();
         let mut curve_ids: HashMap<u32, u32> = HashMap::new();
         let mut ilvl_adjustments: HashMap<u32, i32> = HashMap::new();
 
-        for bonus in iter {
-            if bonus.is_err() {
-                continue;
-            }
-            let bonus = bonus.unwrap();
+        let mut rows: Vec<Db2ItemBonus> = rows.into_iter().collect();
+        rows.sort_by_key(|bonus| (bonus.parent_item_bonus_list_id, bonus.order_index));
+
+        for bonus in rows {
+            effects
+                .entry(bonus.parent_item_bonus_list_id)
+                .or_default()
+                .push(bonus.effect());
 
             // Map the associated curve ids or item level adjustments
             match bonus.type_id {
@@ -150,12 +456,75 @@ impl Db2ItemBonuses {
         }
 
         Db2ItemBonuses {
-            // TODO(seputaes) Do we need to keep this data in memory? // bonuses,
+            effects,
             curve_ids,
             ilvl_adjustments,
         }
     }
 
+    /// The effects of every bonus in a bonus list, in their order within the list.
+    ///
+    /// The Bonus ID is the number that appears in an Item's `bonus_lists` field. If
+    /// the Bonus ID isn't known, the list is empty.
+    pub fn effects(&self, bonus_id: &u32) -> &[ItemBonusEffect] {
+        self.effects.get(bonus_id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// The effects of every bonus in all of the bonus lists of an item.
+    pub fn resolve_effects<'s>(
+        &'s self,
+        bonus_ids: &'s [u32],
+    ) -> impl Iterator<Item = &'s ItemBonusEffect> + 's {
+        bonus_ids
+            .iter()
+            .flat_map(move |bonus_id| self.effects(bonus_id).iter())
+    }
+
+    /// The total number of sockets the bonus lists of an item add.
+    pub fn resolve_sockets(&self, bonus_ids: &[u32]) -> u32 {
+        self.resolve_effects(bonus_ids)
+            .map(|effect| match effect {
+                ItemBonusEffect::Socket { count, .. } => *count as u32,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// The tertiary stats the bonus lists of an item add, sorted and without
+    /// duplicates.
+    pub fn resolve_tertiary_stats(&self, bonus_ids: &[u32]) -> Vec<TertiaryStat> {
+        let mut stats: Vec<TertiaryStat> = self
+            .resolve_effects(bonus_ids)
+            .filter_map(ItemBonusEffect::tertiary_stat)
+            .collect();
+        stats.sort_unstable();
+        stats.dedup();
+        stats
+    }
+
+    /// The secondary stats the bonus lists of an item add, sorted and without
+    /// duplicates.
+    pub fn resolve_secondary_stats(&self, bonus_ids: &[u32]) -> Vec<SecondaryStat> {
+        let mut stats: Vec<SecondaryStat> = self
+            .resolve_effects(bonus_ids)
+            .filter_map(ItemBonusEffect::secondary_stat)
+            .collect();
+        stats.sort_unstable();
+        stats.dedup();
+        stats
+    }
+
+    /// The quality the bonus lists of an item override the item's quality with, if
+    /// any. When several bonuses override it, the last one wins.
+    pub fn resolve_quality(&self, bonus_ids: &[u32]) -> Option<i32> {
+        self.resolve_effects(bonus_ids)
+            .filter_map(|effect| match effect {
+                ItemBonusEffect::Quality(quality) => Some(*quality),
+                _ => None,
+            })
+            .last()
+    }
+
     /// Finds the simple item level adjustment associated with a Bonus ID, if one exists.
     ///
     /// If there are multiple bonus IDs on an item, you should use
@@ -235,6 +604,7 @@ impl Db2ItemBonuses {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::wow::db2::builder::{Db2Builder, Storage, Value};
 
     const ITEM_BONUSES_CSV_HEADER: &str =
         "ID,Value[0],Value[1],Value[2],Value[3],ParentItemBonusListID,Type,OrderIndex";
@@ -244,7 +614,7 @@ mod tests {
         let mut csv = String::from(ITEM_BONUSES_CSV_HEADER);
         csv.push_str("\n5,-2,0,0,0,58,1,0");
 
-        let table = Db2ItemBonuses::from_csv(&csv);
+        let (table, _) = Db2ItemBonuses::from_csv(&csv, LoadMode::Strict).unwrap();
         assert_eq!(-2, table.resolve_ilvl_adjustment(&[58]).unwrap());
     }
 
@@ -253,7 +623,7 @@ mod tests {
         let mut csv = String::from(ITEM_BONUSES_CSV_HEADER);
         csv.push_str("\n5,-2,0,0,0,58,1,0\n9,40,0,0,0,72,1,0");
 
-        let table = Db2ItemBonuses::from_csv(&csv);
+        let (table, _) = Db2ItemBonuses::from_csv(&csv, LoadMode::Strict).unwrap();
         assert_eq!(38, table.resolve_ilvl_adjustment(&[58, 72]).unwrap());
     }
 
@@ -262,7 +632,7 @@ mod tests {
         let mut csv = String::from(ITEM_BONUSES_CSV_HEADER);
         csv.push_str("\n5,-2,0,0,0,58,1,0\n9,40,0,0,0,72,1,0\n3,0,0,0,1222,72,11,0");
 
-        let table = Db2ItemBonuses::from_csv(&csv);
+        let (table, _) = Db2ItemBonuses::from_csv(&csv, LoadMode::Strict).unwrap();
         assert_eq!(38, table.resolve_ilvl_adjustment(&[58, 72]).unwrap());
     }
 
@@ -271,7 +641,7 @@ mod tests {
         let mut csv = String::from(ITEM_BONUSES_CSV_HEADER);
         csv.push_str("\n5,0,0,0,19932,58,11,0");
 
-        let table = Db2ItemBonuses::from_csv(&csv);
+        let (table, _) = Db2ItemBonuses::from_csv(&csv, LoadMode::Strict).unwrap();
         assert_eq!(19932, table.resolve_curve_id(&[58, 72]).unwrap());
     }
 
@@ -280,7 +650,7 @@ mod tests {
         let mut csv = String::from(ITEM_BONUSES_CSV_HEADER);
         csv.push_str("\n5,0,0,0,19932,58,11,0\n9,0,0,0,17322,72,11,0");
 
-        let table = Db2ItemBonuses::from_csv(&csv);
+        let (table, _) = Db2ItemBonuses::from_csv(&csv, LoadMode::Strict).unwrap();
         assert_eq!(19932, table.resolve_curve_id(&[58, 72]).unwrap());
     }
 
@@ -289,7 +659,108 @@ mod tests {
         let mut csv = String::from(ITEM_BONUSES_CSV_HEADER);
         csv.push_str("\n5,0,0,0,17322,58,13,0\n9,0,0,0,19932,72,13,0");
 
-        let table = Db2ItemBonuses::from_csv(&csv);
+        let (table, _) = Db2ItemBonuses::from_csv(&csv, LoadMode::Strict).unwrap();
         assert_eq!(19932, table.resolve_curve_id(&[58, 72]).unwrap());
     }
+
+    #[test]
+    fn from_db2_maps_adjustments_and_curves() {
+        let bytes = Db2Builder::new()
+            .field(Storage::None { bits: 32, count: 4 })
+            .field(Storage::Bitpacked { bits: 5 })
+            .field(Storage::Bitpacked { bits: 3 })
+            .related_row(
+                5,
+                58,
+                vec![
+                    Value::Int(vec![(-2i32) as u32 as u64, 0, 0, 0]),
+                    Value::Int(vec![1]),
+                    Value::Int(vec![0]),
+                ],
+            )
+            .related_row(
+                3,
+                72,
+                vec![
+                    Value::Int(vec![0, 0, 0, 1222]),
+                    Value::Int(vec![11]),
+                    Value::Int(vec![1]),
+                ],
+            )
+            .build();
+
+        let (table, _) = Db2ItemBonuses::from_db2(&bytes, LoadMode::Strict).unwrap();
+        assert_eq!(Some(-2), table.ilvl_adjustment(&58));
+        assert_eq!(Some(1222), table.curve_id(&72));
+        assert_eq!(None, table.curve_id(&58));
+        assert_eq!(&[ItemBonusEffect::ItemLevel(-2)], table.effects(&58));
+    }
+
+    #[test]
+    fn retains_every_bonus_type() {
+        let mut csv = String::from(ITEM_BONUSES_CSV_HEADER);
+        // bonus list 6646: a socket, then speed, in reverse order index
+        csv.push_str("\n1,61,2223,0,0,6646,2,1\n2,1,8,0,0,6646,6,0");
+        // bonus list 1808: quality, a name suffix, a required level and an unknown type
+        csv.push_str("\n3,4,0,0,0,1808,3,0\n4,13420,1,0,0,1808,5,1");
+        csv.push_str("\n5,-5,0,0,0,1808,8,2\n6,7,8,9,10,1808,42,3");
+        // bonus list 41: leech and avoidance, and leech again from a second list
+        csv.push_str("\n7,62,2223,0,0,41,2,0\n8,63,2223,0,0,41,2,1\n9,62,2223,0,0,42,2,0");
+
+        let (table, _) = Db2ItemBonuses::from_csv(&csv, LoadMode::Strict).unwrap();
+
+        assert_eq!(
+            &[
+                ItemBonusEffect::Socket {
+                    count: 1,
+                    socket_type: 8
+                },
+                ItemBonusEffect::Stat {
+                    stat_type: 61,
+                    allocation: 2223
+                },
+            ],
+            table.effects(&6646)
+        );
+        assert_eq!(
+            &[
+                ItemBonusEffect::Quality(4),
+                ItemBonusEffect::NameSuffix {
+                    description_id: 13420,
+                    priority: 1
+                },
+                ItemBonusEffect::RequiredLevel(-5),
+                ItemBonusEffect::Other {
+                    type_id: 42,
+                    values: [7, 8, 9, 10]
+                },
+            ],
+            table.effects(&1808)
+        );
+        assert!(table.effects(&1).is_empty());
+
+        assert_eq!(1, table.resolve_sockets(&[6646, 1808]));
+        assert_eq!(Some(4), table.resolve_quality(&[6646, 1808]));
+        assert_eq!(
+            vec![TertiaryStat::Speed],
+            table.resolve_tertiary_stats(&[6646])
+        );
+        assert!(table.resolve_secondary_stats(&[6646]).is_empty());
+        assert_eq!(
+            vec![TertiaryStat::Leech, TertiaryStat::Avoidance],
+            table.resolve_tertiary_stats(&[42, 41])
+        );
+    }
+
+    #[test]
+    fn effects_round_trip_through_raw_values() {
+        for type_id in 0..40 {
+            let values = [3, 4, 0, 5];
+            let effect = ItemBonusEffect::from_raw(type_id, values);
+            assert_eq!(effect, {
+                let (type_id, values) = effect.to_raw();
+                ItemBonusEffect::from_raw(type_id, values)
+            });
+        }
+    }
 }
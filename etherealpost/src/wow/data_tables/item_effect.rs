@@ -1,8 +1,19 @@
 use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
 
+use super::load::{self, LoadMode, LoadReport};
+use crate::wow::db2::Db2File;
+use crate::Error;
+
+const TABLE_NAME: &str = "ItemEffect";
+
 const LEARN_TRIGGER_TYPE: i16 = 6;
 
+// Field indices of the ItemEffect DB2 as of 9.0.5. The ID is stored in the ID list
+// and ParentItemID in the relationship map.
+const DB2_TRIGGER_TYPE_FIELD: usize = 1;
+const DB2_SPELL_ID_FIELD: usize = 6;
+
 /// Container struct for all of the [`Db2ItemEffect`] rows.
 pub struct Db2ItemEffects {
     // TODO(seputaes) Do we need to keep this data in memory?
@@ -26,6 +37,7 @@ pub struct Db2ItemEffects {
 pub struct Db2ItemEffect {
     /// The unique ID of the spell effect.
     #[serde(rename = "ID")]
+    #[allow(dead_code)]
     id: u32,
 
     /// The Spell ID that is associated with the effect.
@@ -53,26 +65,56 @@ pub struct Db2ItemEffect {
 impl Db2ItemEffects {
     /// Deserializes a CSV string which represents the DB2 ItemEffect table
     /// in World of Warcraft.
-    pub fn from_csv(csv: &str) -> Db2ItemEffects {
-        let mut reader = csv::Reader::from_reader(csv.as_bytes());
-        let iter = reader.deserialize::<Db2ItemEffect>();
+    ///
+    /// Rows which can't be deserialized fail the load, or are skipped and listed in the
+    /// returned [`LoadReport`] when loading with [LoadMode::Lenient](`LoadMode::Lenient`).
+    pub fn from_csv(csv: &str, mode: LoadMode) -> Result<(Db2ItemEffects, LoadReport), Error> {
+        let (rows, report) = load::read_csv::<Db2ItemEffect>(TABLE_NAME, csv, mode)?;
+        Ok((Db2ItemEffects::from_rows(rows), report))
+    }
 
-        let mut item_to_spell_learn = HashMap::new();
-        let mut known_parent_ids = HashSet::new();
+    /// Reads the raw `ItemEffect.db2` file of World of Warcraft.
+    ///
+    /// See [from_csv](`Self::from_csv`) for how `mode` is used.
+    pub fn from_db2(bytes: &[u8], mode: LoadMode) -> Result<(Db2ItemEffects, LoadReport), Error> {
+        Db2ItemEffects::from_db2_file(Db2File::parse(bytes)?, mode)
+    }
 
-        for row in iter {
-            // TOOD(seputaes): Logging for the error
-            if row.is_err() {
-                panic!("Parsing error: {}", row.err().unwrap());
-            }
+    /// Reads an already parsed `ItemEffect.db2` file, such as one with
+    /// [hotfixes applied](`crate::wow::db2::Db2File::with_hotfixes`).
+    pub fn from_db2_file(
+        file: Db2File,
+        mode: LoadMode,
+    ) -> Result<(Db2ItemEffects, LoadReport), Error> {
+        let (rows, report) = load::read_db2(TABLE_NAME, &file, mode, |record| {
+            Ok(Db2ItemEffect {
+                id: record.id(),
+                spell_id: record.u32(DB2_SPELL_ID_FIELD)?,
+                trigger_type: record.i32(DB2_TRIGGER_TYPE_FIELD)? as i16,
+                parent_item_id: record.relation().unwrap_or(0),
+            })
+        })?;
+
+        Ok((Db2ItemEffects::from_rows(rows), report))
+    }
 
-            let row = row.unwrap();
+    fn from_rows<I: IntoIterator<Item = Db2ItemEffect>>(rows: I) -> Db2ItemEffects {
+        let mut item_to_spell_learn = HashMap::new();
+        let mut known_parent_ids = HashSet::new();
+        let mut duplicate_learn_spells = 0;
 
+        for row in rows {
             // check for multiple spell IDS for the same parent item ID with trigger type of 6
             if row.trigger_type == LEARN_TRIGGER_TYPE {
                 if known_parent_ids.contains(&row.parent_item_id) {
-                    // TODO(seputaes): Logging. I think this is harmless, but
-                    //                 it would be good to know when this happens.
+                    // I think this is harmless, the first learn spell of an item wins
+                    duplicate_learn_spells += 1;
+                    tracing::debug!(
+                        item_id = row.parent_item_id,
+                        spell_id = row.spell_id,
+                        kept_spell_id = item_to_spell_learn.get(&row.parent_item_id).copied(),
+                        "item has more than one learn spell, ignoring this one"
+                    );
                     continue;
                 }
                 known_parent_ids.insert(row.parent_item_id);
@@ -81,6 +123,14 @@ impl Db2ItemEffects {
             }
         }
 
+        if duplicate_learn_spells > 0 {
+            tracing::info!(
+                duplicate_learn_spells,
+                "ignored duplicate learn spells in {}",
+                TABLE_NAME
+            );
+        }
+
         Db2ItemEffects {
             // TODO(seputaes) Do we need to keep this data in memory?
             // effects: HashMap::new(),
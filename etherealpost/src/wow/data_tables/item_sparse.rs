@@ -1,6 +1,17 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 
+use super::load::{self, LoadMode, LoadReport};
+use crate::wow::db2::Db2File;
+use crate::Error;
+
+const TABLE_NAME: &str = "ItemSparse";
+
+// Field indices of the ItemSparse DB2 as of 9.0.5. ItemSparse is a sparse table, so its
+// strings (the description and display names) are stored inline in each record.
+const DB2_INLINE_STRING_FIELDS: [usize; 5] = [1, 2, 3, 4, 5];
+const DB2_ITEM_LEVEL_FIELD: usize = 43;
+
 /// Container struct for all of the [`Db2ItemSparse`] rows.
 pub struct Db2ItemSparseTable {
     // TODO(seputaes) Do we need to keep this data in memory?
@@ -33,20 +44,45 @@ pub struct Db2ItemSparse {
 impl Db2ItemSparseTable {
     /// Deserializes a CSV string which represents the DB2 ItemSparse table
     /// in World of Warcraft.
-    pub fn from_csv(csv: &str) -> Db2ItemSparseTable {
-        let mut reader = csv::Reader::from_reader(csv.as_bytes());
-        let iter = reader.deserialize::<Db2ItemSparse>();
+    ///
+    /// Rows which can't be deserialized fail the load, or are skipped and listed in the
+    /// returned [`LoadReport`] when loading with [LoadMode::Lenient](`LoadMode::Lenient`).
+    pub fn from_csv(csv: &str, mode: LoadMode) -> Result<(Db2ItemSparseTable, LoadReport), Error> {
+        let (rows, report) = load::read_csv::<Db2ItemSparse>(TABLE_NAME, csv, mode)?;
+        Ok((Db2ItemSparseTable::from_rows(rows), report))
+    }
 
-        let mut base_item_levels = HashMap::new();
+    /// Reads the raw `ItemSparse.db2` file of World of Warcraft.
+    ///
+    /// See [from_csv](`Self::from_csv`) for how `mode` is used.
+    pub fn from_db2(
+        bytes: &[u8],
+        mode: LoadMode,
+    ) -> Result<(Db2ItemSparseTable, LoadReport), Error> {
+        Db2ItemSparseTable::from_db2_file(Db2File::parse(bytes)?, mode)
+    }
 
-        for row in iter {
-            // TODO(seputaes): Logging for the error
-            if row.is_err() {
-                continue;
-            }
+    /// Reads an already parsed `ItemSparse.db2` file, such as one with
+    /// [hotfixes applied](`crate::wow::db2::Db2File::with_hotfixes`).
+    pub fn from_db2_file(
+        file: Db2File,
+        mode: LoadMode,
+    ) -> Result<(Db2ItemSparseTable, LoadReport), Error> {
+        let file = file.with_inline_strings(&DB2_INLINE_STRING_FIELDS);
+        let (rows, report) = load::read_db2(TABLE_NAME, &file, mode, |record| {
+            Ok(Db2ItemSparse {
+                id: record.id(),
+                item_level: record.u32(DB2_ITEM_LEVEL_FIELD)?,
+            })
+        })?;
+
+        Ok((Db2ItemSparseTable::from_rows(rows), report))
+    }
 
-            let row = row.unwrap();
+    fn from_rows<I: IntoIterator<Item = Db2ItemSparse>>(rows: I) -> Db2ItemSparseTable {
+        let mut base_item_levels = HashMap::new();
 
+        for row in rows {
             base_item_levels.insert(row.id, row.item_level);
         }
 
@@ -64,18 +100,27 @@ impl Db2ItemSparseTable {
     /// is even available to players.
     ///
     /// If the item ID is not present in the data files containing
-    /// item level information, then a default `1` is returned.
-    ///
-    /// TODO(seputaes): Add some logging here. It would be good to know
-    ///                 when there isn't base ilvl info.
+    /// item level information, then a default `1` is returned, and a `debug`
+    /// tracing event is emitted for the item.
     pub fn base_ilvl(&self, item_id: &u32) -> u32 {
-        *self.base_item_levels.get(item_id).unwrap_or(&1)
+        match self.base_item_levels.get(item_id) {
+            Some(ilvl) => *ilvl,
+            None => {
+                tracing::debug!(
+                    item_id = *item_id,
+                    "item has no base item level, falling back to item level 1"
+                );
+                1
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::wow::db2::builder::{db_cache, Db2Builder, Storage, Value, TABLE_HASH};
+    use crate::wow::db2::DbCache;
 
     const ITEM_SPARSE_CSV_HEADER: &str = "ID,AllowableRace,Description_lang,Display3_lang,Display2_lang,Display1_lang,Display_lang,DmgVariance,DurationInInventory,QualityModifier,BagFamily,ItemRange,StatPercentageOfSocket[0],StatPercentageOfSocket[1],StatPercentageOfSocket[2],StatPercentageOfSocket[3],StatPercentageOfSocket[4],StatPercentageOfSocket[5],StatPercentageOfSocket[6],StatPercentageOfSocket[7],StatPercentageOfSocket[8],StatPercentageOfSocket[9],StatPercentEditor[0],StatPercentEditor[1],StatPercentEditor[2],StatPercentEditor[3],StatPercentEditor[4],StatPercentEditor[5],StatPercentEditor[6],StatPercentEditor[7],StatPercentEditor[8],StatPercentEditor[9],Stackable,MaxCount,RequiredAbility,SellPrice,BuyPrice,VendorStackCount,PriceVariance,PriceRandomValue,Flags[0],Flags[1],Flags[2],Flags[3],OppositeFactionItemID,ModifiedCraftingReagentItemID,ContentTuningID,PlayerLevelToItemLevelCurveID,ItemNameDescriptionID,RequiredTransmogHoliday,RequiredHoliday,LimitCategory,Gem_properties,Socket_match_enchantment_ID,TotemCategoryID,InstanceBound,ZoneBound[0],ZoneBound[1],ItemSet,LockID,StartQuestID,PageID,ItemDelay,MinFactionID,RequiredSkillRank,RequiredSkill,ItemLevel,AllowableClass,ExpansionID,ArtifactID,SpellWeight,SpellWeightCategory,SocketType[0],SocketType[1],SocketType[2],SheatheType,Material,PageMaterialID,LanguageID,Bonding,DamageType,StatModifier_bonusStat[0],StatModifier_bonusStat[1],StatModifier_bonusStat[2],StatModifier_bonusStat[3],StatModifier_bonusStat[4],StatModifier_bonusStat[5],StatModifier_bonusStat[6],StatModifier_bonusStat[7],StatModifier_bonusStat[8],StatModifier_bonusStat[9],ContainerSlots,MinReputation,RequiredPVPMedal,RequiredPVPRank,RequiredLevel,InventoryType,OverallQualityID";
 
@@ -84,7 +129,7 @@ mod tests {
         let mut csv = String::from(ITEM_SPARSE_CSV_HEADER);
         csv.push_str("\n183421,-1,,,,,Stone Legion Sabatons,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,5259,7889,4250,2750,0,0,0,0,0,0,1,0,0,424559,2122798,1,1,0.9565,0,8192,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,100,-1,8,0,0,0,0,0,0,0,5,0,0,2,0,74,7,40,36,-1,-1,-1,-1,-1,-1,0,0,0,0,48,8,3");
 
-        let table = Db2ItemSparseTable::from_csv(&csv);
+        let (table, _) = Db2ItemSparseTable::from_csv(&csv, LoadMode::Strict).unwrap();
         assert_eq!(100, table.base_ilvl(&183421));
     }
 
@@ -93,7 +138,67 @@ mod tests {
         let mut csv = String::from(ITEM_SPARSE_CSV_HEADER);
         csv.push_str("\n183421,-1,,,,,Stone Legion Sabatons,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,5259,7889,4250,2750,0,0,0,0,0,0,1,0,0,424559,2122798,1,1,0.9565,0,8192,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,100,-1,8,0,0,0,0,0,0,0,5,0,0,2,0,74,7,40,36,-1,-1,-1,-1,-1,-1,0,0,0,0,48,8,3");
 
-        let table = Db2ItemSparseTable::from_csv(&csv);
+        let (table, _) = Db2ItemSparseTable::from_csv(&csv, LoadMode::Strict).unwrap();
         assert_eq!(1, table.base_ilvl(&25));
     }
+
+    #[test]
+    fn base_ilvl_from_db2() {
+        let mut builder = Db2Builder::new()
+            .sparse()
+            .field(Storage::None { bits: 64, count: 1 });
+        for _ in 1..=5 {
+            builder = builder.field(Storage::String);
+        }
+        for _ in 6..DB2_ITEM_LEVEL_FIELD {
+            builder = builder.field(Storage::None { bits: 32, count: 1 });
+        }
+        builder = builder.field(Storage::None { bits: 16, count: 1 });
+
+        let row = |name: &str, item_level: u64| {
+            let mut values = vec![Value::Int(vec![(-1i64) as u64])];
+            values.push(Value::Str(String::new()));
+            values.push(Value::Str(String::new()));
+            values.push(Value::Str(String::new()));
+            values.push(Value::Str(String::new()));
+            values.push(Value::Str(name.to_string()));
+            for _ in 6..DB2_ITEM_LEVEL_FIELD {
+                values.push(Value::Int(vec![0]));
+            }
+            values.push(Value::Int(vec![item_level]));
+            values
+        };
+        let builder = builder.row(183421, row("Stone Legion Sabatons", 100)).row(
+            19019,
+            row("Thunderfury, Blessed Blade of the Windseeker", 80),
+        );
+        let thunderfury = builder.hotfix_record(
+            &row("Thunderfury, Blessed Blade of the Windseeker", 85),
+            None,
+        );
+        let added = builder.hotfix_record(&row("Martin Thunder", 60), None);
+        let bytes = builder.build();
+
+        let (table, _) = Db2ItemSparseTable::from_db2(&bytes, LoadMode::Strict).unwrap();
+        assert_eq!(100, table.base_ilvl(&183421));
+        assert_eq!(80, table.base_ilvl(&19019));
+        assert_eq!(1, table.base_ilvl(&25));
+
+        // a live hotfix bumps Thunderfury, adds a new item and deletes the sabatons
+        let cache = DbCache::parse(&db_cache(
+            9,
+            &[
+                (1, TABLE_HASH, 19019, 1, thunderfury),
+                (1, TABLE_HASH, 25, 1, added),
+                (2, TABLE_HASH, 183421, 2, vec![]),
+            ],
+        ))
+        .unwrap();
+        let file = Db2File::parse(&bytes).unwrap().with_hotfixes(&cache);
+
+        let (table, _) = Db2ItemSparseTable::from_db2_file(file, LoadMode::Strict).unwrap();
+        assert_eq!(85, table.base_ilvl(&19019));
+        assert_eq!(60, table.base_ilvl(&25));
+        assert_eq!(1, table.base_ilvl(&183421));
+    }
 }
@@ -0,0 +1,172 @@
+use serde::de::DeserializeOwned;
+
+use crate::wow::db2::{Db2Error, Db2File, Db2Record};
+use crate::Error;
+
+/// How a data table treats rows which can't be read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoadMode {
+    /// The first row which can't be read fails the whole load with
+    /// [Error::InvalidRow](`crate::Error::InvalidRow`). This is the default.
+    #[default]
+    Strict,
+
+    /// Rows which can't be read are skipped, and listed in the [`LoadReport`].
+    Lenient,
+}
+
+/// What happened while a data table was loaded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LoadReport {
+    /// The name of the table which was loaded.
+    pub table: &'static str,
+
+    /// The number of rows which were read.
+    pub rows_read: usize,
+
+    /// Every row which was skipped because it couldn't be read. This is always empty
+    /// for a [strict](`LoadMode::Strict`) load.
+    pub skipped: Vec<SkippedRow>,
+}
+
+impl LoadReport {
+    fn new(table: &'static str) -> Self {
+        LoadReport {
+            table,
+            ..LoadReport::default()
+        }
+    }
+
+    /// Whether every row of the table was read.
+    pub fn is_complete(&self) -> bool {
+        self.skipped.is_empty()
+    }
+
+    /// Either records a row which couldn't be read or fails the load, depending on
+    /// the mode.
+    fn skip(&mut self, mode: LoadMode, line: u64, reason: String) -> Result<(), Error> {
+        match mode {
+            LoadMode::Strict => Err(Error::InvalidRow {
+                table: self.table,
+                line,
+                reason,
+            }),
+            // a strict load is failed instead, and its error already says why
+            LoadMode::Lenient => {
+                tracing::warn!(table = self.table, line, reason = %reason, "skipped a row");
+                self.skipped.push(SkippedRow { line, reason });
+                Ok(())
+            }
+        }
+    }
+
+    /// Records the number of rows read and emits the outcome of the load.
+    fn finish(&mut self, rows_read: usize) {
+        self.rows_read = rows_read;
+        tracing::debug!(
+            table = self.table,
+            rows_read,
+            skipped = self.skipped.len(),
+            "loaded table"
+        );
+    }
+}
+
+/// A row which was skipped during a [lenient](`LoadMode::Lenient`) load.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkippedRow {
+    /// The line of the row in a CSV file, or the position of the record in
+    /// a DB2 file, starting from `1`.
+    pub line: u64,
+
+    /// Why the row could not be read.
+    pub reason: String,
+}
+
+/// Deserializes every row of a CSV export of a table.
+pub(crate) fn read_csv<T: DeserializeOwned>(
+    table: &'static str,
+    csv: &str,
+    mode: LoadMode,
+) -> Result<(Vec<T>, LoadReport), Error> {
+    let _span = tracing::debug_span!("load_table", table, format = "csv").entered();
+    let mut reader = csv::Reader::from_reader(csv.as_bytes());
+    let mut report = LoadReport::new(table);
+    let mut rows = Vec::new();
+
+    for row in reader.deserialize::<T>() {
+        match row {
+            Ok(row) => rows.push(row),
+            Err(e) => {
+                let line = e.position().map(|p| p.line()).unwrap_or(0);
+                report.skip(mode, line, e.to_string())?;
+            }
+        }
+    }
+
+    report.finish(rows.len());
+    Ok((rows, report))
+}
+
+/// Reads every record of a DB2 file of a table.
+pub(crate) fn read_db2<T, F>(
+    table: &'static str,
+    file: &Db2File,
+    mode: LoadMode,
+    mut read: F,
+) -> Result<(Vec<T>, LoadReport), Error>
+where
+    F: FnMut(&Db2Record) -> Result<T, Db2Error>,
+{
+    let _span = tracing::debug_span!("load_table", table, format = "db2").entered();
+    let mut report = LoadReport::new(table);
+    let mut rows = Vec::with_capacity(file.len());
+
+    for (index, record) in file.records().enumerate() {
+        match read(&record) {
+            Ok(row) => rows.push(row),
+            Err(e) => {
+                let reason = format!("record {}: {}", record.id(), e);
+                report.skip(mode, index as u64 + 1, reason)?;
+            }
+        }
+    }
+
+    report.finish(rows.len());
+    Ok((rows, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Row {
+        #[serde(rename = "ID")]
+        id: u32,
+    }
+
+    const CSV: &str = "ID\n1\nnot a number\n3\n";
+
+    #[test]
+    fn strict_loads_fail_on_the_first_bad_row() {
+        match read_csv::<Row>("Test", CSV, LoadMode::Strict) {
+            Err(Error::InvalidRow { table, line, .. }) => {
+                assert_eq!("Test", table);
+                assert_eq!(3, line);
+            }
+            other => panic!("unexpected result: {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn lenient_loads_report_skipped_rows() {
+        let (rows, report) = read_csv::<Row>("Test", CSV, LoadMode::Lenient).unwrap();
+        assert_eq!(vec![1, 3], rows.iter().map(|r| r.id).collect::<Vec<_>>());
+        assert_eq!(2, report.rows_read);
+        assert!(!report.is_complete());
+        assert_eq!(1, report.skipped.len());
+        assert_eq!(3, report.skipped[0].line);
+    }
+}
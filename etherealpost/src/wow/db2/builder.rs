@@ -0,0 +1,493 @@
+//! Assembles small DB2 files for tests.
+//!
+//! This only writes the parts of the format the reader relies on, and lays
+//! the file out the same way the client's files are: header, section headers,
+//! field info, pallet and common data, then each section.
+
+use std::collections::HashMap;
+
+/// The table hash written to every file's header.
+pub(crate) const TABLE_HASH: u32 = 0x1234_5678;
+
+/// How a field is stored, mirroring [FieldStorage](`super::FieldStorage`).
+#[derive(Clone, Copy)]
+pub(crate) enum Storage {
+    None { bits: u32, count: u32 },
+    String,
+    Bitpacked { bits: u32 },
+    BitpackedSigned { bits: u32 },
+    Common { default: u32 },
+    Pallet { bits: u32 },
+    PalletArray { bits: u32, count: u32 },
+}
+
+/// The value of a single field in a row.
+pub(crate) enum Value {
+    Int(Vec<u64>),
+    Str(String),
+}
+
+struct Row {
+    id: u32,
+    relation: Option<u32>,
+    values: Vec<Value>,
+}
+
+struct Layout {
+    offset_bits: u32,
+    size_bits: u32,
+}
+
+pub(crate) struct Db2Builder {
+    magic: [u8; 4],
+    fields: Vec<Storage>,
+    rows: Vec<Row>,
+    copies: Vec<(u32, u32)>,
+    sparse: bool,
+    sections: usize,
+    encrypted_sections: Vec<usize>,
+}
+
+impl Db2Builder {
+    pub(crate) fn new() -> Self {
+        Db2Builder {
+            magic: *b"WDC3",
+            fields: Vec::new(),
+            rows: Vec::new(),
+            copies: Vec::new(),
+            sparse: false,
+            sections: 1,
+            encrypted_sections: Vec::new(),
+        }
+    }
+
+    pub(crate) fn version(mut self, magic: &[u8; 4]) -> Self {
+        self.magic = *magic;
+        self
+    }
+
+    pub(crate) fn sparse(mut self) -> Self {
+        self.sparse = true;
+        self
+    }
+
+    /// Splits the rows evenly over `sections` sections.
+    pub(crate) fn sections(mut self, sections: usize) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    /// Marks a section as encrypted with an unknown key, which zeroes its records.
+    pub(crate) fn encrypt_section(mut self, section: usize) -> Self {
+        self.encrypted_sections.push(section);
+        self
+    }
+
+    pub(crate) fn field(mut self, storage: Storage) -> Self {
+        self.fields.push(storage);
+        self
+    }
+
+    pub(crate) fn row(mut self, id: u32, values: Vec<Value>) -> Self {
+        self.rows.push(Row {
+            id,
+            relation: None,
+            values,
+        });
+        self
+    }
+
+    pub(crate) fn related_row(mut self, id: u32, relation: u32, values: Vec<Value>) -> Self {
+        self.rows.push(Row {
+            id,
+            relation: Some(relation),
+            values,
+        });
+        self
+    }
+
+    pub(crate) fn copy(mut self, new_id: u32, copied_id: u32) -> Self {
+        self.copies.push((new_id, copied_id));
+        self
+    }
+
+    /// Lays out a record the way `DBCache.bin` stores it for this table: every field
+    /// at its full size with strings inline, then the relation if there is one.
+    pub(crate) fn hotfix_record(&self, values: &[Value], relation: Option<u32>) -> Vec<u8> {
+        let mut record = Vec::new();
+        for (storage, value) in self.fields.iter().zip(values) {
+            match (storage, value) {
+                (Storage::String, Value::Str(value)) => {
+                    record.extend_from_slice(value.as_bytes());
+                    record.push(0);
+                }
+                (Storage::None { bits, .. }, Value::Int(values)) => {
+                    for value in values {
+                        record.extend_from_slice(&value.to_le_bytes()[..*bits as usize / 8]);
+                    }
+                }
+                (_, Value::Int(values)) => {
+                    for value in values {
+                        record.extend_from_slice(&(*value as u32).to_le_bytes());
+                    }
+                }
+                _ => panic!("hotfix value has the wrong type"),
+            }
+        }
+        if let Some(relation) = relation {
+            record.extend_from_slice(&relation.to_le_bytes());
+        }
+        record
+    }
+
+    fn ints(row: &Row, field: usize) -> &[u64] {
+        match &row.values[field] {
+            Value::Int(values) => values,
+            Value::Str(_) => panic!("field {} of row {} is a string", field, row.id),
+        }
+    }
+
+    fn layout(&self) -> (Vec<Layout>, u32) {
+        let mut offset_bits: u32 = 0;
+        let mut layout = Vec::new();
+        for storage in &self.fields {
+            let size_bits = match storage {
+                Storage::None { bits, count } => {
+                    offset_bits = offset_bits.div_ceil(8) * 8;
+                    bits * count
+                }
+                Storage::String => {
+                    offset_bits = offset_bits.div_ceil(8) * 8;
+                    32
+                }
+                Storage::Bitpacked { bits }
+                | Storage::BitpackedSigned { bits }
+                | Storage::Pallet { bits }
+                | Storage::PalletArray { bits, .. } => *bits,
+                Storage::Common { .. } => 0,
+            };
+            layout.push(Layout {
+                offset_bits,
+                size_bits,
+            });
+            offset_bits += size_bits;
+        }
+        (layout, offset_bits.div_ceil(8))
+    }
+
+    pub(crate) fn build(self) -> Vec<u8> {
+        let (layout, record_size) = self.layout();
+        let record_size = if self.sparse { 0 } else { record_size as usize };
+
+        // pallets hold each distinct value (or array of values) once, and records
+        // store the index of theirs
+        let mut pallets: HashMap<usize, Vec<Vec<u64>>> = HashMap::new();
+        let mut pallet_data = Vec::new();
+        let mut common_data = Vec::new();
+        let mut additional_sizes = vec![0u32; self.fields.len()];
+        for (field, storage) in self.fields.iter().enumerate() {
+            match storage {
+                Storage::Pallet { .. } | Storage::PalletArray { .. } => {
+                    let mut values: Vec<Vec<u64>> = Vec::new();
+                    for row in &self.rows {
+                        let value = Db2Builder::ints(row, field).to_vec();
+                        if !values.contains(&value) {
+                            values.push(value);
+                        }
+                    }
+                    let start = pallet_data.len();
+                    for value in values.iter().flatten() {
+                        pallet_data.extend_from_slice(&(*value as u32).to_le_bytes());
+                    }
+                    additional_sizes[field] = (pallet_data.len() - start) as u32;
+                    pallets.insert(field, values);
+                }
+                Storage::Common { default } => {
+                    let start = common_data.len();
+                    for row in &self.rows {
+                        let value = Db2Builder::ints(row, field)[0] as u32;
+                        if value != *default {
+                            common_data.extend_from_slice(&row.id.to_le_bytes());
+                            common_data.extend_from_slice(&value.to_le_bytes());
+                        }
+                    }
+                    additional_sizes[field] = (common_data.len() - start) as u32;
+                }
+                _ => {}
+            }
+        }
+
+        let per_section = self.rows.len().div_ceil(self.sections.max(1)).max(1);
+        let chunks: Vec<&[Row]> = self.rows.chunks(per_section).collect();
+        let total_records = self.rows.len();
+
+        // build the body of every section first, since section headers need offsets
+        let header_len = 72 + 40 * chunks.len() + 4 * self.fields.len() + 24 * self.fields.len();
+        let data_start = header_len + pallet_data.len() + common_data.len();
+
+        let mut body: Vec<u8> = Vec::new();
+        let mut section_headers: Vec<[u32; 8]> = Vec::new();
+        let mut total_strings = 0;
+        let mut global_index = 0;
+
+        for (section, rows) in chunks.iter().enumerate() {
+            let file_offset = data_start + body.len();
+            let mut records: Vec<Vec<u8>> = Vec::new();
+            let mut strings: Vec<u8> = Vec::new();
+
+            for (i, row) in rows.iter().enumerate() {
+                let mut record = vec![0u8; record_size];
+                let mut sparse_record = Vec::new();
+                for (field, storage) in self.fields.iter().enumerate() {
+                    let at = &layout[field];
+                    match (storage, &row.values[field]) {
+                        (Storage::String, Value::Str(value)) if self.sparse => {
+                            sparse_record.extend_from_slice(value.as_bytes());
+                            sparse_record.push(0);
+                        }
+                        (Storage::String, Value::Str(value)) => {
+                            // string offsets are relative to the field, as if every
+                            // section's records came before every section's strings
+                            let key = total_strings + strings.len();
+                            let relative = key as i64
+                                + ((total_records - (global_index + i)) * record_size) as i64
+                                - (at.offset_bits / 8) as i64;
+                            strings.extend_from_slice(value.as_bytes());
+                            strings.push(0);
+                            write_bits(&mut record, at.offset_bits, 32, relative as u64);
+                        }
+                        (Storage::None { bits, .. }, Value::Int(values)) if self.sparse => {
+                            for value in values {
+                                sparse_record
+                                    .extend_from_slice(&value.to_le_bytes()[..*bits as usize / 8]);
+                            }
+                        }
+                        (Storage::None { bits, .. }, Value::Int(values)) => {
+                            for (k, value) in values.iter().enumerate() {
+                                write_bits(
+                                    &mut record,
+                                    at.offset_bits + k as u32 * bits,
+                                    *bits,
+                                    *value,
+                                );
+                            }
+                        }
+                        (Storage::Bitpacked { bits }, Value::Int(values))
+                        | (Storage::BitpackedSigned { bits }, Value::Int(values)) => {
+                            write_bits(&mut record, at.offset_bits, *bits, values[0]);
+                        }
+                        (Storage::Pallet { bits }, Value::Int(values))
+                        | (Storage::PalletArray { bits, .. }, Value::Int(values)) => {
+                            let index = pallets[&field].iter().position(|v| v == values).unwrap();
+                            write_bits(&mut record, at.offset_bits, *bits, index as u64);
+                        }
+                        (Storage::Common { .. }, _) => {}
+                        _ => panic!("field {} of row {} has the wrong value type", field, row.id),
+                    }
+                }
+                records.push(if self.sparse { sparse_record } else { record });
+            }
+
+            let encrypted = self.encrypted_sections.contains(&section);
+            let mut offset_map = Vec::new();
+            for record in &records {
+                let offset = data_start + body.len();
+                offset_map.push((offset as u32, record.len() as u16));
+                if encrypted {
+                    body.extend(std::iter::repeat_n(0, record.len()));
+                } else {
+                    body.extend_from_slice(record);
+                }
+            }
+            let offset_records_end = data_start + body.len();
+
+            let string_table_size = if self.sparse {
+                0
+            } else {
+                body.extend_from_slice(&strings);
+                strings.len()
+            };
+            total_strings += string_table_size;
+
+            let id_list_size = if self.sparse {
+                0
+            } else {
+                for row in rows.iter() {
+                    body.extend_from_slice(&row.id.to_le_bytes());
+                }
+                rows.len() * 4
+            };
+
+            let copies: &[(u32, u32)] = if section == chunks.len() - 1 {
+                &self.copies
+            } else {
+                &[]
+            };
+            for (new_id, copied_id) in copies {
+                body.extend_from_slice(&new_id.to_le_bytes());
+                body.extend_from_slice(&copied_id.to_le_bytes());
+            }
+
+            let offset_map_id_count = if self.sparse { rows.len() } else { 0 };
+            if self.sparse {
+                for (offset, len) in &offset_map {
+                    body.extend_from_slice(&offset.to_le_bytes());
+                    body.extend_from_slice(&len.to_le_bytes());
+                }
+            }
+
+            let relations: Vec<(u32, u32)> = rows
+                .iter()
+                .enumerate()
+                .filter_map(|(i, row)| row.relation.map(|r| (r, i as u32)))
+                .collect();
+            let relationship_data_size = if relations.is_empty() {
+                0
+            } else {
+                let start = body.len();
+                body.extend_from_slice(&(relations.len() as u32).to_le_bytes());
+                let min = relations.iter().map(|(r, _)| *r).min().unwrap();
+                let max = relations.iter().map(|(r, _)| *r).max().unwrap();
+                body.extend_from_slice(&min.to_le_bytes());
+                body.extend_from_slice(&max.to_le_bytes());
+                for (relation, index) in &relations {
+                    body.extend_from_slice(&relation.to_le_bytes());
+                    body.extend_from_slice(&index.to_le_bytes());
+                }
+                body.len() - start
+            };
+
+            if self.sparse {
+                for row in rows.iter() {
+                    body.extend_from_slice(&row.id.to_le_bytes());
+                }
+            }
+
+            section_headers.push([
+                file_offset as u32,
+                rows.len() as u32,
+                string_table_size as u32,
+                if self.sparse {
+                    offset_records_end as u32
+                } else {
+                    0
+                },
+                id_list_size as u32,
+                relationship_data_size as u32,
+                offset_map_id_count as u32,
+                copies.len() as u32,
+            ]);
+            global_index += rows.len();
+        }
+
+        let mut bytes = Vec::new();
+        let min_id = self.rows.iter().map(|r| r.id).min().unwrap_or(0);
+        let max_id = self.rows.iter().map(|r| r.id).max().unwrap_or(0);
+        bytes.extend_from_slice(&self.magic);
+        for value in &[
+            total_records as u32,
+            self.fields.len() as u32,
+            record_size as u32,
+            total_strings as u32,
+            TABLE_HASH,
+            0x9ABC_DEF0,
+            min_id,
+            max_id,
+            0,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(if self.sparse { 1u16 } else { 0u16 }).to_le_bytes());
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+        for value in &[
+            self.fields.len() as u32,
+            0,
+            0,
+            24 * self.fields.len() as u32,
+            common_data.len() as u32,
+            pallet_data.len() as u32,
+            chunks.len() as u32,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        for (section, header) in section_headers.iter().enumerate() {
+            let tact_key_hash: u64 = if self.encrypted_sections.contains(&section) {
+                0xDEAD_BEEF
+            } else {
+                0
+            };
+            bytes.extend_from_slice(&tact_key_hash.to_le_bytes());
+            for value in header {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        for (field, storage) in self.fields.iter().enumerate() {
+            let bits = match storage {
+                Storage::None { bits, .. } => *bits,
+                _ => 32,
+            };
+            bytes.extend_from_slice(&((32 - bits as i32) as i16).to_le_bytes());
+            bytes.extend_from_slice(&((layout[field].offset_bits / 8) as u16).to_le_bytes());
+        }
+
+        for (field, storage) in self.fields.iter().enumerate() {
+            let at = &layout[field];
+            let (storage_type, values) = match storage {
+                Storage::None { .. } | Storage::String => (0, [0, 0, 0]),
+                Storage::Bitpacked { bits } => (1, [at.offset_bits, *bits, 0]),
+                Storage::Common { default } => (2, [*default, 0, 0]),
+                Storage::Pallet { bits } => (3, [at.offset_bits, *bits, 0]),
+                Storage::PalletArray { bits, count } => (4, [at.offset_bits, *bits, *count]),
+                Storage::BitpackedSigned { bits } => (5, [at.offset_bits, *bits, 0]),
+            };
+            bytes.extend_from_slice(&(at.offset_bits as u16).to_le_bytes());
+            bytes.extend_from_slice(&(at.size_bits as u16).to_le_bytes());
+            bytes.extend_from_slice(&additional_sizes[field].to_le_bytes());
+            bytes.extend_from_slice(&(storage_type as u32).to_le_bytes());
+            for value in &values {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        assert_eq!(header_len, bytes.len());
+
+        bytes.extend_from_slice(&pallet_data);
+        bytes.extend_from_slice(&common_data);
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+}
+
+fn write_bits(record: &mut [u8], offset_bits: u32, size_bits: u32, value: u64) {
+    for bit in 0..size_bits {
+        if (value >> bit) & 1 == 1 {
+            let position = (offset_bits + bit) as usize;
+            record[position / 8] |= 1 << (position % 8);
+        }
+    }
+}
+
+/// Assembles a `DBCache.bin` for build 36753 from
+/// `(push_id, table_hash, record_id, status, data)` entries.
+pub(crate) fn db_cache(version: u32, entries: &[(i32, u32, u32, u8, Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"XFTH");
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&36753u32.to_le_bytes());
+    bytes.extend_from_slice(&[0u8; 32]);
+    for (unique_id, (push_id, table_hash, record_id, status, data)) in entries.iter().enumerate() {
+        bytes.extend_from_slice(b"XFTH");
+        if version >= 9 {
+            bytes.extend_from_slice(&1u32.to_le_bytes());
+        }
+        bytes.extend_from_slice(&push_id.to_le_bytes());
+        bytes.extend_from_slice(&(unique_id as u32).to_le_bytes());
+        bytes.extend_from_slice(&table_hash.to_le_bytes());
+        bytes.extend_from_slice(&record_id.to_le_bytes());
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&[*status, 0, 0, 0]);
+        bytes.extend_from_slice(data);
+    }
+    bytes
+}
@@ -0,0 +1,304 @@
+//! A reader for the client's hotfix cache, `DBCache.bin`.
+//!
+//! Blizzard changes client database rows in live builds by pushing hotfixes, which
+//! the client stores in its cache rather than in the DB2 files. Until the next patch
+//! ships new DB2 files, the cache is the only place the current version of those rows
+//! can be found. Apply a cache on top of a table with
+//! [Db2File::with_hotfixes](`super::Db2File::with_hotfixes`).
+//!
+//! # Layout
+//!
+//! The file starts with the `XFTH` signature, the cache version, the client build and
+//! a 32 byte verification hash. Each hotfix that follows starts with `XFTH` again,
+//! then (from version 9) the region ID, the push ID, a unique ID, the hash of the
+//! table's name, the record ID, the size of the record data, the status of the
+//! hotfix, three bytes of padding and finally the record data itself.
+//!
+//! Record data is laid out like the records of a sparse table: each field one after
+//! the other at its full size, with strings inline. Tables with a relationship map
+//! have the record's foreign key appended.
+
+use super::Db2Error;
+
+const MAGIC: &[u8; 4] = b"XFTH";
+
+/// What a hotfix does to its record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HotfixStatus {
+    /// The record is added, or replaced if it already exists.
+    Valid,
+
+    /// The record is deleted.
+    Removed,
+
+    /// The hotfix was invalidated and should be ignored.
+    Invalid,
+
+    /// The hotfix isn't public yet and should be ignored.
+    NotPublic,
+
+    /// A status this reader doesn't know about. These are ignored.
+    Unknown(u8),
+}
+
+impl From<u8> for HotfixStatus {
+    fn from(status: u8) -> Self {
+        match status {
+            1 => HotfixStatus::Valid,
+            2 => HotfixStatus::Removed,
+            3 => HotfixStatus::Invalid,
+            4 => HotfixStatus::NotPublic,
+            other => HotfixStatus::Unknown(other),
+        }
+    }
+}
+
+/// A single hotfixed record.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HotfixEntry {
+    /// The ID of the push the hotfix was delivered in. Later pushes win.
+    pub push_id: i32,
+
+    /// The unique ID of the hotfix.
+    pub unique_id: u32,
+
+    /// The hash of the name of the table the hotfix applies to. This matches
+    /// [Db2File::table_hash](`super::Db2File::table_hash`).
+    pub table_hash: u32,
+
+    /// The ID of the record the hotfix applies to.
+    pub record_id: u32,
+
+    /// What the hotfix does to the record.
+    pub status: HotfixStatus,
+
+    /// The new data of the record. Empty for removals.
+    pub data: Vec<u8>,
+}
+
+/// A parsed `DBCache.bin` hotfix cache.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DbCache {
+    /// The version of the cache format.
+    pub version: u32,
+
+    /// The client build the cache was written by.
+    pub build: u32,
+
+    /// Every hotfix in the cache, in the order they appear in the file.
+    pub entries: Vec<HotfixEntry>,
+}
+
+impl DbCache {
+    /// Parses the raw bytes of a `DBCache.bin` file.
+    ///
+    /// Versions `8` and `9` of the format are supported.
+    pub fn parse(bytes: &[u8]) -> Result<DbCache, Db2Error> {
+        let mut cursor = Cursor { bytes, offset: 0 };
+
+        cursor.magic()?;
+        let version = cursor.u32()?;
+        if version != 8 && version != 9 {
+            return Err(Db2Error::UnsupportedHotfixVersion(version));
+        }
+        let build = cursor.u32()?;
+        cursor.take(32)?;
+
+        let mut entries = Vec::new();
+        while cursor.offset < bytes.len() {
+            cursor.magic()?;
+            if version >= 9 {
+                // region ID
+                cursor.u32()?;
+            }
+            let push_id = cursor.u32()? as i32;
+            let unique_id = cursor.u32()?;
+            let table_hash = cursor.u32()?;
+            let record_id = cursor.u32()?;
+            let data_size = cursor.u32()? as usize;
+            let status = HotfixStatus::from(cursor.take(4)?[0]);
+            let data = cursor.take(data_size)?.to_vec();
+
+            entries.push(HotfixEntry {
+                push_id,
+                unique_id,
+                table_hash,
+                record_id,
+                status,
+                data,
+            });
+        }
+
+        Ok(DbCache {
+            version,
+            build,
+            entries,
+        })
+    }
+
+    /// Iterates over the hotfixes for a single table.
+    pub fn entries_for(&self, table_hash: u32) -> impl Iterator<Item = &HotfixEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.table_hash == table_hash)
+    }
+}
+
+struct Cursor<'b> {
+    bytes: &'b [u8],
+    offset: usize,
+}
+
+impl<'b> Cursor<'b> {
+    fn take(&mut self, len: usize) -> Result<&'b [u8], Db2Error> {
+        let taken = super::slice(self.bytes, self.offset, len)?;
+        self.offset += len;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, Db2Error> {
+        let value = super::read_u32(self.bytes, self.offset)?;
+        self.offset += 4;
+        Ok(value)
+    }
+
+    fn magic(&mut self) -> Result<(), Db2Error> {
+        let magic = self.take(4)?;
+        if magic != MAGIC {
+            let mut found = [0u8; 4];
+            found.copy_from_slice(magic);
+            return Err(Db2Error::UnsupportedFormat(found));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wow::db2::builder::{db_cache, Db2Builder, Storage, Value, TABLE_HASH};
+    use crate::wow::db2::Db2File;
+
+    #[test]
+    fn parses_entries() {
+        let bytes = db_cache(
+            9,
+            &[
+                (10, TABLE_HASH, 5, 1, vec![1, 2, 3]),
+                (11, 0xFFFF, 6, 2, vec![]),
+            ],
+        );
+        let cache = DbCache::parse(&bytes).unwrap();
+        assert_eq!(9, cache.version);
+        assert_eq!(36753, cache.build);
+        assert_eq!(2, cache.entries.len());
+        assert_eq!(HotfixStatus::Valid, cache.entries[0].status);
+        assert_eq!(vec![1, 2, 3], cache.entries[0].data);
+        assert_eq!(HotfixStatus::Removed, cache.entries[1].status);
+        assert_eq!(1, cache.entries_for(TABLE_HASH).count());
+
+        let v8 = DbCache::parse(&db_cache(8, &[(1, TABLE_HASH, 5, 1, vec![7])])).unwrap();
+        assert_eq!(5, v8.entries[0].record_id);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        assert!(matches!(
+            DbCache::parse(&db_cache(7, &[])),
+            Err(Db2Error::UnsupportedHotfixVersion(7))
+        ));
+    }
+
+    #[test]
+    fn applies_additions_replacements_and_removals() {
+        let builder = Db2Builder::new()
+            .field(Storage::String)
+            .field(Storage::Bitpacked { bits: 4 })
+            .field(Storage::Pallet { bits: 1 })
+            .related_row(
+                1,
+                100,
+                vec![
+                    Value::Str("one".into()),
+                    Value::Int(vec![1]),
+                    Value::Int(vec![10]),
+                ],
+            )
+            .related_row(
+                2,
+                200,
+                vec![
+                    Value::Str("two".into()),
+                    Value::Int(vec![2]),
+                    Value::Int(vec![20]),
+                ],
+            )
+            .related_row(
+                3,
+                300,
+                vec![
+                    Value::Str("three".into()),
+                    Value::Int(vec![3]),
+                    Value::Int(vec![10]),
+                ],
+            );
+        let replaced = builder.hotfix_record(
+            &[
+                Value::Str("two, hotfixed".into()),
+                Value::Int(vec![12]),
+                Value::Int(vec![2000]),
+            ],
+            Some(201),
+        );
+        let stale = builder.hotfix_record(
+            &[
+                Value::Str("stale".into()),
+                Value::Int(vec![0]),
+                Value::Int(vec![0]),
+            ],
+            Some(0),
+        );
+        let added = builder.hotfix_record(
+            &[
+                Value::Str("four".into()),
+                Value::Int(vec![4]),
+                Value::Int(vec![40]),
+            ],
+            Some(400),
+        );
+        let bytes = builder.build();
+        let cache = DbCache::parse(&db_cache(
+            9,
+            &[
+                (12, TABLE_HASH, 2, 1, replaced),
+                (11, TABLE_HASH, 2, 1, stale),
+                (10, TABLE_HASH, 4, 1, added),
+                (10, TABLE_HASH, 3, 2, vec![]),
+                (10, TABLE_HASH, 1, 3, vec![]),
+            ],
+        ))
+        .unwrap();
+
+        let file = Db2File::parse(&bytes)
+            .unwrap()
+            .with_inline_strings(&[0])
+            .with_hotfixes(&cache);
+        assert_eq!(3, file.len());
+        assert!(file.record(3).is_none());
+
+        let untouched = file.record(1).unwrap();
+        assert_eq!("one", untouched.string(0).unwrap());
+        assert_eq!(Some(100), untouched.relation());
+
+        let replaced = file.record(2).unwrap();
+        assert_eq!("two, hotfixed", replaced.string(0).unwrap());
+        assert_eq!(12, replaced.u32(1).unwrap());
+        assert_eq!(2000, replaced.u32(2).unwrap());
+        assert_eq!(Some(201), replaced.relation());
+
+        let added = file.record(4).unwrap();
+        assert_eq!("four", added.string(0).unwrap());
+        assert_eq!(40, added.u32(2).unwrap());
+        assert_eq!(Some(400), added.relation());
+    }
+}
@@ -0,0 +1,1319 @@
+//! A reader for the binary DB2 files which World of Warcraft ships its client
+//! database tables in.
+//!
+//! Only the `WDC3` and `WDC4` container formats are supported, which covers every
+//! retail client since Battle for Azeroth 8.1.
+//!
+//! DB2 files don't describe their own columns. A file only stores how many bits each
+//! field takes up and how the bits are packed, so it is up to the caller to know that,
+//! for example, field `1` of `ItemBonus` is its `Type`. The reader hands out raw values
+//! through [`Db2Record`], and the tables in [data_tables](`crate::wow::data_tables`)
+//! map those onto their rows.
+//!
+//! # Layout
+//!
+//! A file is made up of:
+//!
+//! 1. A header, followed by one header per section.
+//! 2. The structure and storage info of every field, describing where in a record
+//!    the field lives and how it is packed.
+//! 3. Pallet data and common data, which store the values of fields that are packed
+//!    as an index into a list of values, or are mostly the same default value.
+//! 4. The sections themselves. Each section holds either fixed-size records and a
+//!    string table, or (for "sparse" tables) variable-size records with inline strings.
+//!    A section is followed by its list of record IDs, a copy table of rows which are
+//!    identical to another row except for their ID, an offset map locating the records
+//!    of a sparse table, and a relationship map holding each record's foreign key.
+//!
+//! Sections which are encrypted and whose key isn't known to the client are shipped
+//! zeroed out. Those sections are skipped.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+#[cfg(test)]
+pub(crate) mod builder;
+pub mod hotfix;
+
+pub use hotfix::{DbCache, HotfixEntry, HotfixStatus};
+
+const HEADER_SIZE: usize = 72;
+const SECTION_HEADER_SIZE: usize = 40;
+const FIELD_STRUCTURE_SIZE: usize = 4;
+const FIELD_STORAGE_INFO_SIZE: usize = 24;
+const OFFSET_MAP_ENTRY_SIZE: usize = 6;
+
+/// Records are variable-size and located through the offset map.
+const FLAG_SPARSE: u16 = 0x1;
+
+/// An error encountered while reading a DB2 file.
+#[derive(Debug)]
+pub enum Db2Error {
+    /// The file doesn't start with a supported signature such as `WDC3`.
+    UnsupportedFormat([u8; 4]),
+
+    /// The file ended before a structure at `offset` which is `len` bytes long.
+    Truncated {
+        /// Where the structure starts in the file.
+        offset: usize,
+
+        /// The length of the structure.
+        len: usize,
+    },
+
+    /// A field index was requested which the file doesn't have.
+    NoSuchField(usize),
+
+    /// A field uses a storage type this reader doesn't know about.
+    UnsupportedStorage {
+        /// The index of the field.
+        field: usize,
+
+        /// The storage type ID from the file.
+        storage_type: u32,
+    },
+
+    /// A bitpacked field is wider than the 64 bits a value can hold.
+    InvalidFieldSize {
+        /// The index of the field.
+        field: usize,
+
+        /// The number of bits the field claims to take up.
+        size_bits: u32,
+    },
+
+    /// A string field didn't point at a valid UTF-8 string.
+    InvalidString {
+        /// The index of the field.
+        field: usize,
+    },
+
+    /// A hotfix cache has a version this reader doesn't know about.
+    UnsupportedHotfixVersion(u32),
+}
+
+impl fmt::Display for Db2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Db2Error::UnsupportedFormat(magic) => write!(
+                f,
+                "unsupported DB2 format {:?}",
+                String::from_utf8_lossy(magic)
+            ),
+            Db2Error::Truncated { offset, len } => write!(
+                f,
+                "DB2 file is truncated: expected {} bytes at offset {}",
+                len, offset
+            ),
+            Db2Error::NoSuchField(field) => write!(f, "DB2 file has no field {}", field),
+            Db2Error::UnsupportedStorage {
+                field,
+                storage_type,
+            } => write!(
+                f,
+                "DB2 field {} uses unsupported storage type {}",
+                field, storage_type
+            ),
+            Db2Error::InvalidFieldSize { field, size_bits } => write!(
+                f,
+                "DB2 field {} is {} bits wide, but at most 64 are supported",
+                field, size_bits
+            ),
+            Db2Error::InvalidString { field } => {
+                write!(f, "DB2 field {} does not point at a valid string", field)
+            }
+            Db2Error::UnsupportedHotfixVersion(version) => {
+                write!(f, "unsupported hotfix cache version {}", version)
+            }
+        }
+    }
+}
+
+impl Error for Db2Error {}
+
+/// The container format of a DB2 file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Db2Version {
+    /// `WDC3`, used from 8.1 through 10.0.
+    Wdc3,
+
+    /// `WDC4`, used from 10.1. This only adds a list of encrypted record IDs
+    /// ahead of the sections.
+    Wdc4,
+}
+
+/// How the value of a field is stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldStorage {
+    /// Stored as is, byte aligned, in the record.
+    None,
+
+    /// Stored in the record as an unsigned integer of `size_bits` bits.
+    Bitpacked {
+        /// The number of bits the value takes up.
+        size_bits: u32,
+    },
+
+    /// Not stored in the record. Records which don't use `default` have their value
+    /// listed in the common data.
+    CommonData {
+        /// The value of every record not listed in the common data.
+        default: u32,
+    },
+
+    /// Stored in the record as a `size_bits` bit index into the pallet data.
+    BitpackedIndexed {
+        /// The number of bits the index takes up.
+        size_bits: u32,
+    },
+
+    /// Stored in the record as a `size_bits` bit index into the pallet data, where
+    /// each index refers to `array_count` consecutive values.
+    BitpackedIndexedArray {
+        /// The number of bits the index takes up.
+        size_bits: u32,
+
+        /// The number of values in the array.
+        array_count: u32,
+    },
+
+    /// Stored in the record as a signed integer of `size_bits` bits.
+    BitpackedSigned {
+        /// The number of bits the value takes up.
+        size_bits: u32,
+    },
+}
+
+/// Where and how a single field is stored within the records of a DB2 file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Db2Field {
+    /// The offset of the field from the start of a record, in bits.
+    pub offset_bits: u32,
+
+    /// The number of bits the field takes up in a record.
+    pub size_bits: u32,
+
+    /// The size of a single value of the field, in bits. For array fields stored
+    /// as [FieldStorage::None](`FieldStorage::None`), the field holds
+    /// `size_bits / element_bits` values.
+    pub element_bits: u32,
+
+    /// How the field is stored.
+    pub storage: FieldStorage,
+
+    pallet_offset: usize,
+    common_values: HashMap<u32, u32>,
+}
+
+impl Db2Field {
+    /// The number of values the field holds.
+    pub fn array_len(&self) -> u32 {
+        match self.storage {
+            FieldStorage::None => (self.size_bits / self.element_bits.max(1)).max(1),
+            FieldStorage::BitpackedIndexedArray { array_count, .. } => array_count,
+            _ => 1,
+        }
+    }
+}
+
+struct SectionHeader {
+    tact_key_hash: u64,
+    file_offset: usize,
+    record_count: usize,
+    string_table_size: usize,
+    offset_records_end: usize,
+    id_list_size: usize,
+    relationship_data_size: usize,
+    offset_map_id_count: usize,
+    copy_table_count: usize,
+}
+
+/// How the fields of a record are laid out.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RecordKind {
+    /// At fixed (bit) offsets, with strings in the string table.
+    Packed,
+
+    /// One after the other at their full size, with strings inline.
+    Sparse,
+
+    /// Laid out like [RecordKind::Sparse](`RecordKind::Sparse`), followed by the
+    /// record's foreign key if the table has a relationship map.
+    Hotfix,
+}
+
+#[derive(Clone)]
+struct RawRecord<'b> {
+    id: u32,
+    data: &'b [u8],
+    kind: RecordKind,
+    relation: Option<u32>,
+    // the position of the record relative to the start of the combined string tables
+    string_base: i64,
+}
+
+/// A raw value read from a record, along with the width it should be sign
+/// extended from when read as a signed integer.
+#[derive(Clone, Copy)]
+struct RawValue {
+    bits: u64,
+    sign_bits: u32,
+}
+
+impl RawValue {
+    fn signed(&self) -> i64 {
+        if self.sign_bits == 0 || self.sign_bits >= 64 {
+            self.bits as i64
+        } else {
+            let shift = 64 - self.sign_bits;
+            ((self.bits << shift) as i64) >> shift
+        }
+    }
+}
+
+/// A parsed `WDC3` or `WDC4` DB2 file.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use etherealpost::wow::db2::Db2File;
+///
+/// let bytes = std::fs::read("dbfilesclient/itembonus.db2").unwrap();
+/// let file = Db2File::parse(&bytes).unwrap();
+/// for record in file.records() {
+///     println!("{} -> {:?}", record.id(), record.i32_array(0).unwrap());
+/// }
+/// ```
+pub struct Db2File<'b> {
+    version: Db2Version,
+    table_hash: u32,
+    layout_hash: u32,
+    locale: u32,
+    fields: Vec<Db2Field>,
+    pallet_data: &'b [u8],
+    strings: Vec<u8>,
+    inline_string_fields: Vec<usize>,
+    has_relations: bool,
+    records: Vec<RawRecord<'b>>,
+    index: HashMap<u32, usize>,
+}
+
+impl<'b> Db2File<'b> {
+    /// Parses the raw bytes of a DB2 file.
+    pub fn parse(bytes: &'b [u8]) -> Result<Db2File<'b>, Db2Error> {
+        let magic = slice(bytes, 0, 4)?;
+        let version = match magic {
+            b"WDC3" => Db2Version::Wdc3,
+            b"WDC4" => Db2Version::Wdc4,
+            other => {
+                let mut magic = [0u8; 4];
+                magic.copy_from_slice(other);
+                return Err(Db2Error::UnsupportedFormat(magic));
+            }
+        };
+
+        let total_record_count = read_u32(bytes, 4)? as usize;
+        let field_count = read_u32(bytes, 8)? as usize;
+        let record_size = read_u32(bytes, 12)? as usize;
+        let table_hash = read_u32(bytes, 20)?;
+        let layout_hash = read_u32(bytes, 24)?;
+        let locale = read_u32(bytes, 36)?;
+        let flags = read_u16(bytes, 40)?;
+        let id_index = read_u16(bytes, 42)? as usize;
+        let field_storage_info_size = read_u32(bytes, 56)? as usize;
+        let common_data_size = read_u32(bytes, 60)? as usize;
+        let pallet_data_size = read_u32(bytes, 64)? as usize;
+        let section_count = read_u32(bytes, 68)? as usize;
+
+        // the counts come straight from the header, so make sure the file is large
+        // enough to hold what they describe before allocating for them
+        slice(
+            bytes,
+            HEADER_SIZE,
+            section_count.saturating_mul(SECTION_HEADER_SIZE),
+        )?;
+
+        let mut offset = HEADER_SIZE;
+        let mut sections = Vec::with_capacity(section_count);
+        for _ in 0..section_count {
+            sections.push(SectionHeader {
+                tact_key_hash: read_u64(bytes, offset)?,
+                file_offset: read_u32(bytes, offset + 8)? as usize,
+                record_count: read_u32(bytes, offset + 12)? as usize,
+                string_table_size: read_u32(bytes, offset + 16)? as usize,
+                offset_records_end: read_u32(bytes, offset + 20)? as usize,
+                id_list_size: read_u32(bytes, offset + 24)? as usize,
+                relationship_data_size: read_u32(bytes, offset + 28)? as usize,
+                offset_map_id_count: read_u32(bytes, offset + 32)? as usize,
+                copy_table_count: read_u32(bytes, offset + 36)? as usize,
+            });
+            offset += SECTION_HEADER_SIZE;
+        }
+
+        // the field structure only holds the size of each field's values, the
+        // storage info which follows it holds everything else
+        slice(
+            bytes,
+            offset,
+            field_count.saturating_mul(FIELD_STRUCTURE_SIZE),
+        )?;
+        let mut element_bits = Vec::with_capacity(field_count);
+        for _ in 0..field_count {
+            let size = read_u16(bytes, offset)? as i16;
+            element_bits.push((32 - size as i32) as u32);
+            offset += FIELD_STRUCTURE_SIZE;
+        }
+
+        let mut fields = Vec::with_capacity(field_count);
+        let mut pallet_offset = 0;
+        let mut common_offset = 0;
+        let common_start = offset + field_storage_info_size + pallet_data_size;
+        for field in 0..field_storage_info_size / FIELD_STORAGE_INFO_SIZE {
+            let offset_bits = read_u16(bytes, offset)? as u32;
+            let size_bits = read_u16(bytes, offset + 2)? as u32;
+            let additional_data_size = read_u32(bytes, offset + 4)? as usize;
+            let storage_type = read_u32(bytes, offset + 8)?;
+            let value1 = read_u32(bytes, offset + 12)?;
+            let value2 = read_u32(bytes, offset + 16)?;
+            let value3 = read_u32(bytes, offset + 20)?;
+            offset += FIELD_STORAGE_INFO_SIZE;
+
+            let storage = match storage_type {
+                0 => FieldStorage::None,
+                1 => FieldStorage::Bitpacked { size_bits: value2 },
+                2 => FieldStorage::CommonData { default: value1 },
+                3 => FieldStorage::BitpackedIndexed { size_bits: value2 },
+                4 => FieldStorage::BitpackedIndexedArray {
+                    size_bits: value2,
+                    array_count: value3,
+                },
+                5 => FieldStorage::BitpackedSigned { size_bits: value2 },
+                _ => {
+                    return Err(Db2Error::UnsupportedStorage {
+                        field,
+                        storage_type,
+                    })
+                }
+            };
+
+            // bitpacked values are read into a u64, so anything wider can't be valid
+            match storage {
+                FieldStorage::Bitpacked { size_bits }
+                | FieldStorage::BitpackedIndexed { size_bits }
+                | FieldStorage::BitpackedIndexedArray { size_bits, .. }
+                | FieldStorage::BitpackedSigned { size_bits }
+                    if size_bits > 64 =>
+                {
+                    return Err(Db2Error::InvalidFieldSize { field, size_bits });
+                }
+                _ => {}
+            }
+
+            let mut common_values = HashMap::new();
+            let field_pallet_offset = pallet_offset;
+            match storage {
+                FieldStorage::CommonData { .. } => {
+                    let start = common_start + common_offset;
+                    for entry in 0..additional_data_size / 8 {
+                        let id = read_u32(bytes, start + entry * 8)?;
+                        let value = read_u32(bytes, start + entry * 8 + 4)?;
+                        common_values.insert(id, value);
+                    }
+                    common_offset += additional_data_size;
+                }
+                FieldStorage::BitpackedIndexed { .. }
+                | FieldStorage::BitpackedIndexedArray { .. } => {
+                    pallet_offset += additional_data_size;
+                }
+                _ => {}
+            }
+
+            fields.push(Db2Field {
+                offset_bits,
+                size_bits,
+                element_bits: *element_bits.get(field).unwrap_or(&32),
+                storage,
+                pallet_offset: field_pallet_offset,
+                common_values,
+            });
+        }
+
+        let pallet_data = slice(bytes, offset, pallet_data_size)?;
+        slice(bytes, common_start, common_data_size)?;
+
+        // WDC4 lists the IDs of encrypted records here. The sections are located by
+        // their absolute offsets, so there is no need to read past it.
+
+        let mut file = Db2File {
+            version,
+            table_hash,
+            layout_hash,
+            locale,
+            fields,
+            pallet_data,
+            strings: Vec::new(),
+            inline_string_fields: Vec::new(),
+            has_relations: false,
+            records: Vec::new(),
+            index: HashMap::new(),
+        };
+
+        let sparse = flags & FLAG_SPARSE != 0;
+        let total_records_size = (total_record_count * record_size) as i64;
+        let mut global_index = 0;
+        let mut copies: Vec<(u32, u32)> = Vec::new();
+
+        for section in &sections {
+            let mut offset = section.file_offset;
+            let records_offset = offset;
+            if sparse {
+                offset = section.offset_records_end;
+            } else {
+                offset += section.record_count * record_size;
+                file.strings
+                    .extend_from_slice(slice(bytes, offset, section.string_table_size)?);
+                offset += section.string_table_size;
+            }
+
+            let ids = read_u32s(bytes, offset, section.id_list_size / 4)?;
+            offset += section.id_list_size;
+
+            let copy_table = read_u32s(bytes, offset, section.copy_table_count * 2)?;
+            copies.extend(copy_table.chunks(2).map(|pair| (pair[0], pair[1])));
+            offset += section.copy_table_count * 8;
+
+            let offset_map_offset = offset;
+            offset += section.offset_map_id_count * OFFSET_MAP_ENTRY_SIZE;
+
+            let mut relations: HashMap<usize, u32> = HashMap::new();
+            if section.relationship_data_size > 0 {
+                file.has_relations = true;
+                let num_entries = read_u32(bytes, offset)? as usize;
+                let entries = read_u32s(bytes, offset + 12, num_entries * 2)?;
+                for entry in entries.chunks(2) {
+                    relations.insert(entry[1] as usize, entry[0]);
+                }
+                offset += section.relationship_data_size;
+            }
+
+            let offset_map_ids = read_u32s(bytes, offset, section.offset_map_id_count)?;
+
+            if section.tact_key_hash != 0 && is_zeroed(bytes, section, records_offset, record_size)
+            {
+                global_index += section.record_count;
+                continue;
+            }
+
+            if sparse {
+                for i in 0..section.offset_map_id_count {
+                    let entry = offset_map_offset + i * OFFSET_MAP_ENTRY_SIZE;
+                    let record_offset = read_u32(bytes, entry)? as usize;
+                    let record_len = read_u16(bytes, entry + 4)? as usize;
+                    if record_len == 0 {
+                        continue;
+                    }
+
+                    let id = ids
+                        .get(i)
+                        .or_else(|| offset_map_ids.get(i))
+                        .copied()
+                        .unwrap_or(0);
+                    file.push(RawRecord {
+                        id,
+                        data: slice(bytes, record_offset, record_len)?,
+                        kind: RecordKind::Sparse,
+                        relation: relations.get(&i).copied(),
+                        string_base: 0,
+                    });
+                }
+            } else {
+                for i in 0..section.record_count {
+                    let mut record = RawRecord {
+                        id: 0,
+                        data: slice(bytes, records_offset + i * record_size, record_size)?,
+                        kind: RecordKind::Packed,
+                        relation: relations.get(&i).copied(),
+                        string_base: ((global_index + i) * record_size) as i64 - total_records_size,
+                    };
+                    record.id = match ids.get(i) {
+                        Some(id) => *id,
+                        None => file.values(&record, id_index)?[0].bits as u32,
+                    };
+                    file.push(record);
+                }
+            }
+
+            global_index += section.record_count;
+        }
+
+        for (new_id, copied_id) in copies {
+            if let Some(index) = file.index.get(&copied_id) {
+                let mut record = file.records[*index].clone();
+                record.id = new_id;
+                file.push(record);
+            }
+        }
+
+        Ok(file)
+    }
+
+    /// Marks which fields of the table hold strings.
+    ///
+    /// The records of sparse tables, and records added by hotfixes, store their
+    /// strings inline rather than in a string table. Since strings have no fixed size,
+    /// the reader has to know which fields are strings in order to find the fields
+    /// which come after them. Records of other tables don't need this.
+    pub fn with_inline_strings(mut self, fields: &[usize]) -> Self {
+        self.inline_string_fields = fields.to_vec();
+        self
+    }
+
+    /// Applies the hotfixes for this table from a [`DbCache`] on top of the file's records.
+    ///
+    /// Valid hotfixes add a record, or replace the record with the same ID. Removal
+    /// hotfixes delete the record. When a record was hotfixed more than once, the
+    /// hotfix with the highest push ID wins.
+    ///
+    /// Records added by a hotfix store their strings inline, so string fields must be
+    /// marked with [with_inline_strings](`Self::with_inline_strings`) for any fields
+    /// after them to be read correctly.
+    pub fn with_hotfixes<'h>(self, cache: &'h DbCache) -> Db2File<'h>
+    where
+        'b: 'h,
+    {
+        let mut file: Db2File<'h> = self;
+
+        let mut entries: Vec<&HotfixEntry> = cache.entries_for(file.table_hash).collect();
+        entries.sort_by_key(|entry| entry.push_id);
+
+        let mut latest: HashMap<u32, &HotfixEntry> = HashMap::new();
+        for entry in entries {
+            match entry.status {
+                HotfixStatus::Valid | HotfixStatus::Removed => {
+                    latest.insert(entry.record_id, entry);
+                }
+                _ => {}
+            }
+        }
+
+        let updated = latest.len();
+        let mut removed = HashSet::new();
+        for (id, entry) in latest {
+            if entry.status == HotfixStatus::Removed {
+                removed.insert(id);
+                continue;
+            }
+
+            let record = RawRecord {
+                id,
+                data: &entry.data,
+                kind: RecordKind::Hotfix,
+                relation: None,
+                string_base: 0,
+            };
+            match file.index.get(&id) {
+                Some(index) => file.records[*index] = record,
+                None => file.push(record),
+            }
+        }
+
+        if !removed.is_empty() {
+            file.records.retain(|record| !removed.contains(&record.id));
+            file.index = file
+                .records
+                .iter()
+                .enumerate()
+                .map(|(index, record)| (record.id, index))
+                .collect();
+        }
+
+        tracing::debug!(
+            table_hash = file.table_hash,
+            updated = updated - removed.len(),
+            removed = removed.len(),
+            "applied hotfixes"
+        );
+
+        file
+    }
+
+    fn push(&mut self, record: RawRecord<'b>) {
+        self.index.insert(record.id, self.records.len());
+        self.records.push(record);
+    }
+
+    /// The container format of the file.
+    pub fn version(&self) -> Db2Version {
+        self.version
+    }
+
+    /// The hash of the table's name, which identifies which table the file holds.
+    pub fn table_hash(&self) -> u32 {
+        self.table_hash
+    }
+
+    /// The hash of the table's layout, which changes whenever its fields do.
+    pub fn layout_hash(&self) -> u32 {
+        self.layout_hash
+    }
+
+    /// The locale the file's strings are in.
+    pub fn locale(&self) -> u32 {
+        self.locale
+    }
+
+    /// The fields of every record in the file.
+    pub fn fields(&self) -> &[Db2Field] {
+        &self.fields
+    }
+
+    /// The number of records in the file, including copied records.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Whether the file has no records.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Iterates over every record in the file.
+    pub fn records(&self) -> impl Iterator<Item = Db2Record<'_, 'b>> {
+        self.records
+            .iter()
+            .map(move |record| Db2Record { file: self, record })
+    }
+
+    /// Looks up a record by its ID.
+    pub fn record(&self, id: u32) -> Option<Db2Record<'_, 'b>> {
+        self.index.get(&id).map(|index| Db2Record {
+            file: self,
+            record: &self.records[*index],
+        })
+    }
+
+    fn field(&self, field: usize) -> Result<&Db2Field, Db2Error> {
+        self.fields.get(field).ok_or(Db2Error::NoSuchField(field))
+    }
+
+    /// Finds the byte offset of a field within a sparse or hotfix record by walking
+    /// the fields which come before it.
+    fn sparse_offset(&self, record: &RawRecord, field: usize) -> Result<usize, Db2Error> {
+        let mut offset = 0;
+        for index in 0..field {
+            if self.inline_string_fields.contains(&index) {
+                let len = record.data[offset.min(record.data.len())..]
+                    .iter()
+                    .position(|b| *b == 0)
+                    .ok_or(Db2Error::InvalidString { field: index })?;
+                offset += len + 1;
+            } else {
+                let info = self.field(index)?;
+                offset += (info.element_bits / 8 * info.array_len()) as usize;
+            }
+        }
+        Ok(offset)
+    }
+
+    fn values(&self, record: &RawRecord, field: usize) -> Result<Vec<RawValue>, Db2Error> {
+        let info = self.field(field)?;
+        let element_bits = info.element_bits.clamp(1, 64);
+        let count = info.array_len();
+
+        if record.kind != RecordKind::Packed {
+            let offset = self.sparse_offset(record, field)? * 8;
+            return (0..count)
+                .map(|i| {
+                    let bits = read_bits(
+                        record.data,
+                        offset + (i * element_bits) as usize,
+                        element_bits,
+                    )?;
+                    Ok(RawValue {
+                        bits,
+                        sign_bits: element_bits,
+                    })
+                })
+                .collect();
+        }
+
+        let offset = info.offset_bits as usize;
+        match info.storage {
+            FieldStorage::None => (0..count)
+                .map(|i| {
+                    let bits = read_bits(
+                        record.data,
+                        offset + (i * element_bits) as usize,
+                        element_bits,
+                    )?;
+                    Ok(RawValue {
+                        bits,
+                        sign_bits: element_bits,
+                    })
+                })
+                .collect(),
+            FieldStorage::Bitpacked { size_bits } => Ok(vec![RawValue {
+                bits: read_bits(record.data, offset, size_bits)?,
+                sign_bits: 64,
+            }]),
+            FieldStorage::BitpackedSigned { size_bits } => Ok(vec![RawValue {
+                bits: read_bits(record.data, offset, size_bits)?,
+                sign_bits: size_bits,
+            }]),
+            FieldStorage::CommonData { default } => Ok(vec![RawValue {
+                bits: *info.common_values.get(&record.id).unwrap_or(&default) as u64,
+                sign_bits: 32,
+            }]),
+            FieldStorage::BitpackedIndexed { size_bits } => {
+                let index = read_bits(record.data, offset, size_bits)? as usize;
+                let start = pallet_start(info.pallet_offset, index, 1)?;
+                let value = read_u32(self.pallet_data, start)?;
+                Ok(vec![RawValue {
+                    bits: value as u64,
+                    sign_bits: 32,
+                }])
+            }
+            FieldStorage::BitpackedIndexedArray {
+                size_bits,
+                array_count,
+            } => {
+                let index = read_bits(record.data, offset, size_bits)? as usize;
+                let start = pallet_start(info.pallet_offset, index, array_count as usize)?;
+                read_u32s(self.pallet_data, start, array_count as usize).map(|values| {
+                    values
+                        .into_iter()
+                        .map(|value| RawValue {
+                            bits: value as u64,
+                            sign_bits: 32,
+                        })
+                        .collect()
+                })
+            }
+        }
+    }
+
+    fn string<'s>(&'s self, record: &'s RawRecord<'b>, field: usize) -> Result<&'s str, Db2Error> {
+        let (bytes, start) = if record.kind != RecordKind::Packed {
+            (record.data, self.sparse_offset(record, field)?)
+        } else {
+            let value = self.values(record, field)?[0].bits as u32;
+            if value == 0 {
+                return Ok("");
+            }
+            let byte_offset = self.field(field)?.offset_bits as i64 / 8;
+            let position = record.string_base + byte_offset + value as i64;
+            if position < 0 {
+                return Err(Db2Error::InvalidString { field });
+            }
+            (self.strings.as_slice(), position as usize)
+        };
+
+        let rest = bytes
+            .get(start..)
+            .ok_or(Db2Error::InvalidString { field })?;
+        let len = rest
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or(Db2Error::InvalidString { field })?;
+        std::str::from_utf8(&rest[..len]).map_err(|_| Db2Error::InvalidString { field })
+    }
+}
+
+/// A single record of a [`Db2File`].
+///
+/// Fields are referred to by their index. Values are converted to the requested
+/// type as is, so reading a field with the wrong type won't fail but will
+/// produce a meaningless value.
+pub struct Db2Record<'f, 'b> {
+    file: &'f Db2File<'b>,
+    record: &'f RawRecord<'b>,
+}
+
+impl<'f, 'b> Db2Record<'f, 'b> {
+    /// The ID of the record.
+    pub fn id(&self) -> u32 {
+        self.record.id
+    }
+
+    /// The foreign key of the record from the relationship map, if it has one.
+    ///
+    /// Tables which belong to a parent row in another table, such as a curve point
+    /// belonging to its curve, store the parent's ID here instead of in a field.
+    pub fn relation(&self) -> Option<u32> {
+        if self.record.kind == RecordKind::Hotfix {
+            if !self.file.has_relations {
+                return None;
+            }
+            let offset = self
+                .file
+                .sparse_offset(self.record, self.file.fields.len())
+                .ok()?;
+            return read_u32(self.record.data, offset).ok();
+        }
+        self.record.relation
+    }
+
+    fn first(&self, field: usize) -> Result<RawValue, Db2Error> {
+        Ok(self.file.values(self.record, field)?[0])
+    }
+
+    /// Reads the first value of a field as an unsigned integer.
+    pub fn u32(&self, field: usize) -> Result<u32, Db2Error> {
+        Ok(self.first(field)?.bits as u32)
+    }
+
+    /// Reads the first value of a field as a signed integer.
+    pub fn i32(&self, field: usize) -> Result<i32, Db2Error> {
+        Ok(self.first(field)?.signed() as i32)
+    }
+
+    /// Reads the first value of a field as a 64 bit unsigned integer.
+    pub fn u64(&self, field: usize) -> Result<u64, Db2Error> {
+        Ok(self.first(field)?.bits)
+    }
+
+    /// Reads the first value of a field as a 64 bit signed integer.
+    pub fn i64(&self, field: usize) -> Result<i64, Db2Error> {
+        Ok(self.first(field)?.signed())
+    }
+
+    /// Reads the first value of a field as a float.
+    pub fn f32(&self, field: usize) -> Result<f32, Db2Error> {
+        Ok(f32::from_bits(self.first(field)?.bits as u32))
+    }
+
+    /// Reads every value of an array field as unsigned integers.
+    pub fn u32_array(&self, field: usize) -> Result<Vec<u32>, Db2Error> {
+        let values = self.file.values(self.record, field)?;
+        Ok(values.iter().map(|v| v.bits as u32).collect())
+    }
+
+    /// Reads every value of an array field as signed integers.
+    pub fn i32_array(&self, field: usize) -> Result<Vec<i32>, Db2Error> {
+        let values = self.file.values(self.record, field)?;
+        Ok(values.iter().map(|v| v.signed() as i32).collect())
+    }
+
+    /// Reads every value of an array field as floats.
+    pub fn f32_array(&self, field: usize) -> Result<Vec<f32>, Db2Error> {
+        let values = self.file.values(self.record, field)?;
+        Ok(values
+            .iter()
+            .map(|v| f32::from_bits(v.bits as u32))
+            .collect())
+    }
+
+    /// Reads a string field.
+    pub fn string(&self, field: usize) -> Result<&'f str, Db2Error> {
+        self.file.string(self.record, field)
+    }
+}
+
+fn is_zeroed(bytes: &[u8], section: &SectionHeader, offset: usize, record_size: usize) -> bool {
+    let len = if section.offset_records_end > offset {
+        section.offset_records_end - offset
+    } else {
+        section.record_count * record_size
+    };
+    bytes
+        .get(offset..offset + len)
+        .map(|data| data.iter().all(|b| *b == 0))
+        .unwrap_or(false)
+}
+
+fn slice(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8], Db2Error> {
+    offset
+        .checked_add(len)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or(Db2Error::Truncated { offset, len })
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, Db2Error> {
+    let mut buf = [0u8; 2];
+    buf.copy_from_slice(slice(bytes, offset, 2)?);
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, Db2Error> {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(slice(bytes, offset, 4)?);
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, Db2Error> {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(slice(bytes, offset, 8)?);
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_u32s(bytes: &[u8], offset: usize, count: usize) -> Result<Vec<u32>, Db2Error> {
+    let len = count.checked_mul(4).ok_or(Db2Error::Truncated {
+        offset,
+        len: usize::MAX,
+    })?;
+    Ok(slice(bytes, offset, len)?
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect())
+}
+
+/// The offset of the `index`th entry of `count` values in a field's pallet data.
+///
+/// The index comes straight from a record, so an offset which doesn't fit in a
+/// `usize` is reported as a truncated pallet rather than overflowing.
+fn pallet_start(pallet_offset: usize, index: usize, count: usize) -> Result<usize, Db2Error> {
+    index
+        .checked_mul(count)
+        .and_then(|values| values.checked_mul(4))
+        .and_then(|len| len.checked_add(pallet_offset))
+        .ok_or(Db2Error::Truncated {
+            offset: pallet_offset,
+            len: usize::MAX,
+        })
+}
+
+/// Reads `size_bits` bits starting `offset_bits` bits into `data`, least significant
+/// bit first.
+fn read_bits(data: &[u8], offset_bits: usize, size_bits: u32) -> Result<u64, Db2Error> {
+    if size_bits == 0 {
+        return Ok(0);
+    }
+    let start = offset_bits / 8;
+    let end = (offset_bits + size_bits as usize).div_ceil(8);
+    let bytes = slice(data, start, end - start)?;
+
+    let mut value: u128 = 0;
+    for (i, byte) in bytes.iter().enumerate() {
+        value |= (*byte as u128) << (i * 8);
+    }
+    value >>= offset_bits % 8;
+
+    let mask = if size_bits >= 64 {
+        u64::MAX
+    } else {
+        (1u64 << size_bits) - 1
+    };
+    Ok(value as u64 & mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::builder::{Db2Builder, Storage, Value};
+    use super::*;
+
+    /// A complete WDC3 file written out by hand: one section holding a single
+    /// record with ID `7` and a single 32 bit field with the value `42`.
+    fn handwritten_file() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let u16s = |bytes: &mut Vec<u8>, values: &[u16]| {
+            values
+                .iter()
+                .for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()))
+        };
+        let u32s = |bytes: &mut Vec<u8>, values: &[u32]| {
+            values
+                .iter()
+                .for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()))
+        };
+
+        // header
+        bytes.extend_from_slice(b"WDC3");
+        // record count, field count, record size, string table size
+        u32s(&mut bytes, &[1, 1, 4, 0]);
+        // table hash, layout hash, min id, max id, locale
+        u32s(&mut bytes, &[0xCAFE, 0xBEEF, 7, 7, 0]);
+        // flags, id index
+        u16s(&mut bytes, &[0, 0]);
+        // total field count, bitpacked data offset, lookup column count,
+        // field storage info size, common data size, pallet data size, section count
+        u32s(&mut bytes, &[1, 0, 0, 24, 0, 0, 1]);
+        assert_eq!(HEADER_SIZE, bytes.len());
+
+        // section header: tact key hash, then file offset, record count,
+        // string table size, offset records end, id list size, relationship data size,
+        // offset map id count, copy table count
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        u32s(&mut bytes, &[140, 1, 0, 0, 4, 0, 0, 0]);
+
+        // field structure: size (32 - bits), position
+        u16s(&mut bytes, &[0, 0]);
+
+        // field storage info: offset bits, size bits, additional data size,
+        // storage type (none), and the three unused values
+        u16s(&mut bytes, &[0, 32]);
+        u32s(&mut bytes, &[0, 0, 0, 0, 0]);
+        assert_eq!(140, bytes.len());
+
+        // section: the record, then the id list
+        u32s(&mut bytes, &[42, 7]);
+        bytes
+    }
+
+    #[test]
+    fn parses_handwritten_file() {
+        let bytes = handwritten_file();
+        let file = Db2File::parse(&bytes).unwrap();
+
+        assert_eq!(Db2Version::Wdc3, file.version());
+        assert_eq!(0xCAFE, file.table_hash());
+        assert_eq!(0xBEEF, file.layout_hash());
+        assert_eq!(1, file.len());
+
+        let record = file.record(7).unwrap();
+        assert_eq!(42, record.u32(0).unwrap());
+        assert!(record.relation().is_none());
+        assert!(matches!(record.u32(1), Err(Db2Error::NoSuchField(1))));
+    }
+
+    #[test]
+    fn rejects_unknown_and_truncated_files() {
+        assert!(matches!(
+            Db2File::parse(b"WDBC0000"),
+            Err(Db2Error::UnsupportedFormat(_))
+        ));
+
+        let bytes = handwritten_file();
+        assert!(matches!(
+            Db2File::parse(&bytes[..bytes.len() - 2]),
+            Err(Db2Error::Truncated { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_counts_the_file_cannot_hold() {
+        // a section count far beyond the size of the file
+        let mut bytes = handwritten_file();
+        bytes[68..72].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            Db2File::parse(&bytes),
+            Err(Db2Error::Truncated {
+                offset: HEADER_SIZE,
+                ..
+            })
+        ));
+
+        // a field count far beyond the size of the file
+        let mut bytes = handwritten_file();
+        bytes[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            Db2File::parse(&bytes),
+            Err(Db2Error::Truncated { offset: 112, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_pallet_indexes_which_overflow() {
+        let mut bytes = handwritten_file();
+        // a bitpacked indexed array of u32::MAX values, with a 32 bit index
+        bytes[124..128].copy_from_slice(&4u32.to_le_bytes());
+        bytes[132..136].copy_from_slice(&32u32.to_le_bytes());
+        bytes[136..140].copy_from_slice(&u32::MAX.to_le_bytes());
+        // the record's index
+        bytes[140..144].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(148, bytes.len());
+
+        let file = Db2File::parse(&bytes).unwrap();
+        assert!(matches!(
+            file.record(7).unwrap().u32_array(0),
+            Err(Db2Error::Truncated { .. })
+        ));
+
+        // the same index into a pallet of single values
+        bytes[124..128].copy_from_slice(&3u32.to_le_bytes());
+        let file = Db2File::parse(&bytes).unwrap();
+        assert!(matches!(
+            file.record(7).unwrap().u32(0),
+            Err(Db2Error::Truncated { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_fields_wider_than_64_bits() {
+        let mut bytes = handwritten_file();
+        // storage type bitpacked, with a size of 200 bits
+        bytes[124..128].copy_from_slice(&1u32.to_le_bytes());
+        bytes[132..136].copy_from_slice(&200u32.to_le_bytes());
+        assert!(matches!(
+            Db2File::parse(&bytes),
+            Err(Db2Error::InvalidFieldSize {
+                field: 0,
+                size_bits: 200
+            })
+        ));
+
+        bytes[132..136].copy_from_slice(&64u32.to_le_bytes());
+        assert!(Db2File::parse(&bytes).is_ok());
+    }
+
+    #[test]
+    fn reads_every_storage_type() {
+        let bytes = Db2Builder::new()
+            .field(Storage::None { bits: 16, count: 1 })
+            .field(Storage::None { bits: 32, count: 3 })
+            .field(Storage::Bitpacked { bits: 5 })
+            .field(Storage::BitpackedSigned { bits: 7 })
+            .field(Storage::Common { default: 9 })
+            .field(Storage::Pallet { bits: 2 })
+            .field(Storage::PalletArray { bits: 1, count: 2 })
+            .field(Storage::String)
+            .row(
+                10,
+                vec![
+                    Value::Int(vec![65535]),
+                    Value::Int(vec![1, (-2i32) as u32 as u64, 3]),
+                    Value::Int(vec![31]),
+                    Value::Int(vec![(-5i64) as u64]),
+                    Value::Int(vec![9]),
+                    Value::Int(vec![1000]),
+                    Value::Int(vec![4, 5]),
+                    Value::Str("Fireflash".to_string()),
+                ],
+            )
+            .row(
+                11,
+                vec![
+                    Value::Int(vec![2]),
+                    Value::Int(vec![0, 0, 0]),
+                    Value::Int(vec![0]),
+                    Value::Int(vec![63]),
+                    Value::Int(vec![12]),
+                    Value::Int(vec![(-1i32) as u32 as u64]),
+                    Value::Int(vec![6, 7]),
+                    Value::Str("Peerless".to_string()),
+                ],
+            )
+            .build();
+        let file = Db2File::parse(&bytes).unwrap();
+        assert_eq!(2, file.len());
+
+        let first = file.record(10).unwrap();
+        assert_eq!(65535, first.u32(0).unwrap());
+        assert_eq!(-1, first.i32(0).unwrap());
+        assert_eq!(vec![1, -2, 3], first.i32_array(1).unwrap());
+        assert_eq!(31, first.u32(2).unwrap());
+        assert_eq!(-5, first.i32(3).unwrap());
+        assert_eq!(9, first.u32(4).unwrap());
+        assert_eq!(1000, first.u32(5).unwrap());
+        assert_eq!(vec![4, 5], first.u32_array(6).unwrap());
+        assert_eq!("Fireflash", first.string(7).unwrap());
+
+        let second = file.record(11).unwrap();
+        assert_eq!(63, second.i32(3).unwrap());
+        assert_eq!(12, second.u32(4).unwrap());
+        assert_eq!(-1, second.i32(5).unwrap());
+        assert_eq!(vec![6, 7], second.u32_array(6).unwrap());
+        assert_eq!("Peerless", second.string(7).unwrap());
+    }
+
+    #[test]
+    fn reads_floats_and_64_bit_values() {
+        let bytes = Db2Builder::new()
+            .field(Storage::None { bits: 64, count: 1 })
+            .field(Storage::None { bits: 32, count: 2 })
+            .row(
+                1,
+                vec![
+                    Value::Int(vec![(-1i64) as u64]),
+                    Value::Int(vec![1.5f32.to_bits() as u64, (-60.25f32).to_bits() as u64]),
+                ],
+            )
+            .build();
+        let file = Db2File::parse(&bytes).unwrap();
+        let record = file.record(1).unwrap();
+        assert_eq!(-1, record.i64(0).unwrap());
+        assert_eq!(u64::MAX, record.u64(0).unwrap());
+        assert_eq!(vec![1.5, -60.25], record.f32_array(1).unwrap());
+        assert_relative_eq!(1.5, record.f32(1).unwrap());
+    }
+
+    #[test]
+    fn reads_sections_relationships_and_copies() {
+        let bytes = Db2Builder::new()
+            .version(b"WDC4")
+            .sections(2)
+            .field(Storage::None { bits: 32, count: 1 })
+            .field(Storage::String)
+            .related_row(1, 500, vec![Value::Int(vec![10]), Value::Str("a".into())])
+            .related_row(2, 501, vec![Value::Int(vec![20]), Value::Str("b".into())])
+            .related_row(3, 502, vec![Value::Int(vec![30]), Value::Str("c".into())])
+            .copy(4, 3)
+            .build();
+        let file = Db2File::parse(&bytes).unwrap();
+        assert_eq!(Db2Version::Wdc4, file.version());
+        assert_eq!(4, file.len());
+
+        for (id, value, relation, string) in
+            &[(1, 10, 500, "a"), (2, 20, 501, "b"), (3, 30, 502, "c")]
+        {
+            let record = file.record(*id).unwrap();
+            assert_eq!(*value, record.u32(0).unwrap());
+            assert_eq!(Some(*relation), record.relation());
+            assert_eq!(*string, record.string(1).unwrap());
+        }
+
+        let copy = file.record(4).unwrap();
+        assert_eq!(30, copy.u32(0).unwrap());
+        assert_eq!(Some(502), copy.relation());
+        assert_eq!("c", copy.string(1).unwrap());
+    }
+
+    #[test]
+    fn reads_sparse_records_with_inline_strings() {
+        let bytes = Db2Builder::new()
+            .sparse()
+            .field(Storage::None { bits: 16, count: 1 })
+            .field(Storage::String)
+            .field(Storage::None { bits: 32, count: 2 })
+            .field(Storage::String)
+            .field(Storage::None { bits: 8, count: 1 })
+            .row(
+                183421,
+                vec![
+                    Value::Int(vec![1]),
+                    Value::Str("Stone Legion Sabatons".into()),
+                    Value::Int(vec![2, 3]),
+                    Value::Str("".into()),
+                    Value::Int(vec![4]),
+                ],
+            )
+            .row(
+                19019,
+                vec![
+                    Value::Int(vec![5]),
+                    Value::Str("Thunderfury".into()),
+                    Value::Int(vec![6, 7]),
+                    Value::Str("Blessed".into()),
+                    Value::Int(vec![8]),
+                ],
+            )
+            .build();
+        let file = Db2File::parse(&bytes).unwrap().with_inline_strings(&[1, 3]);
+
+        let sabatons = file.record(183421).unwrap();
+        assert_eq!("Stone Legion Sabatons", sabatons.string(1).unwrap());
+        assert_eq!(vec![2, 3], sabatons.u32_array(2).unwrap());
+        assert_eq!("", sabatons.string(3).unwrap());
+        assert_eq!(4, sabatons.u32(4).unwrap());
+
+        let thunderfury = file.record(19019).unwrap();
+        assert_eq!(5, thunderfury.u32(0).unwrap());
+        assert_eq!("Blessed", thunderfury.string(3).unwrap());
+        assert_eq!(8, thunderfury.u32(4).unwrap());
+    }
+
+    #[test]
+    fn skips_zeroed_encrypted_sections() {
+        let bytes = Db2Builder::new()
+            .sections(2)
+            .encrypt_section(1)
+            .field(Storage::None { bits: 32, count: 1 })
+            .row(1, vec![Value::Int(vec![10])])
+            .row(2, vec![Value::Int(vec![20])])
+            .build();
+        let file = Db2File::parse(&bytes).unwrap();
+        assert_eq!(1, file.len());
+        assert!(file.record(1).is_some());
+        assert!(file.record(2).is_none());
+    }
+}
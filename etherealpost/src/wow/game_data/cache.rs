@@ -0,0 +1,456 @@
+//! A compact, prebuilt cache of [`GameData`].
+//!
+//! Deriving the game data means parsing several large tables, which is slow to do on
+//! every start. The derived lookup maps are small in comparison, so they can be
+//! written to a cache once, for example when a new client build is extracted, and
+//! loaded quickly from then on.
+//!
+//! # Layout
+//!
+//! All numbers are little-endian.
+//!
+//! ```text
+//! magic       "EPGD"
+//! version     u32    format version of the cache, currently 2
+//! has_build   u8     1 if the client build is known, otherwise 0
+//! build       u32    the client build the data came from
+//! checksum    u64    64-bit FNV-1a hash of the payload
+//! length      u64    length of the payload in bytes
+//! payload
+//! ```
+//!
+//! The payload is a list of sections, each of which is a `u32` count followed by that
+//! many entries. Entries are sorted by key, so the same data always produces the same
+//! cache. In order:
+//!
+//! 1. base item levels: `u64` item ID, `u32` item level
+//! 2. item to pet: `u64` item ID, `u32` species ID
+//! 3. equippable items: `u64` item ID
+//! 4. bonus curves: `u32` bonus ID, `u32` curve ID
+//! 5. bonus item level adjustments: `u32` bonus ID, `i32` adjustment
+//! 6. curves: `u32` curve ID, `u32` point count, then `f64` x and `f64` y per point
+//! 7. bonus effects: `u32` bonus ID, `u32` effect count, then a `u16` type and four
+//!    `i32` values per effect
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use super::{GameData, GameDataError};
+use crate::parse::ItemLevelCurve;
+use crate::wow::data_tables::{Db2ItemBonuses, ItemBonusEffect};
+
+const MAGIC: &[u8; 4] = b"EPGD";
+const FORMAT_VERSION: u32 = 2;
+const HEADER_SIZE: usize = 29;
+
+impl GameData {
+    /// Writes the game data into a cache. See the [cache](`crate::wow::game_data::cache`)
+    /// module for the format.
+    pub fn write_cache<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let payload = self.cache_payload();
+
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        header.push(self.build.is_some() as u8);
+        header.extend_from_slice(&self.build.unwrap_or(0).to_le_bytes());
+        header.extend_from_slice(&checksum(&payload).to_le_bytes());
+        header.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+
+        writer.write_all(&header)?;
+        writer.write_all(&payload)?;
+        writer.flush()
+    }
+
+    /// Writes the game data into a cache file, replacing the file if it exists.
+    ///
+    /// The cache is written to a temporary file first and then moved into place, so
+    /// a process loading the cache never sees a partially written file.
+    pub fn save_cache<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+
+        self.write_cache(io::BufWriter::new(fs::File::create(&temp)?))?;
+        fs::rename(&temp, path)
+    }
+
+    /// Reads game data from a cache.
+    ///
+    /// If `expected_build` is given, the cache is refused with
+    /// [BuildMismatch](`GameDataError::BuildMismatch`) unless it was built from that
+    /// client build.
+    pub fn read_cache<R: Read>(
+        mut reader: R,
+        expected_build: Option<u32>,
+    ) -> Result<GameData, GameDataError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(GameDataError::Cache)?;
+
+        let mut cursor = Cursor::new(&bytes);
+        if cursor.take(4)? != MAGIC {
+            return Err(GameDataError::InvalidCache("not a game data cache"));
+        }
+        let version = cursor.u32()?;
+        if version != FORMAT_VERSION {
+            return Err(GameDataError::InvalidCache("unsupported cache version"));
+        }
+        let build = match cursor.take(1)?[0] {
+            0 => {
+                cursor.u32()?;
+                None
+            }
+            _ => Some(cursor.u32()?),
+        };
+        if let Some(expected) = expected_build {
+            if build != Some(expected) {
+                return Err(GameDataError::BuildMismatch {
+                    expected,
+                    found: build,
+                });
+            }
+        }
+
+        let expected_checksum = cursor.u64()?;
+        let length = cursor.u64()? as usize;
+        let payload = cursor.take(length)?;
+        let found_checksum = checksum(payload);
+        if found_checksum != expected_checksum {
+            return Err(GameDataError::ChecksumMismatch {
+                expected: expected_checksum,
+                found: found_checksum,
+            });
+        }
+
+        let mut game_data = GameData::from_cache_payload(payload)?;
+        game_data.build = build;
+        Ok(game_data)
+    }
+
+    /// Reads game data from a cache file.
+    ///
+    /// See [read_cache](`Self::read_cache`) for how `expected_build` is used.
+    pub fn load_cache<P: AsRef<Path>>(
+        path: P,
+        expected_build: Option<u32>,
+    ) -> Result<GameData, GameDataError> {
+        let file = fs::File::open(path).map_err(GameDataError::Cache)?;
+        GameData::read_cache(io::BufReader::new(file), expected_build)
+    }
+
+    fn cache_payload(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+
+        write_section(&mut payload, &self.base_ilvls, |out, item_id, ilvl| {
+            out.extend_from_slice(&item_id.to_le_bytes());
+            out.extend_from_slice(&ilvl.to_le_bytes());
+        });
+        write_section(&mut payload, &self.item_to_pet, |out, item_id, species| {
+            out.extend_from_slice(&item_id.to_le_bytes());
+            out.extend_from_slice(&species.to_le_bytes());
+        });
+
+        let mut equippable: Vec<u64> = self.equippable_items.iter().copied().collect();
+        equippable.sort_unstable();
+        payload.extend_from_slice(&(equippable.len() as u32).to_le_bytes());
+        for item_id in equippable {
+            payload.extend_from_slice(&item_id.to_le_bytes());
+        }
+
+        write_section(
+            &mut payload,
+            &self.item_bonuses.curve_ids,
+            |out, bonus_id, curve_id| {
+                out.extend_from_slice(&bonus_id.to_le_bytes());
+                out.extend_from_slice(&curve_id.to_le_bytes());
+            },
+        );
+        write_section(
+            &mut payload,
+            &self.item_bonuses.ilvl_adjustments,
+            |out, bonus_id, adjustment| {
+                out.extend_from_slice(&bonus_id.to_le_bytes());
+                out.extend_from_slice(&adjustment.to_le_bytes());
+            },
+        );
+        write_section(&mut payload, &self.curve_points, |out, curve_id, curve| {
+            let points = curve.points();
+            out.extend_from_slice(&curve_id.to_le_bytes());
+            out.extend_from_slice(&(points.len() as u32).to_le_bytes());
+            for (x, y) in points {
+                out.extend_from_slice(&x.to_le_bytes());
+                out.extend_from_slice(&y.to_le_bytes());
+            }
+        });
+        write_section(
+            &mut payload,
+            &self.item_bonuses.effects,
+            |out, bonus_id, effects| {
+                out.extend_from_slice(&bonus_id.to_le_bytes());
+                out.extend_from_slice(&(effects.len() as u32).to_le_bytes());
+                for effect in effects {
+                    let (type_id, values) = effect.to_raw();
+                    out.extend_from_slice(&type_id.to_le_bytes());
+                    for value in &values {
+                        out.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+            },
+        );
+
+        payload
+    }
+
+    fn from_cache_payload(payload: &[u8]) -> Result<GameData, GameDataError> {
+        let mut cursor = Cursor::new(payload);
+
+        let mut base_ilvls = HashMap::new();
+        for _ in 0..cursor.u32()? {
+            base_ilvls.insert(cursor.u64()?, cursor.u32()?);
+        }
+        let mut item_to_pet = HashMap::new();
+        for _ in 0..cursor.u32()? {
+            item_to_pet.insert(cursor.u64()?, cursor.u32()?);
+        }
+        let mut equippable_items = HashSet::new();
+        for _ in 0..cursor.u32()? {
+            equippable_items.insert(cursor.u64()?);
+        }
+        let mut curve_ids = HashMap::new();
+        for _ in 0..cursor.u32()? {
+            curve_ids.insert(cursor.u32()?, cursor.u32()?);
+        }
+        let mut ilvl_adjustments = HashMap::new();
+        for _ in 0..cursor.u32()? {
+            ilvl_adjustments.insert(cursor.u32()?, cursor.u32()? as i32);
+        }
+        let mut curve_points = HashMap::new();
+        for _ in 0..cursor.u32()? {
+            let curve_id = cursor.u32()?;
+            let mut points = Vec::new();
+            for _ in 0..cursor.u32()? {
+                points.push((cursor.f64()?, cursor.f64()?));
+            }
+            curve_points.insert(curve_id, ItemLevelCurve::from_points(&points));
+        }
+        let mut effects = HashMap::new();
+        for _ in 0..cursor.u32()? {
+            let bonus_id = cursor.u32()?;
+            let mut list = Vec::new();
+            for _ in 0..cursor.u32()? {
+                let type_id = cursor.u16()?;
+                let mut values = [0i32; 4];
+                for value in &mut values {
+                    *value = cursor.u32()? as i32;
+                }
+                list.push(ItemBonusEffect::from_raw(type_id, values));
+            }
+            effects.insert(bonus_id, list);
+        }
+
+        if cursor.offset != payload.len() {
+            return Err(GameDataError::InvalidCache(
+                "unexpected data after the payload",
+            ));
+        }
+
+        Ok(GameData {
+            build: None,
+            curve_points,
+            item_bonuses: Db2ItemBonuses {
+                effects,
+                curve_ids,
+                ilvl_adjustments,
+            },
+            base_ilvls,
+            item_to_pet,
+            equippable_items,
+        })
+    }
+}
+
+/// Writes a map as a section, sorted by key.
+fn write_section<K, V, F>(payload: &mut Vec<u8>, map: &HashMap<K, V>, mut write: F)
+where
+    K: Ord,
+    F: FnMut(&mut Vec<u8>, &K, &V),
+{
+    let mut entries: Vec<(&K, &V)> = map.iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+    payload.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    for (key, value) in entries {
+        write(payload, key, value);
+    }
+}
+
+/// The 64-bit FNV-1a hash of the payload.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+struct Cursor<'b> {
+    bytes: &'b [u8],
+    offset: usize,
+}
+
+impl<'b> Cursor<'b> {
+    fn new(bytes: &'b [u8]) -> Self {
+        Cursor { bytes, offset: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'b [u8], GameDataError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(GameDataError::InvalidCache("the cache is truncated"))?;
+        let taken = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<u16, GameDataError> {
+        let mut bytes = [0u8; 2];
+        bytes.copy_from_slice(self.take(2)?);
+        Ok(u16::from_le_bytes(bytes))
+    }
+
+    fn u32(&mut self) -> Result<u32, GameDataError> {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64, GameDataError> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn f64(&mut self) -> Result<f64, GameDataError> {
+        Ok(f64::from_bits(self.u64()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_data() -> GameData {
+        let mut game_data = GameData::default().with_build(36753);
+        game_data.base_ilvls.insert(19019, 80);
+        game_data.base_ilvls.insert(183421, 100);
+        game_data.item_to_pet.insert(11826, 85);
+        game_data.equippable_items.insert(19019);
+        game_data.item_bonuses.curve_ids.insert(6707, 1748);
+        game_data.item_bonuses.ilvl_adjustments.insert(58, -2);
+        game_data.item_bonuses.effects.insert(
+            6646,
+            vec![
+                ItemBonusEffect::Socket {
+                    count: 1,
+                    socket_type: 8,
+                },
+                ItemBonusEffect::Other {
+                    type_id: 42,
+                    values: [7, -8, 9, 10],
+                },
+            ],
+        );
+        game_data.curve_points.insert(
+            1748,
+            ItemLevelCurve::from_points(&[(1.0, 6.0), (25.0, 31.0), (60.0, 146.0)]),
+        );
+        game_data
+    }
+
+    fn cache_bytes(game_data: &GameData) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        game_data.write_cache(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trips_every_map() {
+        let original = game_data();
+        let bytes = cache_bytes(&original);
+        assert_eq!(bytes, cache_bytes(&original));
+
+        let loaded = GameData::read_cache(bytes.as_slice(), Some(36753)).unwrap();
+        assert_eq!(Some(36753), loaded.build);
+        assert_eq!(original.base_ilvls, loaded.base_ilvls);
+        assert_eq!(original.item_to_pet, loaded.item_to_pet);
+        assert_eq!(original.equippable_items, loaded.equippable_items);
+        assert_eq!(Some(1748), loaded.item_bonuses.curve_id(&6707));
+        assert_eq!(Some(-2), loaded.item_bonuses.ilvl_adjustment(&58));
+        assert_eq!(
+            original.item_bonuses.effects(&6646),
+            loaded.item_bonuses.effects(&6646)
+        );
+        assert_eq!(
+            original.curve_points[&1748].points(),
+            loaded.curve_points[&1748].points()
+        );
+        assert_eq!(70, loaded.curve_points[&1748].calc_ilvl(&37));
+    }
+
+    #[test]
+    fn refuses_mismatched_builds() {
+        let bytes = cache_bytes(&game_data());
+        match GameData::read_cache(bytes.as_slice(), Some(37176)) {
+            Err(GameDataError::BuildMismatch { expected, found }) => {
+                assert_eq!(37176, expected);
+                assert_eq!(Some(36753), found);
+            }
+            other => panic!("unexpected result: {:?}", other.err()),
+        }
+
+        let unknown = cache_bytes(&GameData::default());
+        assert!(matches!(
+            GameData::read_cache(unknown.as_slice(), Some(36753)),
+            Err(GameDataError::BuildMismatch { found: None, .. })
+        ));
+        assert!(GameData::read_cache(unknown.as_slice(), None).is_ok());
+    }
+
+    #[test]
+    fn detects_corruption() {
+        let mut bytes = cache_bytes(&game_data());
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        assert!(matches!(
+            GameData::read_cache(bytes.as_slice(), None),
+            Err(GameDataError::ChecksumMismatch { .. })
+        ));
+
+        let bytes = cache_bytes(&game_data());
+        assert!(matches!(
+            GameData::read_cache(&bytes[..bytes.len() - 4], None),
+            Err(GameDataError::InvalidCache(_))
+        ));
+        assert!(matches!(
+            GameData::read_cache(&b"not a cache"[..], None),
+            Err(GameDataError::InvalidCache(_))
+        ));
+    }
+
+    #[test]
+    fn saves_and_loads_files() {
+        let path = std::env::temp_dir().join(format!(
+            "etherealpost-game-data-cache-{}.bin",
+            std::process::id()
+        ));
+        game_data().save_cache(&path).unwrap();
+        let loaded = GameData::load_cache(&path, Some(36753)).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(80, loaded.base_ilvl(&19019));
+    }
+}
@@ -0,0 +1,495 @@
+pub mod cache;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::parse::{ItemLevelCurve, ItemLevelCurvePoints};
+use crate::wow::data_tables::{
+    Db2BattlePetSpeciesTable, Db2CurvePoints, Db2ItemBonuses, Db2ItemEffects, Db2ItemSparseTable,
+    Db2Items, LoadMode, LoadReport,
+};
+use crate::wow::db2::{Db2File, DbCache};
+use crate::Error;
+
+/// The name of the client's hotfix cache, which is applied to the DB2 files
+/// when it is found next to them.
+const HOTFIX_FILE: &str = "DBCache.bin";
+
+/// The files of every table which is needed, as `(DB2 file, CSV file)` names.
+///
+/// The CSV names are the ones used by the exports of `wow.tools`.
+const ITEM_FILES: (&str, &str) = ("Item.db2", "item.csv");
+const ITEM_EFFECT_FILES: (&str, &str) = ("ItemEffect.db2", "itemeffect.csv");
+const ITEM_SPARSE_FILES: (&str, &str) = ("ItemSparse.db2", "itemsparse.csv");
+const ITEM_BONUS_FILES: (&str, &str) = ("ItemBonus.db2", "itembonus.csv");
+const CURVE_POINT_FILES: (&str, &str) = ("CurvePoint.db2", "curvepoint.csv");
+const BATTLE_PET_SPECIES_FILES: (&str, &str) = ("BattlePetSpecies.db2", "battlepetspecies.csv");
+
+/// All of the game data which is needed to summarize an auction house, derived from
+/// the World of Warcraft data tables.
+///
+/// Placing an auction into its groups needs several lookups which are joined together
+/// from different tables, such as which pet an item teaches, which goes through the
+/// spell the item casts. `GameData` loads every table and performs those joins once,
+/// so that the summaries in [parse](`crate::parse`) can be built straight from it.
+///
+/// The data tables identify items by `u32`, while the auction house API uses `u64`.
+/// Every map here is keyed by the `u64` ID used by the auction house.
+///
+/// # Example
+///
+/// ```rust
+/// use etherealpost::auctions::AuctionFile;
+/// use etherealpost::parse::AuctionsSummary;
+/// use etherealpost::wow::GameData;
+///
+/// let mut game_data = GameData::default();
+/// game_data.base_ilvls.insert(19019, 80);
+///
+/// let auction_file = AuctionFile::from_json(r#"{"auctions": [{"id": 1, "quantity": 1,
+///     "item": {"id": 19019}, "buyout": 9990000, "time_left": "LONG"}]}"#).unwrap();
+/// let summary = AuctionsSummary::from_auction_file(&auction_file, &game_data);
+/// assert!(summary.item_level_summaries[&19019].contains_key(&80));
+/// ```
+#[derive(Default)]
+pub struct GameData {
+    /// The build of the game client the data came from, if it is known.
+    pub build: Option<u32>,
+
+    /// Mapping of **Curve IDs** to the curve used to calculate the level of items
+    /// which have a bonus with that curve.
+    pub curve_points: ItemLevelCurvePoints,
+
+    /// The item bonuses, used to resolve the item level adjustments and curves
+    /// of an item's bonus lists.
+    pub item_bonuses: Db2ItemBonuses,
+
+    /// Mapping of **Item IDs** to their base item level.
+    pub base_ilvls: HashMap<u64, u32>,
+
+    /// Mapping of **Item IDs** to the **Pet Species ID** which they teach when used.
+    ///
+    /// Pet cages are not included, since they carry their species on the auction.
+    pub item_to_pet: HashMap<u64, u32>,
+
+    /// The **Item IDs** of every item which can be equipped.
+    pub equippable_items: HashSet<u64>,
+}
+
+impl GameData {
+    /// Derives the game data from already loaded tables.
+    pub fn from_tables(
+        items: &Db2Items,
+        item_effects: &Db2ItemEffects,
+        item_sparse: &Db2ItemSparseTable,
+        curve_points: &Db2CurvePoints,
+        pet_species: &Db2BattlePetSpeciesTable,
+        item_bonuses: Db2ItemBonuses,
+    ) -> GameData {
+        // item -> spell it teaches -> species that spell summons
+        let item_to_pet = items
+            .pet_item_ids
+            .iter()
+            .filter_map(|item_id| {
+                let spell_id = item_effects.item_to_spell_learn.get(item_id)?;
+                let species_id = pet_species.spell_to_species.get(spell_id)?;
+                Some((*item_id as u64, *species_id))
+            })
+            .collect();
+
+        GameData {
+            build: None,
+            curve_points: ItemLevelCurve::for_whole_table(curve_points),
+            item_bonuses,
+            base_ilvls: item_sparse
+                .base_item_levels
+                .iter()
+                .map(|(item_id, ilvl)| (*item_id as u64, *ilvl))
+                .collect(),
+            item_to_pet,
+            equippable_items: items
+                .equippable_item_ids
+                .iter()
+                .map(|item_id| *item_id as u64)
+                .collect(),
+        }
+    }
+
+    /// Loads the game data from a directory of raw DB2 files, as they are extracted
+    /// from the game client.
+    ///
+    /// The directory must contain `Item.db2`, `ItemEffect.db2`, `ItemSparse.db2`,
+    /// `ItemBonus.db2`, `CurvePoint.db2` and `BattlePetSpecies.db2`. If the client's
+    /// `DBCache.bin` is also in the directory, its hotfixes are applied to every table
+    /// and the client build is taken from it.
+    ///
+    /// Returns the [`LoadReport`] of every table alongside the game data. See
+    /// [`LoadMode`] for how `mode` is used.
+    pub fn from_db2_dir<P: AsRef<Path>>(
+        dir: P,
+        mode: LoadMode,
+    ) -> Result<(GameData, Vec<LoadReport>), GameDataError> {
+        let dir = dir.as_ref();
+        let _span =
+            tracing::info_span!("load_game_data", dir = %dir.display(), format = "db2").entered();
+
+        let hotfixes = if dir.join(HOTFIX_FILE).exists() {
+            let bytes = read(dir, HOTFIX_FILE)?;
+            let cache = DbCache::parse(&bytes).map_err(|e| table_error(HOTFIX_FILE, e))?;
+            Some(cache)
+        } else {
+            None
+        };
+
+        let mut load = TableLoader::new(dir, mode);
+        let game_data = GameData::from_tables(
+            &load.db2(ITEM_FILES.0, &hotfixes, Db2Items::from_db2_file)?,
+            &load.db2(
+                ITEM_EFFECT_FILES.0,
+                &hotfixes,
+                Db2ItemEffects::from_db2_file,
+            )?,
+            &load.db2(
+                ITEM_SPARSE_FILES.0,
+                &hotfixes,
+                Db2ItemSparseTable::from_db2_file,
+            )?,
+            &load.db2(
+                CURVE_POINT_FILES.0,
+                &hotfixes,
+                Db2CurvePoints::from_db2_file,
+            )?,
+            &load.db2(
+                BATTLE_PET_SPECIES_FILES.0,
+                &hotfixes,
+                Db2BattlePetSpeciesTable::from_db2_file,
+            )?,
+            load.db2(ITEM_BONUS_FILES.0, &hotfixes, Db2ItemBonuses::from_db2_file)?,
+        );
+        let build = hotfixes.as_ref().map(|cache| cache.build);
+
+        Ok((GameData { build, ..game_data }, load.reports))
+    }
+
+    /// Loads the game data from a directory of CSV exports of the tables, named the
+    /// way `wow.tools` names them.
+    ///
+    /// The directory must contain `item.csv`, `itemeffect.csv`, `itemsparse.csv`,
+    /// `itembonus.csv`, `curvepoint.csv` and `battlepetspecies.csv`.
+    ///
+    /// Returns the [`LoadReport`] of every table alongside the game data. See
+    /// [`LoadMode`] for how `mode` is used.
+    pub fn from_csv_dir<P: AsRef<Path>>(
+        dir: P,
+        mode: LoadMode,
+    ) -> Result<(GameData, Vec<LoadReport>), GameDataError> {
+        let dir = dir.as_ref();
+        let _span =
+            tracing::info_span!("load_game_data", dir = %dir.display(), format = "csv").entered();
+
+        let mut load = TableLoader::new(dir, mode);
+        let game_data = GameData::from_tables(
+            &load.csv(ITEM_FILES.1, Db2Items::from_csv)?,
+            &load.csv(ITEM_EFFECT_FILES.1, Db2ItemEffects::from_csv)?,
+            &load.csv(ITEM_SPARSE_FILES.1, Db2ItemSparseTable::from_csv)?,
+            &load.csv(CURVE_POINT_FILES.1, Db2CurvePoints::from_csv)?,
+            &load.csv(
+                BATTLE_PET_SPECIES_FILES.1,
+                Db2BattlePetSpeciesTable::from_csv,
+            )?,
+            load.csv(ITEM_BONUS_FILES.1, Db2ItemBonuses::from_csv)?,
+        );
+
+        Ok((game_data, load.reports))
+    }
+
+    /// Sets the build of the game client the data came from.
+    pub fn with_build(mut self, build: u32) -> Self {
+        self.build = Some(build);
+        self
+    }
+
+    /// The base item level of an item, or `1` if the item isn't known.
+    pub fn base_ilvl(&self, item_id: &u64) -> u32 {
+        self.known_base_ilvl(item_id).unwrap_or(1)
+    }
+
+    /// The base item level of an item, or `None` if the item isn't known.
+    pub fn known_base_ilvl(&self, item_id: &u64) -> Option<u32> {
+        self.base_ilvls.get(item_id).copied()
+    }
+
+    /// The pet species an item teaches, if it teaches one.
+    pub fn pet_species(&self, item_id: &u64) -> Option<u32> {
+        self.item_to_pet.get(item_id).copied()
+    }
+
+    /// Whether or not an item can be equipped.
+    pub fn is_equippable(&self, item_id: &u64) -> bool {
+        self.equippable_items.contains(item_id)
+    }
+}
+
+fn read(dir: &Path, file: &'static str) -> Result<Vec<u8>, GameDataError> {
+    fs::read(dir.join(file)).map_err(|error| GameDataError::Io { file, error })
+}
+
+fn table_error<E: Into<Error>>(file: &'static str, error: E) -> GameDataError {
+    GameDataError::Table {
+        file,
+        error: Box::new(error.into()),
+    }
+}
+
+/// A loaded table along with its report, as returned by the data table loaders.
+type Loaded<T> = Result<(T, LoadReport), Error>;
+
+/// Loads the tables of a directory one at a time, collecting their reports.
+struct TableLoader<'d> {
+    dir: &'d Path,
+    mode: LoadMode,
+    reports: Vec<LoadReport>,
+}
+
+impl<'d> TableLoader<'d> {
+    fn new(dir: &'d Path, mode: LoadMode) -> Self {
+        TableLoader {
+            dir,
+            mode,
+            reports: Vec::new(),
+        }
+    }
+
+    fn db2<T>(
+        &mut self,
+        file: &'static str,
+        hotfixes: &Option<DbCache>,
+        from_db2_file: fn(Db2File, LoadMode) -> Loaded<T>,
+    ) -> Result<T, GameDataError> {
+        let bytes = read(self.dir, file)?;
+        let db2 = Db2File::parse(&bytes).map_err(|e| table_error(file, e))?;
+        let db2 = match hotfixes {
+            Some(cache) => db2.with_hotfixes(cache),
+            None => db2,
+        };
+
+        let (table, report) = from_db2_file(db2, self.mode).map_err(|e| table_error(file, e))?;
+        self.reports.push(report);
+        Ok(table)
+    }
+
+    fn csv<T>(
+        &mut self,
+        file: &'static str,
+        from_csv: fn(&str, LoadMode) -> Loaded<T>,
+    ) -> Result<T, GameDataError> {
+        let csv = String::from_utf8(read(self.dir, file)?).map_err(|e| GameDataError::Io {
+            file,
+            error: io::Error::new(io::ErrorKind::InvalidData, e),
+        })?;
+
+        let (table, report) = from_csv(&csv, self.mode).map_err(|e| table_error(file, e))?;
+        self.reports.push(report);
+        Ok(table)
+    }
+}
+
+/// An error while loading [`GameData`] from a directory or a cache.
+#[derive(Debug)]
+pub enum GameDataError {
+    /// A table's file could not be read.
+    Io {
+        /// The name of the file within the directory.
+        file: &'static str,
+        /// The underlying error.
+        error: io::Error,
+    },
+
+    /// A table, or the hotfix cache, could not be parsed.
+    Table {
+        /// The name of the file within the directory.
+        file: &'static str,
+        /// The underlying error.
+        error: Box<Error>,
+    },
+
+    /// A cache could not be read or written.
+    Cache(io::Error),
+
+    /// A cache is not a valid game data cache.
+    InvalidCache(&'static str),
+
+    /// The contents of a cache don't match its checksum.
+    ChecksumMismatch {
+        /// The checksum stored in the cache.
+        expected: u64,
+        /// The checksum of the contents.
+        found: u64,
+    },
+
+    /// A cache was built from a different client build than the one expected.
+    BuildMismatch {
+        /// The build which was expected.
+        expected: u32,
+        /// The build the cache was made from, if it is known.
+        found: Option<u32>,
+    },
+}
+
+impl fmt::Display for GameDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameDataError::Io { file, error } => write!(f, "unable to read {}: {}", file, error),
+            GameDataError::Table { file, error } => {
+                write!(f, "unable to load {}: {}", file, error)
+            }
+            GameDataError::Cache(e) => write!(f, "unable to access game data cache: {}", e),
+            GameDataError::InvalidCache(reason) => {
+                write!(f, "invalid game data cache: {}", reason)
+            }
+            GameDataError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "game data cache checksum mismatch: expected {:016x}, found {:016x}",
+                expected, found
+            ),
+            GameDataError::BuildMismatch {
+                expected,
+                found: Some(found),
+            } => write!(
+                f,
+                "game data cache is for build {}, expected build {}",
+                found, expected
+            ),
+            GameDataError::BuildMismatch {
+                expected,
+                found: None,
+            } => write!(
+                f,
+                "game data cache has no build, expected build {}",
+                expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GameDataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GameDataError::Io { error, .. } => Some(error),
+            GameDataError::Table { error, .. } => Some(error.as_ref()),
+            GameDataError::Cache(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, file: &str, contents: &str) {
+        fs::write(dir.join(file), contents).unwrap();
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "etherealpost-game-data-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_csv_tables(dir: &Path, item_effects: &str) {
+        write(
+            dir,
+            "item.csv",
+            "ID,ClassID,SubclassID\n11826,15,2\n19019,2,7\n44822,15,2\n",
+        );
+        write(dir, "itemeffect.csv", item_effects);
+        write(dir, "battlepetspecies.csv", "ID,SummonSpellID\n85,15048\n");
+        write(dir, "itemsparse.csv", "ID,ItemLevel\n19019,80\n");
+        write(
+            dir,
+            "itembonus.csv",
+            "ID,Value[0],Value[1],Value[2],Value[3],ParentItemBonusListID,Type,OrderIndex\n",
+        );
+        write(
+            dir,
+            "curvepoint.csv",
+            "ID,Pos[0],Pos[1],PosPreSquish[0],PosPreSquish[1],CurveID,OrderIndex\n",
+        );
+    }
+
+    #[test]
+    fn joins_items_to_pets_through_spells() {
+        let dir = temp_dir("csv");
+        write_csv_tables(
+            &dir,
+            "ID,SpellID,TriggerType,ParentItemID\n1,15048,6,11826\n2,99999,6,44822\n",
+        );
+
+        let (game_data, reports) = GameData::from_csv_dir(&dir, LoadMode::Strict).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(Some(85), game_data.pet_species(&11826));
+        // teaches a spell which isn't a pet
+        assert_eq!(None, game_data.pet_species(&44822));
+        assert!(game_data.is_equippable(&19019));
+        assert!(!game_data.is_equippable(&11826));
+        assert_eq!(80, game_data.base_ilvl(&19019));
+        assert_eq!(1, game_data.base_ilvl(&11826));
+
+        assert_eq!(6, reports.len());
+        assert!(reports.iter().all(LoadReport::is_complete));
+    }
+
+    #[test]
+    fn bad_rows_fail_strict_loads_and_are_reported_by_lenient_ones() {
+        let dir = temp_dir("bad-rows");
+        write_csv_tables(
+            &dir,
+            "ID,SpellID,TriggerType,ParentItemID\n1,15048,6,11826\n2,oops,6,44822\n",
+        );
+
+        let strict = GameData::from_csv_dir(&dir, LoadMode::Strict)
+            .err()
+            .unwrap();
+        let lenient = GameData::from_csv_dir(&dir, LoadMode::Lenient);
+        fs::remove_dir_all(&dir).unwrap();
+
+        match strict {
+            GameDataError::Table { file, error } => {
+                assert_eq!("itemeffect.csv", file);
+                assert!(matches!(*error, Error::InvalidRow { line: 3, .. }));
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+
+        let (game_data, reports) = lenient.unwrap();
+        assert_eq!(Some(85), game_data.pet_species(&11826));
+        let report = reports.iter().find(|r| r.table == "ItemEffect").unwrap();
+        assert_eq!(1, report.rows_read);
+        assert_eq!(1, report.skipped.len());
+        assert_eq!(3, report.skipped[0].line);
+    }
+
+    #[test]
+    fn missing_tables_are_reported_by_name() {
+        let dir = temp_dir("missing");
+        let error = GameData::from_db2_dir(&dir, LoadMode::Strict)
+            .err()
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        match error {
+            GameDataError::Io { file, .. } => assert_eq!("Item.db2", file),
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+}
@@ -1 +1,5 @@
 pub mod data_tables;
+pub mod db2;
+pub mod game_data;
+
+pub use game_data::{GameData, GameDataError};
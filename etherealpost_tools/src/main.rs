@@ -1,3 +1,80 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use etherealpost::auctions::AuctionFile;
+use etherealpost::parse::ItemLevelTrace;
+use etherealpost::wow::data_tables::LoadMode;
+use etherealpost::wow::GameData;
+use etherealpost::Error;
+
+const USAGE: &str = "usage: etherealpost_tools explain-ilvl <game data> <auction file> <auction id>
+
+  <game data>     a game data cache file, or a directory of DB2 files or CSV exports
+  <auction file>  the JSON auction file from the Blizzard API
+  <auction id>    the ID of the auction to explain";
+
 fn main() {
-    println!("Hello, world!");
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = match args.as_slice() {
+        ["explain-ilvl", game_data, auction_file, auction_id] => match auction_id.parse() {
+            Ok(auction_id) => {
+                explain_ilvl(Path::new(game_data), Path::new(auction_file), auction_id)
+            }
+            Err(_) => usage(),
+        },
+        _ => usage(),
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn usage() -> Result<(), Error> {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+/// Prints how the item level of a single auction is resolved.
+fn explain_ilvl(game_data: &Path, auction_file: &Path, auction_id: u64) -> Result<(), Error> {
+    let game_data = load_game_data(game_data)?;
+    let auction_file = AuctionFile::from_json(&fs::read_to_string(auction_file)?)?;
+
+    match auction_file.auctions.iter().find(|a| a.id == auction_id) {
+        Some(auction) => {
+            print!("{}", ItemLevelTrace::for_item(&auction.item, &game_data));
+            Ok(())
+        }
+        None => {
+            eprintln!("error: there is no auction {} in the file", auction_id);
+            process::exit(1);
+        }
+    }
+}
+
+/// Loads game data from a cache file, or from a directory of DB2 files or CSV exports.
+fn load_game_data(path: &Path) -> Result<GameData, Error> {
+    if path.is_file() {
+        return Ok(GameData::load_cache(path, None)?);
+    }
+
+    let (game_data, reports) = if path.join("Item.db2").exists() {
+        GameData::from_db2_dir(path, LoadMode::Lenient)?
+    } else {
+        GameData::from_csv_dir(path, LoadMode::Lenient)?
+    };
+    for report in reports.iter().filter(|report| !report.is_complete()) {
+        eprintln!(
+            "warning: skipped {} rows of {}",
+            report.skipped.len(),
+            report.table
+        );
+    }
+
+    Ok(game_data)
 }